@@ -0,0 +1,218 @@
+//! Achievements, unlocked once and for good and kept in a local file next to the leaderboard.
+//! Each is checked from the events of the current run, and announced with a toast when it is unlocked.
+
+use std::fs;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combat::{enemy_death, DeathEvent},
+    enemy::{check_for_collisions, WallSlamEvent},
+    leaderboard::RunClock,
+    locale::Locale,
+    stats::RunStats,
+    toast::Toast,
+    waves::WaveClearedEvent,
+    FixedUpdate, GameState,
+};
+
+const ACHIEVEMENTS_PATH: &str = "achievements.ron";
+// Kills this long after a wall slam still count towards the slam
+const SLAM_KILL_WINDOW: f32 = 0.5;
+const SLAM_KILLS: usize = 3;
+const FLAWLESS_WAVE: u32 = 10;
+const CENTURION_KILLS: u32 = 100;
+
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::load())
+            .init_resource::<AchievementTracker>()
+            .add_event::<AchievementUnlockedEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_achievement_tracker))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(track_wall_slams.after(check_for_collisions))
+                    .with_system(check_slam_kills.after(enemy_death).after(track_wall_slams))
+                    .with_system(check_flawless_waves)
+                    .with_system(check_kill_count.after(enemy_death)),
+            )
+            .add_system(announce_achievements);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Achievement {
+    SlamTriple,
+    FlawlessWave,
+    Centurion,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 3] = [Achievement::SlamTriple, Achievement::FlawlessWave, Achievement::Centurion];
+
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Achievement::SlamTriple => "achievement.slam_triple",
+            Achievement::FlawlessWave => "achievement.flawless_wave",
+            Achievement::Centurion => "achievement.centurion",
+        }
+    }
+
+    pub fn description_key(self) -> &'static str {
+        match self {
+            Achievement::SlamTriple => "achievement.slam_triple.description",
+            Achievement::FlawlessWave => "achievement.flawless_wave.description",
+            Achievement::Centurion => "achievement.centurion.description",
+        }
+    }
+}
+
+// Sent the first time an achievement is unlocked, never again after that
+pub struct AchievementUnlockedEvent(pub Achievement);
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Achievements {
+    unlocked: Vec<Achievement>,
+}
+
+impl Achievements {
+    fn load() -> Achievements {
+        let contents = match fs::read_to_string(ACHIEVEMENTS_PATH) {
+            Ok(contents) => contents,
+            Err(_) => return Achievements::default(),
+        };
+
+        match ron::from_str(&contents) {
+            Ok(achievements) => achievements,
+            Err(error) => {
+                warn!("Ignoring unreadable achievements {}: {}", ACHIEVEMENTS_PATH, error);
+                Achievements::default()
+            }
+        }
+    }
+
+    fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(ACHIEVEMENTS_PATH, contents).map_err(|error| error.to_string()));
+
+        if let Err(error) = result {
+            error!("Failed to write achievements {}: {}", ACHIEVEMENTS_PATH, error);
+        }
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    // Writes the file straight away, so an unlock survives the game being killed
+    fn unlock(&mut self, achievement: Achievement, unlocked_events: &mut EventWriter<AchievementUnlockedEvent>) {
+        if self.is_unlocked(achievement) {
+            return;
+        }
+
+        self.unlocked.push(achievement);
+        self.write();
+        unlocked_events.send(AchievementUnlockedEvent(achievement));
+    }
+}
+
+// Progress towards the achievements within the current run
+#[derive(Default)]
+struct AchievementTracker {
+    // When each enemy last hit a wall, by the run clock
+    slammed: HashMap<Entity, f32>,
+    // When recent kills of slammed enemies happened
+    slam_kills: Vec<f32>,
+    // Damage taken before the current wave started
+    damage_before_wave: i32,
+}
+
+fn reset_achievement_tracker(mut tracker: ResMut<AchievementTracker>)
+{
+    *tracker = AchievementTracker::default();
+}
+
+fn track_wall_slams(
+    clock: Res<RunClock>,
+    mut wall_slam_events: EventReader<WallSlamEvent>,
+    mut tracker: ResMut<AchievementTracker>,
+)
+{
+    for slam in wall_slam_events.iter() {
+        tracker.slammed.insert(slam.enemy, clock.0);
+    }
+}
+
+fn check_slam_kills(
+    clock: Res<RunClock>,
+    mut death_events: EventReader<DeathEvent>,
+    mut tracker: ResMut<AchievementTracker>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+)
+{
+    let now = clock.0;
+    for death in death_events.iter() {
+        if let Some(slammed_at) = tracker.slammed.remove(&death.entity) {
+            if now - slammed_at <= SLAM_KILL_WINDOW {
+                tracker.slam_kills.push(now);
+            }
+        }
+    }
+
+    // Slams too long ago no longer matter, whether or not their enemy died
+    tracker.slammed.retain(|_, slammed_at| now - *slammed_at <= SLAM_KILL_WINDOW);
+    tracker.slam_kills.retain(|killed_at| now - *killed_at <= SLAM_KILL_WINDOW);
+
+    if tracker.slam_kills.len() >= SLAM_KILLS {
+        achievements.unlock(Achievement::SlamTriple, &mut unlocked_events);
+    }
+}
+
+fn check_flawless_waves(
+    run_stats: Res<RunStats>,
+    mut wave_cleared_events: EventReader<WaveClearedEvent>,
+    mut tracker: ResMut<AchievementTracker>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+)
+{
+    for cleared in wave_cleared_events.iter() {
+        if cleared.0 >= FLAWLESS_WAVE && run_stats.damage_taken == tracker.damage_before_wave {
+            achievements.unlock(Achievement::FlawlessWave, &mut unlocked_events);
+        }
+        tracker.damage_before_wave = run_stats.damage_taken;
+    }
+}
+
+fn check_kill_count(
+    run_stats: Res<RunStats>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+)
+{
+    if run_stats.total_kills() >= CENTURION_KILLS {
+        achievements.unlock(Achievement::Centurion, &mut unlocked_events);
+    }
+}
+
+// Unlocks can land right before the upgrade or game over screen, so this runs in every state
+fn announce_achievements(
+    mut unlocked_events: EventReader<AchievementUnlockedEvent>,
+    locale: Res<Locale>,
+    mut toasts: EventWriter<Toast>,
+)
+{
+    for unlocked in unlocked_events.iter() {
+        toasts.send(
+            Toast::new("achievement.unlocked")
+                .with_arg("achievement", locale.get(unlocked.0.title_key()))
+                .with_body(unlocked.0.description_key()),
+        );
+    }
+}
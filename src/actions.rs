@@ -0,0 +1,524 @@
+//! Turns keyboard, mouse and gamepad input into the logical actions gameplay systems care about.
+//! Each player has their own bindings and actions, the `ActionState` resource mirrors the first player's.
+
+use std::fs;
+
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    locale::Locale,
+    player::{Player, PlayerCount},
+    touch::TouchControls,
+    ui::{menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE},
+    GameState, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+};
+
+// Stick deflection below this is treated as the stick resting
+const STICK_DEADZONE: f32 = 0.2;
+
+const KEYBINDINGS_PATH: &str = "keybindings.ron";
+// The second player can only be rebound by editing this file for now
+const SECOND_PLAYER_KEYBINDINGS_PATH: &str = "keybindings_p2.ron";
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .insert_resource(InputMap::load(KEYBINDINGS_PATH, InputMap::default()))
+            .insert_resource(SecondPlayerInputMap(InputMap::load(
+                SECOND_PLAYER_KEYBINDINGS_PATH,
+                InputMap::second_player(),
+            )))
+            .init_resource::<Rebinding>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_action_state.after(InputSystem))
+            .add_system_set(SystemSet::on_enter(GameState::Controls).with_system(setup_controls_screen))
+            .add_system_set(
+                SystemSet::on_update(GameState::Controls)
+                    .with_system(controls_menu)
+                    .with_system(update_controls_text.after(controls_menu)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Pull,
+    Push,
+    Attack,
+    // Picks the weapon `Attack` fires
+    SelectPulse,
+    SelectRailgun,
+    SelectMineLayer,
+    // Switches the magnet between its full circle and a cone aimed at the cursor
+    ToggleMagnetMode,
+    // Locks onto the next enemy in sight, see `lock_on`
+    CycleTarget,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Dash,
+        Action::Pull,
+        Action::Push,
+        Action::Attack,
+        Action::SelectPulse,
+        Action::SelectRailgun,
+        Action::SelectMineLayer,
+        Action::ToggleMagnetMode,
+        Action::CycleTarget,
+        Action::Pause,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "action.move_up",
+            Action::MoveDown => "action.move_down",
+            Action::MoveLeft => "action.move_left",
+            Action::MoveRight => "action.move_right",
+            Action::Dash => "action.dash",
+            Action::Pull => "action.pull",
+            Action::Push => "action.push",
+            Action::Attack => "action.attack",
+            Action::SelectPulse => "action.select_pulse",
+            Action::SelectRailgun => "action.select_railgun",
+            Action::SelectMineLayer => "action.select_mine_layer",
+            Action::ToggleMagnetMode => "action.magnet_mode",
+            Action::CycleTarget => "action.cycle_target",
+            Action::Pause => "action.pause",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    fn label(&self, locale: &Locale) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => locale.format("binding.mouse", &[("button", format!("{:?}", button))]),
+            Binding::Gamepad(button) => locale.format("binding.gamepad", &[("button", format!("{:?}", button))]),
+        }
+    }
+}
+
+// Which keys and buttons trigger each action, persisted to `KEYBINDINGS_PATH`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let mut bindings = HashMap::default();
+        // The arrow keys are left for the first player once the second one takes over WASD
+        bindings.insert(Action::MoveUp, vec![Binding::Key(KeyCode::W), Binding::Key(KeyCode::Up)]);
+        bindings.insert(Action::MoveDown, vec![Binding::Key(KeyCode::S), Binding::Key(KeyCode::Down)]);
+        bindings.insert(Action::MoveLeft, vec![Binding::Key(KeyCode::A), Binding::Key(KeyCode::Left)]);
+        bindings.insert(Action::MoveRight, vec![Binding::Key(KeyCode::D), Binding::Key(KeyCode::Right)]);
+        bindings.insert(
+            Action::Dash,
+            vec![Binding::Key(KeyCode::LShift), Binding::Gamepad(GamepadButtonType::East)],
+        );
+        bindings.insert(
+            Action::Pull,
+            vec![Binding::Key(KeyCode::Q), Binding::Gamepad(GamepadButtonType::LeftTrigger2)],
+        );
+        bindings.insert(
+            Action::Push,
+            vec![Binding::Key(KeyCode::E), Binding::Gamepad(GamepadButtonType::RightTrigger2)],
+        );
+        bindings.insert(
+            Action::Attack,
+            vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+        );
+        bindings.insert(
+            Action::SelectPulse,
+            vec![Binding::Key(KeyCode::Key1), Binding::Gamepad(GamepadButtonType::DPadLeft)],
+        );
+        bindings.insert(
+            Action::SelectRailgun,
+            vec![Binding::Key(KeyCode::Key2), Binding::Gamepad(GamepadButtonType::DPadUp)],
+        );
+        bindings.insert(
+            Action::SelectMineLayer,
+            vec![Binding::Key(KeyCode::Key3), Binding::Gamepad(GamepadButtonType::DPadRight)],
+        );
+        bindings.insert(
+            Action::ToggleMagnetMode,
+            vec![Binding::Key(KeyCode::M), Binding::Gamepad(GamepadButtonType::North)],
+        );
+        bindings.insert(
+            Action::CycleTarget,
+            vec![Binding::Key(KeyCode::Tab), Binding::Gamepad(GamepadButtonType::RightThumb)],
+        );
+        bindings.insert(
+            Action::Pause,
+            vec![Binding::Key(KeyCode::P), Binding::Gamepad(GamepadButtonType::Start)],
+        );
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    // The second player's defaults, sharing the keyboard with the first. Mode toggling, targeting and pausing
+    // are left to the first player.
+    fn second_player() -> Self {
+        let mut bindings = HashMap::default();
+        bindings.insert(Action::MoveUp, vec![Binding::Key(KeyCode::W)]);
+        bindings.insert(Action::MoveDown, vec![Binding::Key(KeyCode::S)]);
+        bindings.insert(Action::MoveLeft, vec![Binding::Key(KeyCode::A)]);
+        bindings.insert(Action::MoveRight, vec![Binding::Key(KeyCode::D)]);
+        bindings.insert(
+            Action::Dash,
+            vec![Binding::Key(KeyCode::C), Binding::Gamepad(GamepadButtonType::East)],
+        );
+        bindings.insert(
+            Action::Pull,
+            vec![Binding::Key(KeyCode::F), Binding::Gamepad(GamepadButtonType::LeftTrigger2)],
+        );
+        bindings.insert(
+            Action::Push,
+            vec![Binding::Key(KeyCode::G), Binding::Gamepad(GamepadButtonType::RightTrigger2)],
+        );
+        bindings.insert(
+            Action::Attack,
+            vec![Binding::Key(KeyCode::R), Binding::Gamepad(GamepadButtonType::South)],
+        );
+        bindings.insert(
+            Action::SelectPulse,
+            vec![Binding::Key(KeyCode::Key8), Binding::Gamepad(GamepadButtonType::DPadLeft)],
+        );
+        bindings.insert(
+            Action::SelectRailgun,
+            vec![Binding::Key(KeyCode::Key9), Binding::Gamepad(GamepadButtonType::DPadUp)],
+        );
+        bindings.insert(
+            Action::SelectMineLayer,
+            vec![Binding::Key(KeyCode::Key0), Binding::Gamepad(GamepadButtonType::DPadRight)],
+        );
+        Self { bindings }
+    }
+
+    // The first binding of an action, as shown to the player
+    pub fn label(&self, action: Action, locale: &Locale) -> String {
+        self.bindings
+            .get(&action)
+            .and_then(|bindings| bindings.first())
+            .map_or_else(|| locale.get("binding.unbound").to_string(), |binding| binding.label(locale))
+    }
+
+    fn load(path: &str, defaults: InputMap) -> InputMap {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return defaults,
+        };
+
+        match ron::from_str::<InputMap>(&contents) {
+            Ok(mut input_map) => {
+                // Actions added since the file was written keep their default bindings
+                for (action, bindings) in defaults.bindings {
+                    input_map.bindings.entry(action).or_insert(bindings);
+                }
+                input_map
+            }
+            Err(error) => {
+                warn!("Ignoring unreadable keybindings {}: {}", path, error);
+                defaults
+            }
+        }
+    }
+
+    fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(KEYBINDINGS_PATH, contents).map_err(|error| error.to_string()));
+
+        if let Err(error) = result {
+            error!("Failed to write keybindings {}: {}", KEYBINDINGS_PATH, error);
+        }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // Replaces the keyboard and mouse bindings of an action, gamepad bindings are kept
+    fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|existing| matches!(existing, Binding::Gamepad(_)));
+        bindings.insert(0, binding);
+    }
+}
+
+pub struct SecondPlayerInputMap(pub InputMap);
+
+// The actions held this frame, whichever device they came from.
+// Also put on every player, holding that player's own actions.
+#[derive(Component, Clone, Default)]
+pub struct ActionState {
+    movement: Vec2,
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn movement(&self) -> Vec2 {
+        self.movement
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+// Every device input the actions are read from
+struct Inputs<'a> {
+    keyboard: &'a Input<KeyCode>,
+    mouse: &'a Input<MouseButton>,
+    gamepad_buttons: &'a Input<GamepadButton>,
+    gamepad_axes: &'a Axis<GamepadAxis>,
+}
+
+pub fn update_action_state(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    input_map: Res<InputMap>,
+    second_input_map: Res<SecondPlayerInputMap>,
+    player_count: Res<PlayerCount>,
+    touch: Res<TouchControls>,
+    mut actions: ResMut<ActionState>,
+    mut player_query: Query<(&Player, &mut ActionState)>,
+)
+{
+    let inputs = Inputs {
+        keyboard: &keyboard_input,
+        mouse: &mouse_input,
+        gamepad_buttons: &gamepad_buttons,
+        gamepad_axes: &gamepad_axes,
+    };
+    let gamepads: Vec<Gamepad> = gamepads.iter().copied().collect();
+
+    // In co-op each player gets a gamepad of their own, and the keys bound for the second player are theirs alone
+    let coop = player_count.0 > 1;
+    let (first_gamepads, second_gamepads) = if coop {
+        (&gamepads[..gamepads.len().min(1)], gamepads.get(1..).unwrap_or_default())
+    } else {
+        (&gamepads[..], &[][..])
+    };
+    let claimed = coop.then(|| &second_input_map.0);
+
+    let first = read_actions(&inputs, &input_map, claimed, first_gamepads, Some(&touch), &actions);
+    *actions = first;
+
+    for (player, mut player_actions) in &mut player_query {
+        let next = match player.0 {
+            0 => actions.clone(),
+            _ => read_actions(&inputs, &second_input_map.0, None, second_gamepads, None, &player_actions),
+        };
+        *player_actions = next;
+    }
+}
+
+// The actions held through `input_map`, ignoring keys `claimed` by another player. The on-screen `touch` controls
+// count for whoever they are passed to
+fn read_actions(
+    inputs: &Inputs,
+    input_map: &InputMap,
+    claimed: Option<&InputMap>,
+    gamepads: &[Gamepad],
+    touch: Option<&TouchControls>,
+    previous: &ActionState,
+) -> ActionState
+{
+    let is_claimed = |binding: &Binding| {
+        matches!(binding, Binding::Key(_) | Binding::Mouse(_))
+            && claimed.map_or(false, |claimed| {
+                Action::ALL.into_iter().any(|action| claimed.bindings(action).contains(binding))
+            })
+    };
+    let binding_pressed = |binding: &Binding| {
+        !is_claimed(binding)
+            && match binding {
+                Binding::Key(key) => inputs.keyboard.pressed(*key),
+                Binding::Mouse(button) => inputs.mouse.pressed(*button),
+                Binding::Gamepad(button) => gamepads.iter().any(|gamepad| {
+                    inputs.gamepad_buttons.pressed(GamepadButton::new(*gamepad, *button))
+                }),
+            }
+    };
+
+    let mut pressed: HashSet<Action> = Action::ALL
+        .into_iter()
+        .filter(|action| input_map.bindings(*action).iter().any(binding_pressed))
+        .collect();
+    let touch = touch.filter(|touch| touch.enabled);
+    if let Some(touch) = touch {
+        pressed.extend(touch.pressed.iter().copied());
+    }
+
+    let mut movement = Vec2::ZERO;
+    if pressed.contains(&Action::MoveLeft) {
+        movement.x -= 1.0;
+    }
+    if pressed.contains(&Action::MoveRight) {
+        movement.x += 1.0;
+    }
+    if pressed.contains(&Action::MoveUp) {
+        movement.y += 1.0;
+    }
+    if pressed.contains(&Action::MoveDown) {
+        movement.y -= 1.0;
+    }
+
+    // Only fall back to the stick while no movement keys are held
+    if movement == Vec2::ZERO {
+        for gamepad in gamepads {
+            let stick = Vec2::new(
+                inputs.gamepad_axes.get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0),
+                inputs.gamepad_axes.get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0),
+            );
+            if stick.length() > STICK_DEADZONE {
+                movement = stick.clamp_length_max(1.0);
+                break;
+            }
+        }
+    }
+    if movement == Vec2::ZERO {
+        movement = touch.map_or(Vec2::ZERO, |touch| touch.movement);
+    }
+
+    ActionState {
+        movement,
+        just_pressed: pressed.difference(&previous.pressed).copied().collect(),
+        pressed,
+    }
+}
+
+// Cursor and listening state of the controls menu
+#[derive(Default)]
+struct Rebinding {
+    selected: usize,
+    listening: bool,
+}
+
+#[derive(Component)]
+struct ControlsEntryText(usize);
+
+fn setup_controls_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut rebinding: ResMut<Rebinding>,
+)
+{
+    *rebinding = Rebinding::default();
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::Controls), Color::NONE, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("controls.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        for index in 0..Action::ALL.len() {
+            parent
+                .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, WALL_COLOR))
+                .insert(ControlsEntryText(index));
+        }
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            locale.get("controls.hint"),
+            MENU_TEXT_FONT_SIZE,
+            WALL_COLOR,
+        ));
+    });
+}
+
+fn controls_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if rebinding.listening {
+        let binding = keyboard_input
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or_else(|| mouse_input.get_just_pressed().next().map(|button| Binding::Mouse(*button)));
+
+        if let Some(binding) = binding {
+            input_map.rebind(Action::ALL[rebinding.selected], binding);
+            input_map.write();
+            rebinding.listening = false;
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        let _ = state.set(GameState::MainMenu);
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        rebinding.selected = (rebinding.selected + Action::ALL.len() - 1) % Action::ALL.len();
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        rebinding.selected = (rebinding.selected + 1) % Action::ALL.len();
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        rebinding.listening = true;
+    }
+}
+
+fn update_controls_text(
+    rebinding: Res<Rebinding>,
+    input_map: Res<InputMap>,
+    locale: Res<Locale>,
+    mut query: Query<(&mut Text, &ControlsEntryText)>,
+)
+{
+    for (mut text, entry) in &mut query {
+        let action = Action::ALL[entry.0];
+        let selected = entry.0 == rebinding.selected;
+
+        let bindings = if selected && rebinding.listening {
+            locale.get("controls.listening").to_string()
+        } else {
+            input_map
+                .bindings(action)
+                .iter()
+                .map(|binding| binding.label(&locale))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        text.sections[0].value = format!("{}: {}", locale.get(action.key()), bindings);
+        text.sections[0].style.color = if selected { SCORE_COLOR } else { WALL_COLOR };
+    }
+}
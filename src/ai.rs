@@ -0,0 +1,304 @@
+//! Enemy behavior as a small state machine: every enemy carries an `AiState`, moved along by shared transitions
+//! (wander until a player is spotted, chase, attack once in range, flee when worn down), and steers by its state.
+//! Only how they chase and attack depends on their `EnemyKind`. Enemies keep some distance from each other throughout.
+//! Every attack is telegraphed: the enemy stops and pulses for a moment before it can hurt anyone.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::Arena,
+    combat::Hp,
+    enemy::{Enemy, EnemyKind, Speed, Tint},
+    magnet::magnet,
+    physics::{Forces, Mass, Velocity},
+    player::{nearest_player, Player},
+    spatial::SpatialHash,
+    status::StatusEffects,
+    tween::{lerp_color, SpriteSize, Tween},
+    FixedUpdate, TIME_STEP,
+};
+
+// How quickly an enemy can change its own velocity, in units per second squared.
+// Kept low enough that the magnet and knockback can still fling enemies around.
+const STEERING_FORCE: f32 = 300.0;
+// Enemies closer than this push away from each other
+const SEPARATION_RADIUS: f32 = 40.0;
+const SEPARATION_WEIGHT: f32 = 1.5;
+// Enemies closer than this to an obstacle steer around it
+const OBSTACLE_AVOID_RADIUS: f32 = 50.0;
+const OBSTACLE_AVOID_WEIGHT: f32 = 2.0;
+// Distance orbiters try to keep from the player while circling
+const ORBIT_RADIUS: f32 = 180.0;
+// How strongly orbiters correct toward their orbit compared to circling along it
+const ORBIT_CORRECTION: f32 = 0.5;
+// Distance shooters try to keep from the player, they back off when it gets closer
+pub const SHOOTER_RANGE: f32 = 250.0;
+// Below this fraction of their health enemies of every kind flee from the player, and have to be pulled in to finish
+const FLEE_HEALTH_FRACTION: f32 = 0.3;
+// Wandering enemies spot a player this close, if nothing is in the way
+const DETECTION_RADIUS: f32 = 600.0;
+// Chasing enemies give up on a player this far away
+const LOSE_RADIUS: f32 = 900.0;
+// Attacking enemies only fall back to chasing once this much further out than their attack range
+const ATTACK_HYSTERESIS: f32 = 1.25;
+// Wandering enemies drift around the middle of the arena at this distance, at a fraction of their speed
+const WANDER_RADIUS: f32 = 300.0;
+const WANDER_SPEED_SCALE: f32 = 0.5;
+// Seconds an enemy winds up before an attack, pulsing toward this color and size meanwhile
+const WIND_UP_DURATION: f32 = 0.4;
+const WIND_UP_COLOR: Color = Color::rgb(1.0, 0.2, 0.2);
+const WIND_UP_SCALE: f32 = 1.3;
+const WIND_UP_PULSES: f32 = 2.0;
+
+pub struct EnemyAiPlugin;
+
+impl Plugin for EnemyAiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(update_ai_states.before(steer_enemies))
+                .with_system(tick_telegraphs.after(update_ai_states).before(steer_enemies))
+                .with_system(steer_enemies.before(magnet)),
+        );
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AiState {
+    // No player in sight, drifting around
+    #[default]
+    Wander,
+    // Closing in on the nearest player
+    Chase,
+    // Within range of the nearest player, going for it
+    Attack,
+    // Worn down, running away from the nearest player
+    Flee,
+}
+
+// An attack being wound up. Contact damage waits for it to finish, shooters fire as it does.
+#[derive(Component)]
+pub struct Telegraph(pub Timer);
+
+impl Default for Telegraph {
+    fn default() -> Self {
+        Self(Timer::from_seconds(WIND_UP_DURATION, false))
+    }
+}
+
+impl Telegraph {
+    pub fn winding_up(&self) -> bool {
+        !self.0.finished()
+    }
+}
+
+// What an enemy knows about its surroundings when deciding on its next state
+pub struct AiContext {
+    pub distance: f32,
+    pub line_of_sight: bool,
+    pub health_fraction: f32,
+    pub attack_range: f32,
+}
+
+impl AiState {
+    // Transitions shared by every kind, only the attack range differs between them
+    pub fn next(self, context: &AiContext) -> AiState {
+        // Once fleeing there is no turning back
+        if self == AiState::Flee || context.health_fraction < FLEE_HEALTH_FRACTION {
+            return AiState::Flee;
+        }
+
+        match self {
+            AiState::Wander if context.distance < DETECTION_RADIUS && context.line_of_sight => AiState::Chase,
+            AiState::Chase if context.distance > LOSE_RADIUS => AiState::Wander,
+            AiState::Chase if context.distance < context.attack_range => AiState::Attack,
+            AiState::Attack if context.distance > context.attack_range * ATTACK_HYSTERESIS => AiState::Chase,
+            state => state,
+        }
+    }
+}
+
+// How close a player has to be for an enemy of this kind to start attacking
+fn attack_range(kind: EnemyKind) -> f32
+{
+    match kind {
+        EnemyKind::Chaser | EnemyKind::Heavy => 150.0,
+        // Orbiters keep circling unless something knocks them well inside their orbit
+        EnemyKind::Orbiter => ORBIT_RADIUS * 0.5,
+        // Shooters open fire from twice the distance they like to keep
+        EnemyKind::Shooter => SHOOTER_RANGE * 2.0,
+    }
+}
+
+fn update_ai_states(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    arena: Res<Arena>,
+    mut enemy_query: Query<(Entity, &Transform, &EnemyKind, &Hp, &mut AiState), (With<Enemy>, Without<Player>)>,
+)
+{
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+
+    for (entity, transform, kind, hp, mut state) in &mut enemy_query {
+        let position = transform.translation.truncate();
+        let player_position = match nearest_player(players.iter().copied(), position) {
+            Some(player_position) => player_position,
+            None => return,
+        };
+
+        let context = AiContext {
+            distance: position.distance(player_position),
+            line_of_sight: arena.line_of_sight(position, player_position),
+            health_fraction: hp.current as f32 / hp.max as f32,
+            attack_range: attack_range(*kind),
+        };
+        let next = state.next(&context);
+        if next != *state {
+            // Shooters wind up each shot on their own instead
+            if next == AiState::Attack && *kind != EnemyKind::Shooter {
+                commands.entity(entity).insert(Telegraph::default());
+            }
+            *state = next;
+        }
+    }
+}
+
+// Pulses enemies winding up an attack, and puts them back to normal once done or no longer attacking.
+// An enemy still popping in is sized by its tween until that is done, only its color pulses meanwhile.
+pub fn tick_telegraphs(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut Telegraph,
+        &AiState,
+        &Tint,
+        &Hp,
+        &mut TextureAtlasSprite,
+        Option<&Tween<SpriteSize>>,
+    )>,
+)
+{
+    for (entity, mut telegraph, state, tint, hp, mut sprite, popping_in) in &mut query {
+        telegraph.0.tick(Duration::from_secs_f32(TIME_STEP));
+
+        // Same as after a hit flash, damaged enemies fade out as they lose health
+        let mut color = tint.0;
+        color.set_a(hp.current as f32 / hp.max as f32);
+
+        if !telegraph.winding_up() || *state != AiState::Attack {
+            commands.entity(entity).remove::<Telegraph>();
+            sprite.color = color;
+            if popping_in.is_none() {
+                sprite.custom_size = Some(Vec2::ONE);
+            }
+            continue;
+        }
+
+        let pulse = 0.5 - (telegraph.0.percent() * WIND_UP_PULSES * TAU).cos() / 2.0;
+        sprite.color = lerp_color(color, WIND_UP_COLOR, pulse);
+        if popping_in.is_none() {
+            sprite.custom_size = Some(Vec2::splat(1.0 + (WIND_UP_SCALE - 1.0) * telegraph.0.percent()));
+        }
+    }
+}
+
+// Steers every enemy toward its state's heading in a single pass, keeping them apart from each other and obstacles.
+// Neighbours come from the spatial hash, so this stays cheap with hundreds of enemies around.
+fn steer_enemies(
+    player_query: Query<&Transform, With<Player>>,
+    arena: Res<Arena>,
+    spatial_hash: Res<SpatialHash>,
+    mut enemy_query: Query<
+        (Entity, &Transform, &Velocity, &mut Forces, &Mass, &Speed, &EnemyKind, &StatusEffects, &AiState, Option<&Telegraph>),
+        (With<Enemy>, Without<Player>),
+    >,
+)
+{
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+    if players.is_empty() {
+        return;
+    }
+
+    for (entity, transform, velocity, mut forces, mass, speed, kind, effects, state, telegraph) in enemy_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let player_position = nearest_player(players.iter().copied(), position).unwrap_or(position);
+        let heading = match (state, kind) {
+            (AiState::Wander, _) => orbit(position, Vec2::ZERO, WANDER_RADIUS),
+            (AiState::Flee, _) => flee(position, player_position),
+            (AiState::Chase, EnemyKind::Orbiter) => orbit(position, player_position, ORBIT_RADIUS),
+            (AiState::Chase | AiState::Attack, EnemyKind::Shooter) => keep_range(position, player_position),
+            // Orbiters break off circling to dive in once attacking
+            (AiState::Chase | AiState::Attack, _) => chase(position, player_position),
+        };
+        // Winding up an attack roots the enemy to the spot
+        let state_speed = if telegraph.map_or(false, Telegraph::winding_up) {
+            0.0
+        } else if *state == AiState::Wander {
+            WANDER_SPEED_SCALE
+        } else {
+            1.0
+        };
+
+        let mut separation = Vec2::ZERO;
+        for (other, other_position) in spatial_hash.neighbours(position, SEPARATION_RADIUS) {
+            if other == entity {
+                continue;
+            }
+
+            let offset = position - other_position;
+            let distance = offset.length();
+            if distance > 0.0 {
+                // The closer the neighbour, the harder we push away from it
+                separation += offset / distance * (1.0 - distance / SEPARATION_RADIUS);
+            }
+        }
+
+        let avoidance = arena.avoidance(position, OBSTACLE_AVOID_RADIUS);
+
+        let desired = (heading + separation * SEPARATION_WEIGHT + avoidance * OBSTACLE_AVOID_WEIGHT)
+            .normalize_or_zero()
+            * speed.0
+            * state_speed
+            * effects.speed_scale();
+        let steering = ((desired - velocity.0) / TIME_STEP).clamp_length_max(STEERING_FORCE);
+        forces.add_force(steering * mass.0);
+    }
+}
+
+// Heads straight for the nearest player
+fn chase(position: Vec2, player_position: Vec2) -> Vec2
+{
+    (player_position - position).normalize_or_zero()
+}
+
+// Badly hurt enemies run straight away from the nearest player
+fn flee(position: Vec2, player_position: Vec2) -> Vec2
+{
+    (position - player_position).normalize_or_zero()
+}
+
+// Circles `center`, drifting toward the given distance from it
+fn orbit(position: Vec2, center: Vec2, radius: f32) -> Vec2
+{
+    let offset = position - center;
+    let distance = offset.length();
+    let outward = offset.normalize_or_zero();
+    let tangent = outward.perp();
+    let correction = (radius - distance) / radius * ORBIT_CORRECTION;
+    (tangent + outward * correction).normalize_or_zero()
+}
+
+// Shooters hover around their firing range
+fn keep_range(position: Vec2, player_position: Vec2) -> Vec2
+{
+    let offset = player_position - position;
+    let toward = offset.normalize_or_zero();
+    if offset.length() > SHOOTER_RANGE {
+        toward
+    } else {
+        -toward
+    }
+}
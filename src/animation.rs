@@ -0,0 +1,82 @@
+//! Frame by frame animation of sprites from a texture atlas. An `AnimatedSprite` plays a run of frames
+//! from its sheet at a fixed rate, once or over and over, and can be switched to another run at any time.
+
+use bevy::prelude::*;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(animate_sprites);
+    }
+}
+
+#[derive(Component)]
+pub struct AnimatedSprite {
+    // Index of the first frame of the run in the atlas, and how many frames follow it
+    first: usize,
+    frames: usize,
+    looping: bool,
+    // Frame within the run currently shown
+    frame: usize,
+    timer: Timer,
+}
+
+impl AnimatedSprite {
+    pub fn new(first: usize, frames: usize, fps: f32, looping: bool) -> Self {
+        Self {
+            first,
+            frames: frames.max(1),
+            looping,
+            frame: 0,
+            timer: Timer::from_seconds(1.0 / fps, true),
+        }
+    }
+
+    // Starts partway through, so sprites spawned together don't move in lockstep
+    pub fn with_frame(mut self, frame: usize) -> Self {
+        self.frame = frame % self.frames;
+        self
+    }
+
+    // Switches to another run of frames, unless it is already playing
+    pub fn play(&mut self, first: usize, frames: usize, fps: f32) {
+        if self.first == first && self.frames == frames {
+            return;
+        }
+        self.first = first;
+        self.frames = frames.max(1);
+        self.frame = 0;
+        self.timer = Timer::from_seconds(1.0 / fps, true);
+    }
+
+    // Plays the current run again from its first frame
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.timer.reset();
+    }
+
+    // A run that doesn't loop stops on its last frame
+    pub fn finished(&self) -> bool {
+        !self.looping && self.frame + 1 >= self.frames
+    }
+
+    pub fn index(&self) -> usize {
+        self.first + self.frame
+    }
+}
+
+pub fn animate_sprites(time: Res<Time>, mut query: Query<(&mut AnimatedSprite, &mut TextureAtlasSprite)>)
+{
+    for (mut animation, mut sprite) in &mut query {
+        let advanced = animation.timer.tick(time.delta()).times_finished_this_tick() as usize;
+        if advanced > 0 && !animation.finished() {
+            animation.frame = if animation.looping {
+                (animation.frame + advanced) % animation.frames
+            } else {
+                (animation.frame + advanced).min(animation.frames - 1)
+            };
+        }
+        sprite.index = animation.index();
+    }
+}
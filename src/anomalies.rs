@@ -0,0 +1,101 @@
+//! Magnetic anomalies laid out with the arena: fixed spots that keep pulling in, or pushing away,
+//! everything magnetic around them with the same field as the player's magnet.
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    config::GameConfig,
+    magnet::{field_force, magnet, MagnetImmune, Magnetic, Polarity},
+    physics::{integrate_forces, Forces},
+    player::Player,
+    tween::{Ease, Scale, Tween, TweenMode},
+    FixedUpdate, GameState, LevelEntity,
+};
+
+pub const ANOMALY_RADIUS: f32 = 220.0;
+// A fraction of what the player's magnet starts out with, so the player can still pull things clear
+const ANOMALY_FORCE: f32 = 30.0;
+const ANOMALY_CORE_RADIUS: f32 = 10.0;
+const ANOMALY_RING_THICKNESS: f32 = 2.0;
+const ATTRACT_COLOR: Color = Color::rgba(0.7, 0.4, 1.0, 0.35);
+const REPEL_COLOR: Color = Color::rgba(1.0, 0.7, 0.3, 0.35);
+// The core throbs up to this scale and back
+const ANOMALY_PULSE_SCALE: f32 = 1.5;
+const ANOMALY_PULSE_DURATION: f32 = 0.8;
+
+pub struct AnomalyPlugin;
+
+impl Plugin for AnomalyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(anomaly_fields.after(magnet).before(integrate_forces)),
+        );
+    }
+}
+
+#[derive(Component, Clone, Copy, Deserialize)]
+pub struct Anomaly {
+    pub center: Vec2,
+    // Pushes everything away rather than drawing it in
+    pub repels: bool,
+}
+
+impl Anomaly {
+    fn color(&self) -> Color {
+        if self.repels { REPEL_COLOR } else { ATTRACT_COLOR }
+    }
+}
+
+// The anomaly's reach as a faint ring, with a pulsing core in the middle
+pub fn spawn_anomaly(commands: &mut Commands, anomaly: Anomaly)
+{
+    let color = anomaly.color();
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Circle { radius: ANOMALY_RADIUS, center: Vec2::ZERO },
+            DrawMode::Stroke(StrokeMode::new(color, ANOMALY_RING_THICKNESS)),
+            Transform::from_translation(anomaly.center.extend(-0.5)),
+        ))
+        .insert(anomaly)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .with_children(|parent| {
+            let mut core_color = color;
+            core_color.set_a(1.0);
+            parent
+                .spawn_bundle(GeometryBuilder::build_as(
+                    &shapes::Circle { radius: ANOMALY_CORE_RADIUS, center: Vec2::ZERO },
+                    DrawMode::Fill(FillMode::color(core_color)),
+                    Transform::default(),
+                ))
+                .insert(
+                    Tween::<Scale>::new(Vec3::ONE, Vec3::splat(ANOMALY_PULSE_SCALE), ANOMALY_PULSE_DURATION, Ease::QuadInOut)
+                        .with_mode(TweenMode::PingPong),
+                );
+        });
+}
+
+// Anomalies ignore polarity, they only leave alone what the magnet itself can't move
+fn anomaly_fields(
+    config: Res<GameConfig>,
+    anomaly_query: Query<&Anomaly>,
+    mut magnetic_query: Query<(&Transform, &Magnetic, &mut Forces), (Without<Player>, Without<MagnetImmune>)>,
+)
+{
+    for anomaly in &anomaly_query {
+        let center = anomaly.center.extend(0.0);
+        for (transform, magnetic, mut forces) in &mut magnetic_query {
+            if magnetic.polarity == Polarity::Neutral {
+                continue;
+            }
+            if let Some(force) = field_force(&config, ANOMALY_RADIUS, ANOMALY_FORCE, center, transform.translation, anomaly.repels) {
+                forces.add_force(force * magnetic.strength);
+            }
+        }
+    }
+}
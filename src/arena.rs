@@ -0,0 +1,410 @@
+//! The arena a run is fought in: the four outer walls plus interior obstacles and magnetic anomalies,
+//! laid out at random every time a run starts, or as a handcrafted level describes them.
+//! The size of random arenas can be changed from the command line.
+//! Interior obstacles can be worn down and knocked out, the outer walls always stand.
+
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    anomalies::{spawn_anomaly, Anomaly, ANOMALY_RADIUS},
+    boss::boss_spawn,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, Bystander, Hp},
+    data::{LevelDefinition, LevelTable},
+    enemy::Tint,
+    fx::ExplosionToSpawn,
+    levels::{LoadingLevel, SelectedLevel},
+    physics::Collider,
+    player::player_spawn,
+    rng::GameRng,
+    FixedUpdate, GameState, LevelEntity, WALL_COLOR, WALL_THICKNESS,
+};
+
+// Distance between the middles of the outer walls, unless set with `--arena`
+pub const DEFAULT_ARENA_SIZE: Vec2 = Vec2::new(1800.0, 1200.0);
+
+const MIN_OBSTACLES: usize = 8;
+const MAX_OBSTACLES: usize = 14;
+const OBSTACLE_LENGTH: (f32, f32) = (60.0, 220.0);
+const OBSTACLE_THICKNESS: (f32, f32) = (20.0, 40.0);
+// Space kept free between obstacles and around the outer walls, so enemies can always get through
+const OBSTACLE_GAP: f32 = 60.0;
+const MIN_ANOMALIES: usize = 1;
+const MAX_ANOMALIES: usize = 3;
+// Anomalies keep at least this far from each other, so their fields don't pile up
+const ANOMALY_SPACING: f32 = ANOMALY_RADIUS * 1.5;
+// Space kept free around where the player and bosses appear
+const SPAWN_CLEARANCE: f32 = 120.0;
+// Attempts at finding a free spot before giving up on an obstacle or spawn position
+const PLACEMENT_ATTEMPTS: usize = 50;
+// Damage an interior obstacle takes before it breaks
+const OBSTACLE_HEALTH: i32 = 40;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Arena>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(generate_arena))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(generate_arena))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(break_obstacles.after(apply_damage)),
+            );
+    }
+}
+
+// An axis-aligned box, as walls and obstacles are never rotated
+#[derive(Component, Clone, Copy, Deserialize)]
+pub struct Obstacle {
+    pub center: Vec2,
+    pub half_size: Vec2,
+}
+
+impl Obstacle {
+    fn overlaps(&self, center: Vec2, half_size: Vec2) -> bool {
+        let distance = (center - self.center).abs();
+        let reach = self.half_size + half_size;
+        distance.x < reach.x && distance.y < reach.y
+    }
+
+    // The shortest move that gets a box out of this obstacle, zero if they don't overlap
+    pub fn push_out(&self, center: Vec2, half_size: Vec2) -> Vec2 {
+        if !self.overlaps(center, half_size) {
+            return Vec2::ZERO;
+        }
+
+        let offset = center - self.center;
+        let depth = self.half_size + half_size - offset.abs();
+        if depth.x < depth.y {
+            Vec2::new(depth.x * sign(offset.x), 0.0)
+        } else {
+            Vec2::new(0.0, depth.y * sign(offset.y))
+        }
+    }
+
+    // Whether the segment from `from` to `to` passes through this obstacle, clipped against each axis in turn
+    fn blocks(&self, from: Vec2, to: Vec2) -> bool {
+        let min = self.center - self.half_size;
+        let max = self.center + self.half_size;
+        let delta = to - from;
+        let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+
+        for axis in 0..2 {
+            if delta[axis] == 0.0 {
+                // Parallel to this axis, it is either always between the sides or never
+                if from[axis] < min[axis] || from[axis] > max[axis] {
+                    return false;
+                }
+                continue;
+            }
+            let near = (min[axis] - from[axis]) / delta[axis];
+            let far = (max[axis] - from[axis]) / delta[axis];
+            enter = enter.max(near.min(far));
+            exit = exit.min(near.max(far));
+            if enter > exit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Like `signum`, but picks a side when exactly centered
+fn sign(value: f32) -> f32
+{
+    if value < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+// The bounds of the arena, its interior obstacles and anomalies of the current run.
+// The outer walls are left to the bounds, centered on the origin.
+pub struct Arena {
+    half_size: Vec2,
+    // What random arenas go back to after a level of another size
+    random_half_size: Vec2,
+    obstacles: Vec<Obstacle>,
+    anomalies: Vec<Anomaly>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::new(DEFAULT_ARENA_SIZE)
+    }
+}
+
+impl Arena {
+    pub fn new(size: Vec2) -> Self {
+        Self { half_size: size / 2.0, random_half_size: size / 2.0, obstacles: Vec::new(), anomalies: Vec::new() }
+    }
+
+    // Where the middle of each outer wall runs
+    pub fn left(&self) -> f32 {
+        -self.half_size.x
+    }
+
+    pub fn right(&self) -> f32 {
+        self.half_size.x
+    }
+
+    pub fn bottom(&self) -> f32 {
+        -self.half_size.y
+    }
+
+    pub fn top(&self) -> f32 {
+        self.half_size.y
+    }
+
+    fn generate(&mut self, rng: &mut impl Rng) {
+        self.half_size = self.random_half_size;
+        self.obstacles.clear();
+        let spawns = [player_spawn(self), boss_spawn(self)];
+
+        let count = rng.gen_range(MIN_OBSTACLES..=MAX_OBSTACLES);
+        for _ in 0..count {
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let length = rng.gen_range(OBSTACLE_LENGTH.0..OBSTACLE_LENGTH.1);
+                let thickness = rng.gen_range(OBSTACLE_THICKNESS.0..OBSTACLE_THICKNESS.1);
+                let half_size = if rng.gen() {
+                    Vec2::new(length, thickness) / 2.0
+                } else {
+                    Vec2::new(thickness, length) / 2.0
+                };
+                let obstacle = Obstacle {
+                    center: self.random_position(rng, half_size + OBSTACLE_GAP),
+                    half_size,
+                };
+
+                let blocked = self.overlaps(obstacle.center, half_size + OBSTACLE_GAP)
+                    || spawns
+                        .iter()
+                        .any(|spawn| obstacle.overlaps(*spawn, Vec2::splat(SPAWN_CLEARANCE)));
+                if !blocked {
+                    self.obstacles.push(obstacle);
+                    break;
+                }
+            }
+        }
+
+        // Anomalies go in the open, away from the spawns so nothing gets caught in one straight away
+        self.anomalies.clear();
+        let count = rng.gen_range(MIN_ANOMALIES..=MAX_ANOMALIES);
+        for _ in 0..count {
+            let mut avoid = spawns.to_vec();
+            avoid.extend(self.anomalies.iter().map(|anomaly| anomaly.center));
+            let center = self.random_open_position(rng, Vec2::splat(SPAWN_CLEARANCE / 2.0), &avoid, ANOMALY_SPACING);
+            self.anomalies.push(Anomaly { center, repels: rng.gen() });
+        }
+    }
+
+    fn load(&mut self, level: &LevelDefinition) {
+        self.half_size = level.size / 2.0;
+        self.obstacles = level.walls.clone();
+        self.anomalies = level.anomalies.clone();
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    pub fn anomalies(&self) -> &[Anomaly] {
+        &self.anomalies
+    }
+
+    pub fn overlaps(&self, center: Vec2, half_size: Vec2) -> bool {
+        self.obstacles.iter().any(|obstacle| obstacle.overlaps(center, half_size))
+    }
+
+    // Once broken, an obstacle no longer blocks anything. Returns whether it was still standing.
+    fn remove_obstacle(&mut self, center: Vec2) -> bool {
+        let count = self.obstacles.len();
+        self.obstacles.retain(|obstacle| obstacle.center != center);
+        self.obstacles.len() < count
+    }
+
+    // A random spot inside the walls where a box of the given size doesn't touch any obstacle,
+    // and is at least `radius` away from each of the `avoid` points
+    pub fn random_open_position(&self, rng: &mut impl Rng, half_size: Vec2, avoid: &[Vec2], radius: f32) -> Vec2 {
+        let mut position = self.random_position(rng, half_size);
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let crowded = avoid.iter().any(|point| point.distance(position) < radius);
+            if !crowded && !self.overlaps(position, half_size) {
+                break;
+            }
+            position = self.random_position(rng, half_size);
+        }
+        position
+    }
+
+    // A random spot inside the walls, far enough from them for a box of the given size
+    fn random_position(&self, rng: &mut impl Rng, half_size: Vec2) -> Vec2 {
+        let inset = half_size + WALL_THICKNESS / 2.0;
+        Vec2::new(
+            rng.gen_range(self.left() + inset.x..self.right() - inset.x),
+            rng.gen_range(self.bottom() + inset.y..self.top() - inset.y),
+        )
+    }
+
+    // Moves a box out of every obstacle it overlaps
+    pub fn push_out(&self, mut center: Vec2, half_size: Vec2) -> Vec2 {
+        for obstacle in &self.obstacles {
+            center += obstacle.push_out(center, half_size);
+        }
+        center
+    }
+
+    // Whether nothing stands between two points, the outer walls never do as both are inside them
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        !self.obstacles.iter().any(|obstacle| obstacle.blocks(from, to))
+    }
+
+    // Points away from nearby obstacles, stronger the closer they are
+    pub fn avoidance(&self, position: Vec2, radius: f32) -> Vec2 {
+        let mut avoidance = Vec2::ZERO;
+        for obstacle in &self.obstacles {
+            let closest = position.clamp(obstacle.center - obstacle.half_size, obstacle.center + obstacle.half_size);
+            let offset = position - closest;
+            let distance = offset.length();
+            if distance <= 0.0 {
+                // Already inside, head out the way we came in
+                avoidance += (position - obstacle.center).normalize_or_zero();
+            } else if distance < radius {
+                avoidance += offset / distance * (1.0 - distance / radius);
+            }
+        }
+        avoidance
+    }
+}
+
+// This bundle is a collection of the components that define a "wall" in our game
+#[derive(Bundle)]
+struct WallBundle {
+    // You can nest bundles inside of other bundles like this
+    // Allowing you to compose their functionality
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+    collider: Collider,
+}
+
+enum WallLocation {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl WallLocation {
+    fn position(&self, arena: &Arena) -> Vec2 {
+        match self {
+            WallLocation::Left => Vec2::new(arena.left(), 0.),
+            WallLocation::Right => Vec2::new(arena.right(), 0.),
+            WallLocation::Bottom => Vec2::new(0., arena.bottom()),
+            WallLocation::Top => Vec2::new(0., arena.top()),
+        }
+    }
+
+    fn size(&self, arena: &Arena) -> Vec2 {
+        let arena_height = arena.top() - arena.bottom();
+        let arena_width = arena.right() - arena.left();
+        // Make sure we haven't been given a nonsensical size
+        assert!(arena_height > 0.0);
+        assert!(arena_width > 0.0);
+
+        match self {
+            WallLocation::Left | WallLocation::Right => {
+                Vec2::new(WALL_THICKNESS, arena_height + WALL_THICKNESS)
+            }
+            WallLocation::Bottom | WallLocation::Top => {
+                Vec2::new(arena_width + WALL_THICKNESS, WALL_THICKNESS)
+            }
+        }
+    }
+}
+
+impl WallBundle {
+    // This "builder method" allows us to reuse logic across our wall entities,
+    // making our code easier to read and less prone to bugs when we change the logic
+    fn new(position: Vec2, size: Vec2) -> WallBundle {
+        WallBundle {
+            sprite_bundle: SpriteBundle {
+                transform: Transform {
+                    // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
+                    // This is used to determine the order of our sprites
+                    translation: position.extend(0.0),
+                    // The z-scale of 2D objects must always be 1.0,
+                    // or their ordering will be affected in surprising ways.
+                    // See https://github.com/bevyengine/bevy/issues/4149
+                    scale: size.extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    ..default()
+                },
+                ..default()
+            },
+            collider: Collider,
+        }
+    }
+}
+
+// Lays out a fresh arena, or the selected level's, and spawns its walls, torn down with the rest of the run
+pub fn generate_arena(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut arena: ResMut<Arena>,
+    selected: Res<SelectedLevel>,
+    levels: Option<Res<LevelTable>>,
+)
+{
+    match selected.get(levels.as_deref()) {
+        Some(level) => arena.load(level),
+        None => arena.generate(&mut rng.0),
+    }
+
+    for location in [WallLocation::Left, WallLocation::Right, WallLocation::Bottom, WallLocation::Top] {
+        commands
+            .spawn_bundle(WallBundle::new(location.position(&arena), location.size(&arena)))
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .insert(LevelEntity);
+    }
+
+    // Obstacles flash when hit like enemies do, and fade as they wear down. Breaking them isn't damage dealt
+    for obstacle in arena.obstacles() {
+        commands
+            .spawn_bundle(WallBundle::new(obstacle.center, obstacle.half_size * 2.0))
+            .insert(*obstacle)
+            .insert(Hp { current: OBSTACLE_HEALTH, max: OBSTACLE_HEALTH })
+            .insert(Bystander)
+            .insert(Tint(WALL_COLOR))
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .insert(LevelEntity);
+    }
+
+    for anomaly in arena.anomalies() {
+        spawn_anomaly(&mut commands, *anomaly);
+    }
+}
+
+fn break_obstacles(
+    mut commands: Commands,
+    mut arena: ResMut<Arena>,
+    query: Query<(Entity, &Obstacle, &Hp, &Transform)>,
+)
+{
+    for (entity, obstacle, hp, transform) in &query {
+        // A frame may run several steps before the despawn is applied
+        if hp.current > 0 || !arena.remove_obstacle(obstacle.center) {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+    }
+}
@@ -0,0 +1,333 @@
+//! Bosses showing up every few waves: a big enemy cycling through charge, projectile burst
+//! and magnet-immune phases, with its own health bar across the top of the screen.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{DamageKind, Hp, Resistances},
+    config::GameConfig,
+    enemy::{check_for_collisions, Enemy, ENEMY_DRAG, ENEMY_HEALTH_BAR_COLOR, ENEMY_MASS},
+    fx::{spawn_sparks, ExplosionToSpawn},
+    juice::{ScreenShake, BOSS_HIT_TRAUMA},
+    magnet::{magnet, MagnetImmune},
+    physics::{Collider, Forces, Mass, PhysicsBundle, Velocity},
+    pickups::PowerUps,
+    player::{nearest_player, Player},
+    projectiles::spawn_projectile,
+    shield::{spawn_shield_bar, Shield},
+    status::StatusEffects,
+    time_scale::TimeScale,
+    FixedUpdate, GameState, LevelEntity, Scoreboard, BACKGROUND_COLOR, TIME_STEP,
+};
+
+// Keys of the banner shown when the boss makes its entrance
+pub const BOSS_NAME: &str = "boss.name";
+pub const BOSS_TITLE: &str = "boss.title";
+
+const BOSS_SIZE: Vec2 = Vec2::new(80.0, 80.0);
+// Bosses shrug off part of the damage the crowd around them deals when it blows up or burns
+const BOSS_AREA_RESISTANCE: f32 = 0.5;
+const BOSS_MASS: f32 = ENEMY_MASS * 20.0;
+const BOSS_SPEED: f32 = 60.0;
+const BOSS_STEERING_FORCE: f32 = 200.0;
+const BOSS_SCORE: i32 = 50;
+// Speed the boss lunges at the player with when charging
+const BOSS_CHARGE_SPEED: f32 = 500.0;
+const BOSS_BURST_PROJECTILES: usize = 16;
+const BOSS_PROJECTILE_SPEED: f32 = 220.0;
+const BOSS_PROJECTILE_DAMAGE: i32 = 2;
+// How long each phase lasts, in seconds
+const BOSS_CHASE_DURATION: f32 = 3.0;
+const BOSS_CHARGE_DURATION: f32 = 1.2;
+const BOSS_BURST_DURATION: f32 = 1.5;
+const BOSS_SHIELD_DURATION: f32 = 2.5;
+const BOSS_SHIELD_COLOR: Color = Color::rgb(0.6, 0.6, 1.0);
+// On top of its health the boss carries a recharging shield worth this fraction of it
+const BOSS_SHIELD_FRACTION: f32 = 0.25;
+const BOSS_SHIELD_REGEN_DELAY: f32 = 5.0;
+
+// Explosions going off one after another where the boss died
+const BOSS_DEATH_EXPLOSIONS: u32 = 12;
+const BOSS_DEATH_EXPLOSION_INTERVAL: f32 = 0.12;
+// The kill plays out in slow motion for this many real seconds
+const BOSS_DEATH_SLOW_MOTION_SCALE: f32 = 0.3;
+const BOSS_DEATH_SLOW_MOTION_DURATION: f32 = 2.0;
+
+const BOSS_BAR_SIZE: Size<Val> = Size { width: Val::Percent(50.0), height: Val::Px(16.0) };
+const BOSS_BAR_TOP: Val = Val::Px(50.0);
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(boss_behavior.before(magnet))
+                .with_system(boss_death.after(check_for_collisions))
+                .with_system(play_boss_death_sequence),
+        )
+        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_boss_health_bar));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BossPhase {
+    Chase,
+    Charge,
+    Burst,
+    Shielded,
+}
+
+impl BossPhase {
+    fn next(&self) -> BossPhase {
+        match self {
+            BossPhase::Chase => BossPhase::Charge,
+            BossPhase::Charge => BossPhase::Burst,
+            BossPhase::Burst => BossPhase::Shielded,
+            BossPhase::Shielded => BossPhase::Chase,
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        match self {
+            BossPhase::Chase => BOSS_CHASE_DURATION,
+            BossPhase::Charge => BOSS_CHARGE_DURATION,
+            BossPhase::Burst => BOSS_BURST_DURATION,
+            BossPhase::Shielded => BOSS_SHIELD_DURATION,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Boss {
+    phase: BossPhase,
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct BossHealthBar;
+
+#[derive(Component)]
+struct BossHealthBarFill;
+
+// Keeps setting off explosions around where a boss died
+#[derive(Component)]
+struct BossDeathSequence {
+    timer: Timer,
+    remaining: u32,
+}
+
+// Where bosses appear, kept clear of obstacles when the arena is generated
+pub fn boss_spawn(arena: &Arena) -> Vec2
+{
+    Vec2::new(0.0, arena.top() - BOSS_SIZE.y)
+}
+
+pub fn spawn_boss(commands: &mut Commands, asset_server: &AssetServer, arena: &Arena, health: i32)
+{
+    let boss = commands
+        .spawn()
+        .insert(Enemy)
+        .insert(Boss {
+            phase: BossPhase::Chase,
+            timer: Timer::from_seconds(BossPhase::Chase.duration(), false),
+        })
+        .insert(Hp { current: health, max: health })
+        .insert(Shield::new((health as f32 * BOSS_SHIELD_FRACTION) as i32, BOSS_SHIELD_REGEN_DELAY))
+        .insert(StatusEffects::default())
+        .insert(Resistances(
+            [(DamageKind::Explosion, BOSS_AREA_RESISTANCE), (DamageKind::Burn, BOSS_AREA_RESISTANCE)].into_iter().collect(),
+        ))
+        .insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(1.0, 1.0)),
+                ..default()
+            },
+            transform: Transform {
+                translation: boss_spawn(arena).extend(0.0),
+                scale: BOSS_SIZE.extend(1.0),
+                ..default()
+            },
+            texture: asset_server.load("images/enemy_3.png"),
+            ..default()
+        })
+        .insert_bundle(PhysicsBundle::new(Vec2::ZERO, BOSS_MASS, ENEMY_DRAG))
+        .insert(Collider)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .id();
+    spawn_shield_bar(commands, boss, BOSS_SIZE.x, BOSS_SIZE.y / 2.0);
+
+    // Health bar across the top of the screen
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: BOSS_BAR_TOP,
+                    left: Val::Percent(25.0),
+                    ..default()
+                },
+                size: BOSS_BAR_SIZE,
+                padding: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            color: BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .insert(BossHealthBar)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    color: ENEMY_HEALTH_BAR_COLOR.into(),
+                    ..default()
+                })
+                .insert(BossHealthBarFill);
+        });
+}
+
+fn boss_behavior(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    mut boss_query: Query<(Entity, &mut Boss, &Transform, &Velocity, &mut Forces, &Mass, &mut Sprite), Without<Player>>,
+)
+{
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+
+    for (entity, mut boss, transform, velocity, mut forces, mass, mut sprite) in &mut boss_query {
+        let position = transform.translation.truncate();
+        let player_position = match nearest_player(players.iter().copied(), position) {
+            Some(player_position) => player_position,
+            None => return,
+        };
+        let to_player = (player_position - position).normalize_or_zero();
+
+        if boss.phase == BossPhase::Chase {
+            let steering = ((to_player * BOSS_SPEED - velocity.0) / TIME_STEP).clamp_length_max(BOSS_STEERING_FORCE);
+            forces.add_force(steering * mass.0);
+        }
+
+        if !boss.timer.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            continue;
+        }
+
+        // Leaving the shielded phase
+        if boss.phase == BossPhase::Shielded {
+            commands.entity(entity).remove::<MagnetImmune>();
+            sprite.color = Color::WHITE;
+        }
+
+        boss.phase = boss.phase.next();
+        boss.timer = Timer::from_seconds(boss.phase.duration(), false);
+
+        match boss.phase {
+            BossPhase::Chase => {}
+            BossPhase::Charge => {
+                forces.add_impulse(to_player * BOSS_CHARGE_SPEED * mass.0);
+            }
+            BossPhase::Burst => {
+                for index in 0..BOSS_BURST_PROJECTILES {
+                    let angle = index as f32 / BOSS_BURST_PROJECTILES as f32 * TAU;
+                    let direction = Vec2::new(angle.cos(), angle.sin());
+                    spawn_projectile(
+                        &mut commands,
+                        position + direction * BOSS_SIZE.x / 2.0,
+                        direction * BOSS_PROJECTILE_SPEED,
+                        BOSS_PROJECTILE_DAMAGE,
+                    );
+                }
+            }
+            BossPhase::Shielded => {
+                commands.entity(entity).insert(MagnetImmune);
+                sprite.color = BOSS_SHIELD_COLOR;
+            }
+        }
+    }
+}
+
+fn boss_death(
+    mut commands: Commands,
+    mut scoreboard: ResMut<Scoreboard>,
+    power_ups: Res<PowerUps>,
+    mut time_scale: ResMut<TimeScale>,
+    boss_query: Query<(Entity, &Hp, &Transform), With<Boss>>,
+    bar_query: Query<Entity, With<BossHealthBar>>,
+)
+{
+    for (entity, hp, transform) in &boss_query {
+        if hp.current > 0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        for bar in &bar_query {
+            commands.entity(bar).despawn_recursive();
+        }
+        scoreboard.score += BOSS_SCORE * power_ups.score_multiplier();
+        time_scale.slow_motion(BOSS_DEATH_SLOW_MOTION_SCALE, BOSS_DEATH_SLOW_MOTION_DURATION);
+
+        commands
+            .spawn_bundle(TransformBundle::from_transform(Transform::from_translation(transform.translation)))
+            .insert(BossDeathSequence {
+                timer: Timer::from_seconds(BOSS_DEATH_EXPLOSION_INTERVAL, true),
+                remaining: BOSS_DEATH_EXPLOSIONS,
+            })
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .insert(LevelEntity);
+    }
+}
+
+fn play_boss_death_sequence(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut shake: ResMut<ScreenShake>,
+    mut query: Query<(Entity, &mut BossDeathSequence, &Transform)>,
+)
+{
+    for (entity, mut sequence, transform) in &mut query {
+        if !sequence.timer.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+            continue;
+        }
+
+        let offset = Vec2::new(
+            thread_rng().gen_range(-BOSS_SIZE.x..BOSS_SIZE.x),
+            thread_rng().gen_range(-BOSS_SIZE.y..BOSS_SIZE.y),
+        ) / 2.0;
+        let position = transform.translation + offset.extend(0.0);
+        commands.spawn().insert(ExplosionToSpawn(position));
+        spawn_sparks(&mut commands, &config, position);
+        shake.add_trauma(BOSS_HIT_TRAUMA);
+
+        sequence.remaining -= 1;
+        if sequence.remaining == 0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn update_boss_health_bar(
+    boss_query: Query<&Hp, With<Boss>>,
+    mut fill_query: Query<&mut Style, With<BossHealthBarFill>>,
+)
+{
+    let hp = match boss_query.get_single() {
+        Ok(hp) => hp,
+        Err(_) => return,
+    };
+
+    for mut style in &mut fill_query {
+        style.size.width = Val::Percent(hp.current.max(0) as f32 / hp.max as f32 * 100.0);
+    }}
@@ -0,0 +1,152 @@
+//! Bullet time: with the magnet fully charged, holding pull and push together spends all of its energy
+//! on a short burst of slow motion, while the screen drains of color. Nobody presses both on the very same
+//! step, so the second button only has to follow the first within `PRESS_WINDOW`, and the charge counts from
+//! when the first went down, before the magnet started draining it.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    actions::{Action, ActionState},
+    cleanup::DespawnOnStateExit,
+    magnet::{magnet, MagnetEnergy},
+    player::Player,
+    time_scale::TimeScale,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const BULLET_TIME_SCALE: f32 = 0.3;
+// Real seconds the burst lasts
+const BULLET_TIME_DURATION: f32 = 2.0;
+// A gray veil over the screen washes the colors out, fading in and out over this many seconds
+const DESATURATION_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.45);
+const DESATURATION_FADE: f32 = 0.2;
+// Seconds the second button may come after the first
+const PRESS_WINDOW: f32 = 0.2;
+
+pub struct BulletTimePlugin;
+
+impl Plugin for BulletTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BulletTime>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_bullet_time)
+                    .with_system(spawn_desaturation),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    // Before the magnet gets to drain the energy
+                    .with_system(trigger_bullet_time.before(magnet)),
+            )
+            .add_system(update_desaturation);
+    }
+}
+
+// Counts down the burst in real time, like the slow motion it drives
+pub struct BulletTime(Timer);
+
+impl Default for BulletTime {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(BULLET_TIME_DURATION, false);
+        timer.tick(Duration::from_secs_f32(BULLET_TIME_DURATION));
+        Self(timer)
+    }
+}
+
+impl BulletTime {
+    pub fn active(&self) -> bool {
+        !self.0.finished()
+    }
+}
+
+#[derive(Component)]
+struct Desaturation;
+
+// Since the first of pull and push went down, for as long as either is held
+struct MagnetPress {
+    charged: bool,
+    held: f32,
+}
+
+fn reset_bullet_time(mut bullet_time: ResMut<BulletTime>)
+{
+    *bullet_time = BulletTime::default();
+}
+
+fn spawn_desaturation(mut commands: Commands)
+{
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(Desaturation)
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+fn trigger_bullet_time(
+    mut bullet_time: ResMut<BulletTime>,
+    mut time_scale: ResMut<TimeScale>,
+    mut presses: Local<HashMap<Entity, MagnetPress>>,
+    mut query: Query<(Entity, &ActionState, &mut MagnetEnergy), With<Player>>,
+)
+{
+    for (player, actions, mut energy) in &mut query {
+        if !(actions.pressed(Action::Pull) || actions.pressed(Action::Push)) {
+            presses.remove(&player);
+            continue;
+        }
+        let press = presses
+            .entry(player)
+            .or_insert(MagnetPress { charged: energy.current >= energy.max, held: 0.0 });
+        press.held += TIME_STEP;
+
+        if bullet_time.active() || !press.charged || press.held > PRESS_WINDOW {
+            continue;
+        }
+        if !(actions.pressed(Action::Pull) && actions.pressed(Action::Push)) {
+            continue;
+        }
+
+        // Leaves the magnet depleted, so it stays off until it has recharged some
+        press.charged = false;
+        energy.current = 0.0;
+        energy.depleted = true;
+        bullet_time.0.reset();
+        time_scale.slow_motion(BULLET_TIME_SCALE, BULLET_TIME_DURATION);
+    }
+    // Players gone mid-press
+    presses.retain(|player, _| query.contains(*player));
+}
+
+fn update_desaturation(
+    time: Res<Time>,
+    mut bullet_time: ResMut<BulletTime>,
+    mut query: Query<&mut UiColor, With<Desaturation>>,
+)
+{
+    bullet_time.0.tick(time.delta());
+
+    // Fades in as the burst starts and out as it ends
+    let elapsed = bullet_time.0.elapsed_secs();
+    let remaining = BULLET_TIME_DURATION - elapsed;
+    let strength = if bullet_time.active() {
+        (elapsed / DESATURATION_FADE).min(remaining / DESATURATION_FADE).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    for mut color in &mut query {
+        let mut veil = DESATURATION_COLOR;
+        veil.set_a(DESATURATION_COLOR.a() * strength);
+        color.0 = veil;
+    }
+}
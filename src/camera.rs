@@ -0,0 +1,160 @@
+//! A camera that smoothly follows the player (the middle of the players in co-op), stays inside the arena and zooms with the scroll wheel.
+//! Cutscenes can briefly point it elsewhere through the `CameraDirector`.
+//! It also keeps track of where the mouse cursor points in the arena.
+
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::*,
+    render::camera::ScalingMode,
+    window::{CursorLeft, CursorMoved},
+};
+
+use crate::{
+    arena::Arena,
+    cutscene::CameraDirector,
+    juice::{update_screen_shake, ScreenShake},
+    player::Player,
+    GameState, WALL_THICKNESS,
+};
+
+// How quickly the camera catches up with the player, higher is snappier
+const FOLLOW_SPEED: f32 = 5.0;
+// Projection scale limits, larger shows more of the arena
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 2.0;
+// Change in projection scale per scroll line
+const ZOOM_STEP: f32 = 0.1;
+
+pub struct FollowCameraPlugin;
+
+impl Plugin for FollowCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorWorldPos>()
+            .add_system(zoom_camera.before(follow_player))
+            .add_system(follow_player.after(update_screen_shake))
+            .add_system(track_cursor.after(follow_player));
+    }
+}
+
+// Where the camera is looking, before screen shake is added on top
+#[derive(Component, Default)]
+pub struct FollowCamera {
+    focus: Vec2,
+}
+
+// Where the mouse cursor points in the arena, `None` while it is outside the window
+#[derive(Default)]
+pub struct CursorWorldPos {
+    // In window pixels, kept so the world position follows the camera while the mouse stays still
+    screen: Option<Vec2>,
+    pub world: Option<Vec2>,
+}
+
+fn zoom_camera(mut wheel_events: EventReader<MouseWheel>, mut camera_query: Query<&mut OrthographicProjection, With<FollowCamera>>)
+{
+    let scrolled: f32 = wheel_events.iter().map(|event| event.y.signum()).sum();
+    if scrolled == 0.0 {
+        return;
+    }
+
+    for mut projection in &mut camera_query {
+        // Scrolling up zooms in
+        projection.scale = (projection.scale - scrolled * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+pub fn follow_player(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    shake: Res<ScreenShake>,
+    arena: Res<Arena>,
+    director: Res<CameraDirector>,
+    player_query: Query<&Transform, (With<Player>, Without<FollowCamera>)>,
+    mut camera_query: Query<(&mut FollowCamera, &mut Transform, &OrthographicProjection)>,
+)
+{
+    // The camera is moved by hand in photo mode
+    if *state.current() == GameState::PhotoMode {
+        return;
+    }
+
+    for (mut camera, mut transform, projection) in &mut camera_query {
+        // Keep looking at the last spot if the players are gone, e.g. on the game over screen
+        let count = player_query.iter().count();
+        if count > 0 {
+            let target = player_query.iter().map(|transform| transform.translation.truncate()).sum::<Vec2>() / count as f32;
+            // Exponential smoothing, independent of the frame rate
+            let blend = 1.0 - (-FOLLOW_SPEED * time.delta_seconds()).exp();
+            camera.focus = camera.focus.lerp(target, blend);
+        }
+
+        let half_view = visible_size(projection) / 2.0;
+        let clamp = |point: Vec2| {
+            Vec2::new(
+                clamp_to_arena(point.x, half_view.x, arena.left(), arena.right()),
+                clamp_to_arena(point.y, half_view.y, arena.bottom(), arena.top()),
+            )
+        };
+        camera.focus = clamp(camera.focus);
+
+        // A cutscene can take the camera elsewhere for a while, following carries on underneath
+        let position = clamp(director.framing(camera.focus)) + shake.offset();
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+// How much of the arena the camera shows, leaving out what the letterbox bars cover
+pub fn visible_size(projection: &OrthographicProjection) -> Vec2
+{
+    let size = match projection.scaling_mode {
+        ScalingMode::Auto { min_width, min_height } => Vec2::new(min_width, min_height),
+        _ => Vec2::new(projection.right - projection.left, projection.top - projection.bottom),
+    };
+    size * projection.scale
+}
+
+// Keeps the view within the walls, centering it when the arena is smaller than the view
+fn clamp_to_arena(value: f32, half_view: f32, min: f32, max: f32) -> f32
+{
+    let min = min - WALL_THICKNESS / 2.0 + half_view;
+    let max = max + WALL_THICKNESS / 2.0 - half_view;
+    if min > max {
+        (min + max) / 2.0
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+pub fn track_cursor(
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut cursor_left_events: EventReader<CursorLeft>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+    mut cursor: ResMut<CursorWorldPos>,
+)
+{
+    if let Some(event) = cursor_moved_events.iter().last() {
+        cursor.screen = Some(event.position);
+    }
+    if cursor_left_events.iter().count() > 0 {
+        cursor.screen = None;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (camera, camera_transform) = match camera_query.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    // From window pixels to normalized device coordinates, then back through the camera into the world
+    let window_size = Vec2::new(window.width(), window.height());
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+    cursor.world = cursor.screen.map(|screen| {
+        let ndc = screen / window_size * 2.0 - Vec2::ONE;
+        ndc_to_world.project_point3(ndc.extend(-1.0)).truncate()
+    });
+}
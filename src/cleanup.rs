@@ -0,0 +1,43 @@
+//! Scoped cleanup: entities are tagged with the state they belong to and despawned in bulk, children and all,
+//! once the game leaves it. A run's entities belong to `Playing` and go when it is restarted, menu screens
+//! belong to their own state. Entities tagged `LevelEntity` go whenever the next level is loaded.
+
+use bevy::{ecs::schedule::StateData, prelude::*};
+
+use crate::{levels::LoadingLevel, GameState, LevelEntity};
+
+pub struct CleanupPlugin;
+
+impl Plugin for CleanupPlugin {
+    fn build(&self, app: &mut App) {
+        for state in GameState::ALL {
+            app.add_system_set(SystemSet::on_exit(state).with_system(despawn_on_state_exit(state)));
+        }
+        app.add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(despawn_level));
+    }
+}
+
+// Despawned once the game leaves `S`. Only leaving counts, not having another state pushed on top
+#[derive(Component)]
+pub struct DespawnOnStateExit<S: StateData>(pub S);
+
+fn despawn_on_state_exit<S: StateData>(
+    state: S,
+) -> impl FnMut(Commands, Query<(Entity, &DespawnOnStateExit<S>)>)
+{
+    move |mut commands: Commands, query: Query<(Entity, &DespawnOnStateExit<S>)>| {
+        for (entity, scope) in &query {
+            if scope.0 == state {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+// What the next level brings is only spawned once this has run, so only the last level's entities are found
+fn despawn_level(mut commands: Commands, query: Query<Entity, With<LevelEntity>>)
+{
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,131 @@
+//! Command-line options, parsed once at startup before the app is built:
+//!
+//!   --seed <n>             lay out every run from the same seed
+//!   --fullscreen           start in borderless fullscreen
+//!   --window <w>x<h>       start with a window of this size
+//!   --arena <w>x<h>        play in an arena of this size
+//!   --wave <n>             start runs at this wave
+//!   --mode <mode>          start in waves, survival or horde mode
+//!   --mute                 silence music and sound effects
+//!
+//! None of them are written to the settings file, they only last for the session.
+
+use std::{env, process, str::FromStr};
+
+use bevy::{prelude::*, window::WindowMode};
+
+use crate::{settings::WINDOW_SIZES, survival::GameMode};
+
+// Smaller arenas leave no room to spawn enemies away from the players
+const MIN_ARENA_SIZE: Vec2 = Vec2::new(600.0, 400.0);
+
+const WINDOW_TITLE: &str = "Magnet PvE";
+
+const USAGE: &str = "usage: magnet-pve [--seed <n>] [--fullscreen] [--window <w>x<h>] [--arena <w>x<h>] \
+                     [--wave <n>] [--mode waves|survival|horde] [--mute]";
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub seed: Option<u64>,
+    pub fullscreen: bool,
+    pub window_size: Option<Vec2>,
+    pub arena_size: Option<Vec2>,
+    pub start_wave: Option<u32>,
+    pub mode: Option<GameMode>,
+    pub mute: bool,
+}
+
+impl Options {
+    // Exits with the usage on anything it does not understand, before a window is ever opened
+    pub fn from_args() -> Options {
+        match Options::parse(env::args().skip(1)) {
+            Ok(options) => options,
+            Err(error) => {
+                eprintln!("{}\n{}", error, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--fullscreen" => options.fullscreen = true,
+                "--window" => options.window_size = Some(parse_size(&arg, args.next())?),
+                "--arena" => {
+                    let size = parse_size(&arg, args.next())?;
+                    if size.x < MIN_ARENA_SIZE.x || size.y < MIN_ARENA_SIZE.y {
+                        return Err(format!(
+                            "--arena must be at least {}x{}",
+                            MIN_ARENA_SIZE.x, MIN_ARENA_SIZE.y
+                        ));
+                    }
+                    options.arena_size = Some(size);
+                }
+                "--wave" => {
+                    let wave: u32 = parse_value(&arg, args.next())?;
+                    if wave == 0 {
+                        return Err("--wave starts at 1".to_string());
+                    }
+                    options.start_wave = Some(wave);
+                }
+                "--mode" => {
+                    let name: String = parse_value(&arg, args.next())?;
+                    let mode = GameMode::ALL
+                        .into_iter()
+                        .find(|mode| mode.name().eq_ignore_ascii_case(&name))
+                        .ok_or_else(|| format!("unknown mode '{}'", name))?;
+                    options.mode = Some(mode);
+                }
+                "--mute" => options.mute = true,
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    // The window to open with, the saved settings take over from there unless overridden here
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        // The smallest of the sizes offered in the settings, unless given here
+        let (width, height) = self.window_size.map_or(WINDOW_SIZES[0], |size| (size.x, size.y));
+        let mut window = WindowDescriptor {
+            title: WINDOW_TITLE.to_string(),
+            width,
+            height,
+            resizable: true,
+            ..default()
+        };
+        if self.fullscreen {
+            window.mode = WindowMode::BorderlessFullscreen;
+        }
+        window
+    }
+}
+
+fn parse_value<T: FromStr>(option: &str, value: Option<String>) -> Result<T, String>
+{
+    let value = value.ok_or_else(|| format!("{} expects a value", option))?;
+    value.parse().map_err(|_| format!("invalid value '{}' for {}", value, option))
+}
+
+// Sizes are given as `<width>x<height>`, e.g. `1280x720`
+fn parse_size(option: &str, value: Option<String>) -> Result<Vec2, String>
+{
+    let value: String = parse_value(option, value)?;
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("{} expects <width>x<height>, got '{}'", option, value))?;
+    let width: f32 = parse_value(option, Some(width.to_string()))?;
+    let height: f32 = parse_value(option, Some(height.to_string()))?;
+    // `NaN` and `inf` parse as floats too, and would get past every comparison below
+    if !width.is_finite() || !height.is_finite() {
+        return Err(format!("{} must be a finite size, got '{}'", option, value));
+    }
+    if width <= 0.0 || height <= 0.0 {
+        return Err(format!("{} must be positive, got '{}'", option, value));
+    }
+    Ok(Vec2::new(width, height))
+}
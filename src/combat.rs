@@ -0,0 +1,614 @@
+//! The pulse, the player's default weapon swung towards where they aim, and the damage it deals to enemies.
+//! Every source of damage, in here or elsewhere, goes through a `DamageEvent` resolved in `apply_damage`.
+
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_prototype_lyon::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    boss::Boss,
+    cleanup::DespawnOnStateExit,
+    config::GameConfig,
+    damage_numbers::DamageNumberEvent,
+    difficulty::Difficulty,
+    enemy::{check_for_collisions, check_for_enemy_collisions, Enemy, EnemyKind, ScoreValue, Tint},
+    fx::ExplosionToSpawn,
+    juice::{HitStop, JuiceSettings, ScreenShake, BOSS_HIT_TRAUMA, ENEMY_DEATH_TRAUMA, PLAYER_DAMAGE_TRAUMA},
+    physics::{integrate_forces, Forces},
+    player::{Aim, Knockback, Player, PlayerCollisionEvent},
+    rng::GameRng,
+    shield::Shield,
+    sound::{PlaySfx, Sfx},
+    spatial::SpatialHash,
+    spawners::Spawner,
+    stats::RunStats,
+    upgrades::PlayerStats,
+    weapons::Weapon,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+// Enemies within this distance of the player are hit by an attack
+pub const WEAPON_RADIUS: f32 = 200.0;
+// Half the opening of the attack, either side of the aim
+const ATTACK_HALF_ANGLE: f32 = FRAC_PI_2;
+const ATTACK_RING_SEGMENTS: usize = 24;
+// Starting value, upgraded over the course of a run through `PlayerStats`
+pub const DAMAGE: f32 = 5.0;
+pub const CRIT_CHANCE: f32 = 0.05;
+pub const CRIT_MULTIPLIER: f32 = 2.0;
+// Weapon hits land up to this fraction harder or softer than the weapon's damage
+const DAMAGE_VARIANCE: f32 = 0.15;
+// Damage the player takes each time an enemy runs into them
+pub const ENEMY_CONTACT_DAMAGE: f32 = 1.0;
+// Impulse an enemy receives when hit by an attack
+const ATTACK_KNOCKBACK: f32 = 250.0;
+// Seconds between two attacks with the pulse
+pub const ATTACK_COOLDOWN: f32 = 0.4;
+const ATTACK_RING_DURATION: f32 = 0.2;
+const ATTACK_RING_THICKNESS: f32 = 3.0;
+const ATTACK_RING_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+// Dying enemies blow up, hurting the enemies around them the closer they are
+const EXPLOSION_RADIUS: f32 = 90.0;
+const EXPLOSION_DAMAGE: f32 = 4.0;
+const EXPLOSION_KNOCKBACK: f32 = 200.0;
+const HIT_FLASH_DURATION: f32 = 0.1;
+const HIT_FLASH_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+// Seconds the player can't be hurt again after taking damage, blinking meanwhile
+const PLAYER_INVULNERABILITY: f32 = 1.0;
+const BLINK_INTERVAL: f32 = 0.1;
+
+pub struct CombatPlugin;
+
+// Sent whenever the player swings their weapon
+pub struct AttackEvent {
+    pub player: Entity,
+    pub origin: Vec2,
+    // Normalized, the middle of the swing
+    pub direction: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Component)]
+pub struct Hp {
+    pub current: i32,
+    pub max: i32,
+}
+
+// Shared by every weapon, each starting it with its own duration when fired
+#[derive(Component)]
+pub struct AttackCooldown(pub Timer);
+
+impl Default for AttackCooldown {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(ATTACK_COOLDOWN, false);
+        // The first attack of a run is available straight away
+        timer.tick(Duration::from_secs_f32(ATTACK_COOLDOWN));
+        Self(timer)
+    }
+}
+
+impl AttackCooldown {
+    pub fn start(&mut self, seconds: f32) {
+        self.0 = Timer::from_seconds(seconds, false);
+    }
+}
+
+#[derive(Component)]
+struct AttackRing(Timer);
+
+impl Default for AttackRing {
+    fn default() -> Self {
+        Self(Timer::from_seconds(ATTACK_RING_DURATION, false))
+    }
+}
+
+// Tints an enemy for a moment after it has been hit
+#[derive(Component)]
+pub struct HitFlash(Timer);
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self(Timer::from_seconds(HIT_FLASH_DURATION, false))
+    }
+}
+
+// Keeps the player from being hurt, either right after a hit or while dashing
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+
+impl Invulnerable {
+    pub fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, false))
+    }
+
+    // Makes sure it lasts at least `seconds` longer, without cutting it short
+    pub fn extend(&mut self, seconds: f32) {
+        if self.0.duration().as_secs_f32() - self.0.elapsed_secs() < seconds {
+            *self = Invulnerable::new(seconds);
+        }
+    }
+}
+
+// The damaging part of an explosion, spawned on its own as the sprite may be gone before the next step.
+// Enemies it kills explode in turn on a later step, so a chain reaction spreads step by step.
+#[derive(Component)]
+pub struct ExplosionDamage {
+    pub position: Vec2,
+    pub radius: f32,
+    pub damage: f32,
+    // A frame may run several steps before the despawn is applied, it must only hit once
+    detonated: bool,
+}
+
+impl ExplosionDamage {
+    pub fn new(position: Vec2, radius: f32, damage: f32) -> Self {
+        Self { position, radius, damage, detonated: false }
+    }
+}
+
+// Sent whenever a player loses health, from whatever source, carrying the player
+pub struct PlayerHurtEvent(pub Entity);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DamageKind {
+    // The player's weapon
+    Melee,
+    // An enemy running into a player
+    Contact,
+    Projectile,
+    // Flung into a wall, or into another enemy
+    Impact,
+    Explosion,
+    Burn,
+    // Saws and electric fences in the arena
+    Hazard,
+}
+
+// Asks for `target` to lose `amount` health, resolved in `apply_damage`
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: i32,
+    // Whoever dealt it, if anyone in particular
+    pub source: Option<Entity>,
+    pub kind: DamageKind,
+}
+
+// Sent once an enemy has died, its score and drops are handed out in `loot`
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub kind: EnemyKind,
+    pub position: Vec3,
+    pub score: i32,
+}
+
+// Something with health that isn't fighting the players, such as the escort payload or an obstacle. It still
+// flashes and wears down when hit, but the hit isn't one the players landed: no damage number, no hit sound,
+// and no run stats
+#[derive(Component)]
+pub struct Bystander;
+
+// Multiplies the damage of each listed kind its bearer takes, anything else is taken in full
+#[derive(Component, Default)]
+pub struct Resistances(pub HashMap<DamageKind, f32>);
+
+impl Resistances {
+    fn scale(&self, kind: DamageKind, amount: i32) -> i32 {
+        match self.0.get(&kind) {
+            Some(factor) => (amount as f32 * factor).ceil() as i32,
+            None => amount,
+        }
+    }
+}
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AttackEvent>()
+            .add_event::<PlayerHurtEvent>()
+            .add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(trigger_attack.before(resolve_attacks))
+                .with_system(resolve_attacks.before(integrate_forces))
+                .with_system(fade_attack_rings)
+                .with_system(explosion_damage.after(resolve_attacks).before(integrate_forces))
+                .with_system(
+                    apply_damage
+                        .after(resolve_attacks)
+                        .after(explosion_damage)
+                        .after(check_for_collisions)
+                        .after(check_for_enemy_collisions),
+                )
+                .with_system(flash_hit_enemies.after(apply_damage))
+                .with_system(enemy_death.after(apply_damage))
+                .with_system(blink_invulnerable_player)
+                .with_system(player_death.after(apply_damage)),
+        );
+    }
+}
+
+// Ticks every weapon's cooldown, and swings the pulse. The other weapons fire in `weapons`.
+pub fn trigger_attack(
+    mut player_query: Query<(Entity, &ActionState, &Aim, &Transform, &mut AttackCooldown, &Weapon), With<Player>>,
+    mut attack_events: EventWriter<AttackEvent>,
+)
+{
+    for (player, actions, aim, player_transform, mut cooldown, weapon) in &mut player_query {
+        cooldown.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if *weapon != Weapon::Pulse || !cooldown.0.finished() || !actions.just_pressed(Action::Attack) {
+            continue;
+        }
+
+        cooldown.start(ATTACK_COOLDOWN);
+        attack_events.send(AttackEvent {
+            player,
+            origin: player_transform.translation.truncate(),
+            direction: aim.0,
+            radius: WEAPON_RADIUS,
+        });
+    }
+}
+
+fn resolve_attacks(
+    mut commands: Commands,
+    mut attack_events: EventReader<AttackEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    stats: Res<PlayerStats>,
+    arena: Res<Arena>,
+    spatial_hash: Res<SpatialHash>,
+    mut enemy_query: Query<(&Transform, &Hp, &mut Forces, Entity), (With<Enemy>, Without<Player>)>,
+    spawner_query: Query<(Entity, &Transform), With<Spawner>>,
+)
+{
+    for attack in attack_events.iter() {
+        // The arc shows how far the attack reached, built along x and turned towards the aim
+        let points = (0..=ATTACK_RING_SEGMENTS)
+            .map(|segment| {
+                let angle = -ATTACK_HALF_ANGLE + 2.0 * ATTACK_HALF_ANGLE * segment as f32 / ATTACK_RING_SEGMENTS as f32;
+                Vec2::new(angle.cos(), angle.sin()) * attack.radius
+            })
+            .collect();
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Polygon { points, closed: false },
+                DrawMode::Stroke(StrokeMode::new(ATTACK_RING_COLOR, ATTACK_RING_THICKNESS)),
+                Transform {
+                    translation: attack.origin.extend(1.0),
+                    rotation: Quat::from_rotation_z(attack.direction.y.atan2(attack.direction.x)),
+                    ..default()
+                },
+            ))
+            .insert(AttackRing::default())
+            .insert(DespawnOnStateExit(GameState::Playing));
+
+        for nearby in spatial_hash.query_radius(attack.origin, attack.radius) {
+            let (enemy_transform, enemy_health, mut enemy_forces, entity) = match enemy_query.get_mut(nearby) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+            let enemy_position = enemy_transform.translation.truncate();
+            let offset = enemy_position - attack.origin;
+            if offset.length() > attack.radius || enemy_health.current <= 0 {
+                continue;
+            }
+            // Enemies right on top of the player are hit whichever way they face
+            if offset != Vec2::ZERO && attack.direction.angle_between(offset).abs() > ATTACK_HALF_ANGLE {
+                continue;
+            }
+            // Obstacles shield whatever is behind them
+            if !arena.line_of_sight(attack.origin, enemy_position) {
+                continue;
+            }
+
+            damage_events.send(DamageEvent {
+                target: entity,
+                amount: stats.weapon_damage as i32,
+                source: Some(attack.player),
+                kind: DamageKind::Melee,
+            });
+            enemy_forces.add_impulse((enemy_position - attack.origin).normalize_or_zero() * ATTACK_KNOCKBACK);
+        }
+
+        // Spawners aren't in the spatial hash, and there are only ever a few
+        for (entity, transform) in &spawner_query {
+            let offset = transform.translation.truncate() - attack.origin;
+            // Reached as soon as the arc touches their edge
+            if offset.length() > attack.radius + transform.scale.x / 2.0 {
+                continue;
+            }
+            if offset != Vec2::ZERO && attack.direction.angle_between(offset).abs() > ATTACK_HALF_ANGLE {
+                continue;
+            }
+            damage_events.send(DamageEvent {
+                target: entity,
+                amount: stats.weapon_damage as i32,
+                source: Some(attack.player),
+                kind: DamageKind::Melee,
+            });
+        }
+    }
+}
+
+fn explosion_damage(
+    mut commands: Commands,
+    mut damage_events: EventWriter<DamageEvent>,
+    spatial_hash: Res<SpatialHash>,
+    mut explosion_query: Query<(Entity, &mut ExplosionDamage)>,
+    mut enemy_query: Query<(&Transform, &Hp, &mut Forces), With<Enemy>>,
+    spawner_query: Query<(Entity, &Transform), With<Spawner>>,
+)
+{
+    for (explosion_entity, mut explosion) in &mut explosion_query {
+        if explosion.detonated {
+            continue;
+        }
+        explosion.detonated = true;
+        commands.entity(explosion_entity).despawn();
+
+        for entity in spatial_hash.query_radius(explosion.position, explosion.radius) {
+            let (transform, hp, mut forces) = match enemy_query.get_mut(entity) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+            // Whatever is already dying, the one that exploded included, is left alone
+            if hp.current <= 0 {
+                continue;
+            }
+            let offset = transform.translation.truncate() - explosion.position;
+            let distance = offset.length();
+            if distance > explosion.radius {
+                continue;
+            }
+
+            let falloff = 1.0 - distance / explosion.radius;
+            let damage = (explosion.damage * falloff).ceil() as i32;
+            if damage <= 0 {
+                continue;
+            }
+            damage_events.send(DamageEvent { target: entity, amount: damage, source: None, kind: DamageKind::Explosion });
+            forces.add_impulse(offset.normalize_or_zero() * EXPLOSION_KNOCKBACK * falloff);
+        }
+
+        for (entity, transform) in &spawner_query {
+            let distance = transform.translation.truncate().distance(explosion.position);
+            if distance > explosion.radius {
+                continue;
+            }
+            let damage = (explosion.damage * (1.0 - distance / explosion.radius)).ceil() as i32;
+            if damage > 0 {
+                damage_events.send(DamageEvent { target: entity, amount: damage, source: None, kind: DamageKind::Explosion });
+            }
+        }
+    }
+}
+
+// Enemies can be worn down by several sources, they all die here
+pub fn enemy_death(
+    mut commands: Commands,
+    mut death_events: EventWriter<DeathEvent>,
+    settings: Res<JuiceSettings>,
+    mut shake: ResMut<ScreenShake>,
+    mut hit_stop: ResMut<HitStop>,
+    enemy_query: Query<(Entity, &Hp, &Transform, &ScoreValue, &EnemyKind), With<Enemy>>,
+)
+{
+    for (entity, hp, transform, score, kind) in &enemy_query {
+        if hp.current > 0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        death_events.send(DeathEvent { entity, kind: *kind, position: transform.translation, score: score.0 });
+
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+        commands
+            .spawn()
+            .insert(ExplosionDamage::new(transform.translation.truncate(), EXPLOSION_RADIUS, EXPLOSION_DAMAGE))
+            .insert(DespawnOnStateExit(GameState::Playing));
+        shake.add_trauma(ENEMY_DEATH_TRAUMA);
+        hit_stop.trigger(&settings);
+    }
+}
+
+fn fade_attack_rings(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut AttackRing, &mut DrawMode)>,
+)
+{
+    for (entity, mut ring, mut draw_mode) in &mut query {
+        ring.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if ring.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let DrawMode::Stroke(ref mut stroke) = *draw_mode {
+            stroke.color.set_a(ATTACK_RING_COLOR.a() * ring.0.percent_left());
+        }
+    }
+}
+
+fn flash_hit_enemies(
+    mut commands: Commands,
+    // Enemies are animated from a sprite sheet, the boss is a plain sprite
+    mut query: Query<(Entity, &mut HitFlash, AnyOf<(&mut Sprite, &mut TextureAtlasSprite)>, &Hp, Option<&Tint>)>,
+)
+{
+    for (entity, mut flash, (sprite, atlas_sprite), hp, tint) in &mut query {
+        flash.0.tick(Duration::from_secs_f32(TIME_STEP));
+        let color = if flash.0.finished() {
+            commands.entity(entity).remove::<HitFlash>();
+            // Damaged enemies fade out as they lose health
+            let mut color = tint.map_or(Color::WHITE, |tint| tint.0);
+            color.set_a(hp.current as f32 / hp.max as f32);
+            color
+        } else {
+            HIT_FLASH_COLOR
+        };
+
+        if let Some(mut sprite) = sprite {
+            sprite.color = color;
+        }
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color = color;
+        }
+    }
+}
+
+// Applies resistances, lets any shield soak up what it can and takes the rest off health. Players are then invulnerable for a moment,
+// enemies flash and show the damage. Weapon hits vary a little, and now and then land a critical hit. Deaths are left to `enemy_death` and `player_death`.
+pub fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut damage_number_events: EventWriter<DamageNumberEvent>,
+    mut player_hurt_events: EventWriter<PlayerHurtEvent>,
+    mut player_collision_events: EventWriter<PlayerCollisionEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    stats: Res<PlayerStats>,
+    mut rng: ResMut<GameRng>,
+    mut shake: ResMut<ScreenShake>,
+    mut run_stats: ResMut<RunStats>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut query: Query<(
+        &mut Hp,
+        &Transform,
+        Option<&Resistances>,
+        Option<&Player>,
+        Option<&Invulnerable>,
+        Option<&Boss>,
+        Option<&mut Knockback>,
+        Option<&mut Shield>,
+        Option<&Bystander>,
+    )>,
+)
+{
+    // Several hits at once still only hurt a player once, their invulnerability only starts after this step
+    let mut hurt_players = HashSet::new();
+    // However many enemies are hit in a step, one hit sound is enough, heard from the first of them
+    let mut hit_sound: Option<(Sfx, Vec2)> = None;
+
+    for event in damage_events.iter() {
+        let source_position = event.source.and_then(|source| query.get(source).ok()).map(|(_, transform, ..)| transform.translation);
+        let (mut hp, transform, resistances, player, invulnerable, boss, knockback, shield, bystander) = match query.get_mut(event.target) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+        // Already dying
+        if hp.current <= 0 {
+            continue;
+        }
+        let mut amount = resistances.map_or(event.amount, |resistances| resistances.scale(event.kind, event.amount));
+        if amount <= 0 {
+            continue;
+        }
+
+        let mut crit = false;
+        if event.kind == DamageKind::Melee && player.is_none() {
+            let variance = rng.0.gen_range(1.0 - DAMAGE_VARIANCE..=1.0 + DAMAGE_VARIANCE);
+            crit = rng.0.gen_bool(stats.crit_chance.clamp(0.0, 1.0) as f64);
+            let multiplier = if crit { stats.crit_multiplier } else { 1.0 };
+            amount = ((amount as f32 * variance * multiplier).round() as i32).max(1);
+        }
+
+        if player.is_some() {
+            if invulnerable.is_some() || !hurt_players.insert(event.target) {
+                continue;
+            }
+            // Even the easiest difficulty never shrugs a hit off entirely
+            let amount = ((amount as f32 * difficulty.enemy_damage()).round() as i32).max(1);
+            // A hit the shield soaks up entirely still leaves the player invulnerable for a moment
+            let amount = shield.map_or(amount, |mut shield| shield.absorb(amount));
+            if amount == 0 {
+                commands.entity(event.target).insert(Invulnerable::new(PLAYER_INVULNERABILITY));
+                continue;
+            }
+            hp.current -= amount;
+            run_stats.damage_taken += amount;
+            shake.add_trauma(PLAYER_DAMAGE_TRAUMA);
+            commands.entity(event.target).insert(Invulnerable::new(PLAYER_INVULNERABILITY));
+            player_hurt_events.send(PlayerHurtEvent(event.target));
+            // Only for the hits that land, an enemy leaning on an invulnerable player stays quiet
+            if event.kind == DamageKind::Contact {
+                player_collision_events.send(PlayerCollisionEvent(event.target));
+            }
+            // Thrown away from whatever hit them, so they get a moment to get clear
+            if let (Some(mut knockback), Some(source_position)) = (knockback, source_position) {
+                let direction = (transform.translation - source_position).truncate().normalize_or_zero();
+                knockback.0 = direction * config.player_hit_speed;
+            }
+        } else {
+            let amount = shield.map_or(amount, |mut shield| shield.absorb(amount));
+            if amount == 0 {
+                commands.entity(event.target).insert(HitFlash::default());
+                continue;
+            }
+            hp.current -= amount;
+            commands.entity(event.target).insert(HitFlash::default());
+            if bystander.is_some() {
+                continue;
+            }
+            run_stats.damage_dealt += amount;
+            damage_number_events.send(DamageNumberEvent { position: transform.translation, amount, crit });
+            // A crit is what gets heard, if any of the hits was one
+            if crit || hit_sound.is_none() {
+                let position = hit_sound.map_or(transform.translation.truncate(), |(_, position)| position);
+                hit_sound = Some((if crit { Sfx::Crit } else { Sfx::Hit }, position));
+            }
+            if boss.is_some() && event.kind == DamageKind::Melee {
+                shake.add_trauma(BOSS_HIT_TRAUMA);
+            }
+        }
+    }
+
+    if let Some((sfx, position)) = hit_sound {
+        sfx_events.send(PlaySfx::new(sfx).at(position));
+    }
+}
+
+fn blink_invulnerable_player(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+)
+{
+    for (entity, mut invulnerable, mut visibility) in &mut query {
+        invulnerable.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if invulnerable.0.finished() {
+            visibility.is_visible = true;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else {
+            visibility.is_visible = (invulnerable.0.elapsed_secs() / BLINK_INTERVAL) as u32 % 2 == 1;
+        }
+    }
+}
+
+// In co-op the run goes on while anyone is still standing
+fn player_death(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Hp, &Transform), With<Player>>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    let mut survivors = 0;
+    for (entity, hp, transform) in &player_query {
+        if hp.current > 0 {
+            survivors += 1;
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+    }
+
+    if survivors == 0 && !player_query.is_empty() {
+        let _ = state.push(GameState::GameOver);
+    }
+}
@@ -0,0 +1,131 @@
+//! Kill combos: every kill landed shortly after the last one raises a multiplier on the score they award.
+//! Let the window run out and the combo breaks, back to x1.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    sound::{PlaySfx, Sfx},
+    hud::HUD_TEXT_PADDING,
+    FixedUpdate, GameState, SCORE_COLOR, TIME_STEP,
+};
+
+// Seconds after a kill the next one has to land in to keep the combo going
+const COMBO_WINDOW: f32 = 2.0;
+// Each kill in a combo adds one to the multiplier, up to this
+const MAX_COMBO_MULTIPLIER: i32 = 8;
+// How much higher the kill sound gets with each step of the multiplier
+const KILL_PITCH_STEP: f32 = 0.08;
+const COMBO_FONT_SIZE: f32 = 30.0;
+// Just below the scoreboard
+const COMBO_TEXT_TOP: Val = Val::Px(50.0);
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Combo>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_combo)
+                    .with_system(spawn_combo_text),
+            )
+            .add_system_set(SystemSet::new().with_run_criteria(FixedUpdate).with_system(decay_combo))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_combo_text));
+    }
+}
+
+// Kills in the running combo, and the time left to extend it
+pub struct Combo {
+    kills: i32,
+    window: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        let mut window = Timer::from_seconds(COMBO_WINDOW, false);
+        window.tick(Duration::from_secs_f32(COMBO_WINDOW));
+        Self { kills: 0, window }
+    }
+}
+
+impl Combo {
+    // Counts a kill, and returns the multiplier it scores with
+    pub fn register_kill(&mut self) -> i32 {
+        self.kills += 1;
+        self.window.reset();
+        self.multiplier()
+    }
+
+    pub fn multiplier(&self) -> i32 {
+        self.kills.clamp(1, MAX_COMBO_MULTIPLIER)
+    }
+
+    // Kills ring out higher the longer the combo runs
+    pub fn kill_pitch(&self) -> f32 {
+        1.0 + (self.multiplier() - 1) as f32 * KILL_PITCH_STEP
+    }
+}
+
+#[derive(Component)]
+struct ComboText;
+
+fn reset_combo(mut combo: ResMut<Combo>)
+{
+    *combo = Combo::default();
+}
+
+fn decay_combo(mut combo: ResMut<Combo>, mut sfx_events: EventWriter<PlaySfx>)
+{
+    if combo.kills == 0 {
+        return;
+    }
+
+    combo.window.tick(Duration::from_secs_f32(TIME_STEP));
+    if combo.window.finished() {
+        // A single kill was never much of a combo to lose
+        if combo.multiplier() > 1 {
+            sfx_events.send(PlaySfx::new(Sfx::ComboBreak));
+        }
+        combo.kills = 0;
+    }
+}
+
+// Only shown while the multiplier is above x1
+fn spawn_combo_text(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: COMBO_FONT_SIZE,
+                    color: SCORE_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: COMBO_TEXT_TOP,
+                    left: HUD_TEXT_PADDING,
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(ComboText)
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+fn update_combo_text(combo: Res<Combo>, mut query: Query<&mut Text, With<ComboText>>)
+{
+    for mut text in &mut query {
+        let multiplier = combo.multiplier();
+        text.sections[0].value = if combo.kills > 1 { format!("x{}", multiplier) } else { String::new() };
+        // Fades as the window runs out, a hint to find the next kill
+        text.sections[0].style.color.set_a(combo.window.percent_left().max(0.3));
+    }
+}
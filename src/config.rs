@@ -0,0 +1,94 @@
+//! Gameplay tuning read from `assets/data/game.config.ron`. Changes to the file are picked up
+//! while the game runs, anything missing from it keeps the default below.
+
+use bevy::{prelude::*, reflect::TypeUuid};
+use serde::Deserialize;
+
+use crate::data::RonAsset;
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "b3a1f0d2-6c4e-4e8a-9d17-0f5e2c8a7b64"]
+#[serde(default)]
+pub struct GameConfig {
+    pub player_speed: f32,
+    // Impulse an enemy receives when it runs into the player
+    pub player_knockback: f32,
+    // Speed the player is thrown back with by whatever hurt them
+    pub player_hit_speed: f32,
+
+    // Energy is drained per second while the magnet is held, and regenerated per second otherwise
+    pub magnet_energy: f32,
+    pub magnet_energy_drain: f32,
+    pub magnet_energy_regen: f32,
+    // Once depleted, the magnet stays disabled until this fraction of energy is back
+    pub magnet_reenable_fraction: f32,
+    // Scales the magnet force stat into the force applied to enemies
+    pub magnet_force_scale: f32,
+    // Caps how much stronger the magnet gets for enemies close to the player
+    pub magnet_max_falloff: f32,
+
+    // Enemies colliding faster than this hurt each other, harder the faster they hit
+    pub enemy_smash_speed: f32,
+    pub enemy_smash_damage_scale: f32,
+    // Same for enemies slammed into a wall
+    pub wall_slam_speed: f32,
+    pub wall_slam_damage_scale: f32,
+
+    pub spark_count: usize,
+    pub spark_size: f32,
+    pub spark_speed: f32,
+    pub spark_lifetime: f32,
+    pub spark_color: Color,
+
+    // Particles showing the magnet's field while it is held
+    pub magnet_particle_rate: f32,
+    pub magnet_particle_speed: f32,
+    pub magnet_particle_size: f32,
+
+    // Magnet particles and the cone are tinted with these while pulling or pushing
+    pub pull_color: Color,
+    pub push_color: Color,
+    pub magnet_energy_color: Color,
+    pub magnet_depleted_color: Color,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            player_speed: 300.0,
+            player_knockback: 300.0,
+            player_hit_speed: 450.0,
+
+            magnet_energy: 100.0,
+            magnet_energy_drain: 40.0,
+            magnet_energy_regen: 25.0,
+            magnet_reenable_fraction: 0.3,
+            magnet_force_scale: 4.0,
+            magnet_max_falloff: 5.0,
+
+            enemy_smash_speed: 250.0,
+            enemy_smash_damage_scale: 0.02,
+            wall_slam_speed: 250.0,
+            wall_slam_damage_scale: 0.03,
+
+            spark_count: 6,
+            spark_size: 3.0,
+            spark_speed: 200.0,
+            spark_lifetime: 0.3,
+            spark_color: Color::rgb(1.0, 0.9, 0.4),
+
+            magnet_particle_rate: 80.0,
+            magnet_particle_speed: 300.0,
+            magnet_particle_size: 2.0,
+
+            pull_color: Color::rgb(1.0, 0.5, 0.5),
+            push_color: Color::rgb(0.5, 1.0, 0.5),
+            magnet_energy_color: Color::rgb(0.5, 0.5, 1.0),
+            magnet_depleted_color: Color::rgb(0.4, 0.4, 0.4),
+        }
+    }
+}
+
+impl RonAsset for GameConfig {
+    const EXTENSION: &'static str = "config.ron";
+}
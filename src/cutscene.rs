@@ -0,0 +1,134 @@
+//! Short camera cutscenes taking over from the follow camera, such as the introduction of a boss:
+//! the camera pans over to it while its name is shown, holds for a moment, then pans back to the players.
+//! The run is frozen for as long as a cutscene plays.
+
+use bevy::prelude::*;
+
+use crate::{
+    boss::{Boss, BOSS_NAME, BOSS_TITLE},
+    locale::Locale,
+    tween::{Ease, TextColor, Tween},
+    ui::{despawn_screen, menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE},
+    GameState, SCORE_COLOR, TEXT_COLOR,
+};
+
+// Seconds spent panning over, looking at the target, and panning back
+const PAN_DURATION: f32 = 0.6;
+const HOLD_DURATION: f32 = 1.4;
+const SHOT_DURATION: f32 = PAN_DURATION * 2.0 + HOLD_DURATION;
+
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraDirector>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_camera_director)
+                    .with_system(despawn_screen::<CutsceneBanner>),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(introduce_bosses)
+                    .with_system(play_cutscene.after(introduce_bosses)),
+            );
+    }
+}
+
+// Takes the camera away from the players while a shot is playing
+#[derive(Default)]
+pub struct CameraDirector {
+    shot: Option<Shot>,
+}
+
+struct Shot {
+    target: Vec2,
+    elapsed: f32,
+}
+
+impl CameraDirector {
+    pub fn active(&self) -> bool {
+        self.shot.is_some()
+    }
+
+    // Starts panning over to `target`, unless a shot is already playing
+    pub fn focus_on(&mut self, target: Vec2) -> bool {
+        if self.active() {
+            return false;
+        }
+        self.shot = Some(Shot { target, elapsed: 0.0 });
+        true
+    }
+
+    // Where the camera should look, given where following the players would have it look
+    pub fn framing(&self, follow: Vec2) -> Vec2 {
+        let shot = match &self.shot {
+            Some(shot) => shot,
+            None => return follow,
+        };
+
+        let panned = (shot.elapsed.min(SHOT_DURATION - shot.elapsed) / PAN_DURATION).clamp(0.0, 1.0);
+        // Smoothstep, so the camera eases in and out of each pan
+        let blend = panned * panned * (3.0 - 2.0 * panned);
+        follow.lerp(shot.target, blend)
+    }
+}
+
+// The name shown while the camera looks at a boss
+#[derive(Component)]
+struct CutsceneBanner;
+
+fn reset_camera_director(mut director: ResMut<CameraDirector>)
+{
+    *director = CameraDirector::default();
+}
+
+fn introduce_bosses(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut director: ResMut<CameraDirector>,
+    boss_query: Query<&Transform, Added<Boss>>,
+)
+{
+    for transform in &boss_query {
+        if !director.focus_on(transform.translation.truncate()) {
+            continue;
+        }
+
+        // The banner fades in while the camera pans over
+        spawn_screen(&mut commands, CutsceneBanner, Color::NONE, |parent| {
+            for (key, font_size, color) in [(BOSS_NAME, MENU_TITLE_FONT_SIZE, SCORE_COLOR), (BOSS_TITLE, MENU_TEXT_FONT_SIZE, TEXT_COLOR)] {
+                let mut hidden = color;
+                hidden.set_a(0.0);
+                parent
+                    .spawn_bundle(menu_text(&asset_server, locale.get(key), font_size, hidden))
+                    .insert(Tween::<TextColor>::new(hidden, color, PAN_DURATION, Ease::QuadOut));
+            }
+        });
+    }
+}
+
+// Cutscenes play in real time, the fixed timestep is held back meanwhile
+fn play_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut director: ResMut<CameraDirector>,
+    banner_query: Query<Entity, With<CutsceneBanner>>,
+)
+{
+    let finished = match director.shot.as_mut() {
+        Some(shot) => {
+            shot.elapsed += time.delta_seconds();
+            shot.elapsed >= SHOT_DURATION
+        }
+        None => return,
+    };
+
+    if finished {
+        director.shot = None;
+        for entity in &banner_query {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Floating numbers showing how much damage a hit dealt, drifting up from where it landed and fading out.
+//! The text entities are pooled: finished numbers are hidden and reused rather than despawned.
+//! Critical hits stand out, bigger and in their own color.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    pool::{clear_pool, Pool},
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const DAMAGE_NUMBER_FONT_SIZE: f32 = 24.0;
+const DAMAGE_NUMBER_COLOR: Color = Color::rgb(1.0, 0.9, 0.4);
+const CRIT_FONT_SIZE: f32 = 36.0;
+const CRIT_COLOR: Color = Color::rgb(1.0, 0.35, 0.2);
+// Seconds a number stays up, and how fast it rises meanwhile
+const DAMAGE_NUMBER_LIFETIME: f32 = 0.7;
+const DAMAGE_NUMBER_RISE_SPEED: f32 = 60.0;
+// Numbers are scattered sideways a little so simultaneous hits don't overlap
+const DAMAGE_NUMBER_SPREAD: f32 = 10.0;
+
+pub struct DamageNumberPlugin;
+
+impl Plugin for DamageNumberPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageNumberEvent>()
+            .init_resource::<Pool<DamageNumber>>()
+            .add_startup_system(load_damage_number_font)
+            // The pooled entities went with the last run when it left `Playing`
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(clear_pool::<DamageNumber>))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(spawn_damage_numbers)
+                    .with_system(update_damage_numbers.before(spawn_damage_numbers)),
+            );
+    }
+}
+
+// Shows `amount` rising from `position`
+pub struct DamageNumberEvent {
+    pub position: Vec3,
+    pub amount: i32,
+    pub crit: bool,
+}
+
+#[derive(Component)]
+struct DamageNumber(Timer);
+
+struct DamageNumberFont(Handle<Font>);
+
+fn load_damage_number_font(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.insert_resource(DamageNumberFont(asset_server.load("fonts/FiraMono-Medium.ttf")));
+}
+
+fn spawn_damage_numbers(
+    mut commands: Commands,
+    font: Res<DamageNumberFont>,
+    mut pool: ResMut<Pool<DamageNumber>>,
+    mut damage_number_events: EventReader<DamageNumberEvent>,
+    mut query: Query<(&mut Text, &mut Transform, &mut Visibility, &mut DamageNumber)>,
+)
+{
+    for (index, event) in damage_number_events.iter().enumerate() {
+        if event.amount <= 0 {
+            continue;
+        }
+
+        let offset = ((index % 3) as f32 - 1.0) * DAMAGE_NUMBER_SPREAD;
+        let translation = Vec3::new(event.position.x + offset, event.position.y, 5.0);
+        let value = if event.crit { format!("{}!", event.amount) } else { event.amount.to_string() };
+        let (font_size, color) = if event.crit {
+            (CRIT_FONT_SIZE, CRIT_COLOR)
+        } else {
+            (DAMAGE_NUMBER_FONT_SIZE, DAMAGE_NUMBER_COLOR)
+        };
+
+        let reused = pool.acquire().and_then(|entity| query.get_mut(entity).ok());
+        match reused {
+            Some((mut text, mut transform, mut visibility, mut damage_number)) => {
+                text.sections[0].value = value;
+                text.sections[0].style.font_size = font_size;
+                text.sections[0].style.color = color;
+                transform.translation = translation;
+                visibility.is_visible = true;
+                damage_number.0.reset();
+            }
+            None => {
+                commands
+                    .spawn_bundle(Text2dBundle {
+                        text: Text::from_section(
+                            value,
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size,
+                                color,
+                            },
+                        )
+                            .with_alignment(TextAlignment::CENTER),
+                        transform: Transform::from_translation(translation),
+                        ..default()
+                    })
+                    .insert(DamageNumber(Timer::from_seconds(DAMAGE_NUMBER_LIFETIME, false)))
+                    .insert(DespawnOnStateExit(GameState::Playing));
+            }
+        }
+    }
+}
+
+fn update_damage_numbers(
+    mut pool: ResMut<Pool<DamageNumber>>,
+    mut query: Query<(Entity, &mut Text, &mut Transform, &mut Visibility, &mut DamageNumber)>,
+)
+{
+    for (entity, mut text, mut transform, mut visibility, mut damage_number) in &mut query {
+        if !visibility.is_visible {
+            continue;
+        }
+
+        damage_number.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if damage_number.0.finished() {
+            visibility.is_visible = false;
+            pool.release(entity);
+            continue;
+        }
+
+        transform.translation.y += DAMAGE_NUMBER_RISE_SPEED * TIME_STEP;
+        text.sections[0].style.color.set_a(damage_number.0.percent_left());
+    }
+}
@@ -0,0 +1,359 @@
+//! Game config, enemy, wave, difficulty and loot tuning, and the handcrafted levels, loaded from RON files under `assets/data/`
+//! so they can be changed without recompiling. Each file is copied into a resource of the same type whenever it (re)loads.
+
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use rand::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    anomalies::Anomaly,
+    arena::Obstacle,
+    config::GameConfig,
+    enemy::EnemyKind,
+    hazards::HazardPlacement,
+    objectives::Objective,
+    pickups::Pickup,
+};
+
+const CONFIG_PATH: &str = "data/game.config.ron";
+const ENEMIES_PATH: &str = "data/enemies.ron";
+const WAVES_PATH: &str = "data/waves.ron";
+const DIFFICULTY_PATH: &str = "data/difficulty.ron";
+const LOOT_PATH: &str = "data/loot.ron";
+const LEVELS_PATH: &str = "data/levels.ron";
+
+pub struct DataPlugin;
+
+impl Plugin for DataPlugin {
+    fn build(&self, app: &mut App) {
+        // The config has defaults to fall back on, so unlike the tables it is usable before its file has loaded
+        app.init_resource::<GameConfig>()
+            .add_asset::<GameConfig>()
+            .add_asset::<EnemyTable>()
+            .add_asset::<WaveTable>()
+            .add_asset::<DifficultyCurve>()
+            .add_asset::<LootTable>()
+            .add_asset::<LevelTable>()
+            .init_asset_loader::<RonLoader<GameConfig>>()
+            .init_asset_loader::<RonLoader<EnemyTable>>()
+            .init_asset_loader::<RonLoader<WaveTable>>()
+            .init_asset_loader::<RonLoader<DifficultyCurve>>()
+            .init_asset_loader::<RonLoader<LootTable>>()
+            .init_asset_loader::<RonLoader<LevelTable>>()
+            .add_startup_system(load_data)
+            .add_system(sync_data::<GameConfig>)
+            .add_system(sync_data::<EnemyTable>)
+            .add_system(sync_data::<WaveTable>)
+            .add_system(sync_data::<DifficultyCurve>)
+            .add_system(sync_data::<LootTable>)
+            .add_system(sync_data::<LevelTable>);
+    }
+}
+
+// A RON file deserialized straight into an asset, told apart from other RON files by its extension
+pub trait RonAsset: TypeUuid + DeserializeOwned + Clone + Send + Sync + 'static {
+    const EXTENSION: &'static str;
+}
+
+struct RonLoader<T>(PhantomData<T>);
+
+impl<T> Default for RonLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: RonAsset> AssetLoader for RonLoader<T> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let asset = ron::de::from_bytes::<T>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&T::EXTENSION)
+    }
+}
+
+// Keeps the data file loaded for as long as the game runs
+struct DataHandle<T: RonAsset>(Handle<T>);
+
+#[derive(Clone, Deserialize)]
+pub struct EnemyDefinition {
+    // Health and speed at the start of a run, `DifficultyCurve` scales them up
+    pub health: f32,
+    pub speed: f32,
+    // Width and height of the sprite, also used for collisions
+    pub size: f32,
+    pub mass: f32,
+    // Sprite sheet with the frames of the hover animation side by side
+    pub sprite: String,
+    #[serde(default)]
+    pub frames: usize,
+    pub color: Color,
+    // Chance an enemy of this kind spawns neutral, unaffected by the magnet
+    #[serde(default)]
+    pub neutral_chance: f64,
+    // Shield soaking up damage before health, grown by `DifficultyCurve` like health. Most kinds go without.
+    #[serde(default)]
+    pub shield: f32,
+    // Score awarded for a kill
+    pub score: i32,
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "5c6f3b8e-0a61-4f0c-9a55-2d1f4e7b9c31"]
+pub struct EnemyTable {
+    chaser: EnemyDefinition,
+    orbiter: EnemyDefinition,
+    heavy: EnemyDefinition,
+    shooter: EnemyDefinition,
+}
+
+impl RonAsset for EnemyTable {
+    const EXTENSION: &'static str = "enemies.ron";
+}
+
+impl EnemyTable {
+    pub fn get(&self, kind: EnemyKind) -> &EnemyDefinition {
+        match kind {
+            EnemyKind::Chaser => &self.chaser,
+            EnemyKind::Orbiter => &self.orbiter,
+            EnemyKind::Heavy => &self.heavy,
+            EnemyKind::Shooter => &self.shooter,
+        }
+    }
+}
+
+// How often a kind of enemy shows up, starting from a given wave
+#[derive(Clone, Deserialize)]
+pub struct WaveEnemy {
+    pub kind: EnemyKind,
+    pub from_wave: u32,
+    pub weight: u32,
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "9e2d7a40-3b1c-4d8e-8f6a-71c0b5e2d4a9"]
+pub struct WaveTable {
+    // Enemies in the first wave, every later wave adds a few more
+    pub first_wave_enemy_count: u32,
+    pub extra_enemies_per_wave: u32,
+    // Breather between clearing a wave and the next one arriving, in seconds
+    pub delay: f32,
+    // Every this many waves a boss shows up instead of the usual enemies
+    pub boss_interval: u32,
+    // Boss health on the first wave, scaled up like everyone else's
+    pub boss_health: f32,
+    // Every this many waves spawners have to be destroyed instead, unless it is a boss wave. 0 never
+    #[serde(default)]
+    pub spawner_interval: u32,
+    #[serde(default)]
+    pub spawner_count: u32,
+    // Spawner health on the first wave, scaled up like the boss's
+    #[serde(default)]
+    pub spawner_health: f32,
+    // Every this many waves the payload has to be escorted across the arena. 0 never
+    #[serde(default)]
+    pub escort_interval: u32,
+    // Every this many waves the players have to hold out for `survive_time` seconds. 0 never
+    #[serde(default)]
+    pub survive_interval: u32,
+    #[serde(default)]
+    pub survive_time: f32,
+    pub enemies: Vec<WaveEnemy>,
+}
+
+impl RonAsset for WaveTable {
+    const EXTENSION: &'static str = "waves.ron";
+}
+
+impl WaveTable {
+    // Picks a kind for an enemy of the given wave, among those that show up by then
+    pub fn random_kind(&self, rng: &mut impl Rng, wave: u32) -> EnemyKind {
+        let available: Vec<&WaveEnemy> = self.enemies.iter().filter(|enemy| enemy.from_wave <= wave).collect();
+        available
+            .choose_weighted(rng, |enemy| enemy.weight)
+            .map(|enemy| enemy.kind)
+            .unwrap_or(EnemyKind::Chaser)
+    }
+}
+
+// How one stat grows: a fraction of its base value added per wave cleared and per minute played
+#[derive(Clone, Copy, Deserialize)]
+pub struct Growth {
+    pub per_wave: f32,
+    pub per_minute: f32,
+    // The multiplier never goes past this
+    pub max: f32,
+}
+
+impl Growth {
+    fn at(&self, waves_cleared: u32, minutes: f32) -> f32 {
+        (1.0 + waves_cleared as f32 * self.per_wave + minutes * self.per_minute).min(self.max)
+    }
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "b7d21c5e-6f3a-4e90-a1c8-3e5f0d9a7b42"]
+pub struct DifficultyCurve {
+    pub health: Growth,
+    pub speed: Growth,
+    pub spawn_rate: Growth,
+    pub magnet_resistance: Growth,
+}
+
+impl RonAsset for DifficultyCurve {
+    const EXTENSION: &'static str = "difficulty.ron";
+}
+
+impl DifficultyCurve {
+    // How tough things are on `wave` (0 outside of the wave mode), `elapsed` seconds into the run
+    pub fn at(&self, wave: u32, elapsed: f32) -> DifficultyScale {
+        let waves_cleared = wave.saturating_sub(1);
+        let minutes = elapsed / 60.0;
+        DifficultyScale {
+            health: self.health.at(waves_cleared, minutes),
+            speed: self.speed.at(waves_cleared, minutes),
+            spawn_rate: self.spawn_rate.at(waves_cleared, minutes),
+            magnet_resistance: self.magnet_resistance.at(waves_cleared, minutes),
+        }
+    }
+}
+
+// Multipliers on the base stats at one point of a run, 1 across the board at its start
+#[derive(Clone, Copy)]
+pub struct DifficultyScale {
+    pub health: f32,
+    pub speed: f32,
+    pub spawn_rate: f32,
+    pub magnet_resistance: f32,
+}
+
+// A pickup and how often it comes up among an enemy's drops
+#[derive(Clone, Deserialize)]
+pub struct LootDrop {
+    pub pickup: Pickup,
+    pub weight: u32,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct EnemyLoot {
+    // Chance a kill drops anything at all
+    pub chance: f64,
+    pub drops: Vec<LootDrop>,
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "e41a9c2d-58b7-4f36-9d0e-6a2c8f1b7e53"]
+pub struct LootTable {
+    chaser: EnemyLoot,
+    orbiter: EnemyLoot,
+    heavy: EnemyLoot,
+    shooter: EnemyLoot,
+}
+
+impl RonAsset for LootTable {
+    const EXTENSION: &'static str = "loot.ron";
+}
+
+impl LootTable {
+    pub fn get(&self, kind: EnemyKind) -> &EnemyLoot {
+        match kind {
+            EnemyKind::Chaser => &self.chaser,
+            EnemyKind::Orbiter => &self.orbiter,
+            EnemyKind::Heavy => &self.heavy,
+            EnemyKind::Shooter => &self.shooter,
+        }
+    }
+
+    // What a kill of the given kind drops, if anything, with the chance of a drop multiplied by `chance_scale`
+    pub fn roll(&self, rng: &mut impl Rng, kind: EnemyKind, chance_scale: f32) -> Option<Pickup> {
+        let loot = self.get(kind);
+        if !rng.gen_bool((loot.chance * chance_scale as f64).clamp(0.0, 1.0)) {
+            return None;
+        }
+        loot.drops.choose_weighted(rng, |drop| drop.weight).ok().map(|drop| drop.pickup)
+    }
+}
+
+// An arena laid out by hand rather than at random, and the wave it opens with
+#[derive(Clone, Deserialize)]
+pub struct LevelDefinition {
+    // Translated, as shown on the level select
+    pub name: String,
+    // Distance between the middles of the outer walls
+    pub size: Vec2,
+    // Interior walls, which can be knocked out like random obstacles
+    pub walls: Vec<Obstacle>,
+    #[serde(default)]
+    pub anomalies: Vec<Anomaly>,
+    #[serde(default)]
+    pub hazards: Vec<HazardPlacement>,
+    #[serde(default)]
+    pub spawners: Vec<Vec2>,
+    pub objective: Objective,
+    // Enemies it opens with besides what the spawners send out
+    #[serde(default)]
+    pub enemies: u32,
+    // Which wave it counts as, deciding which kinds show up and how tough they are
+    pub wave: u32,
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "3a8f5d17-c2e4-4b69-8d05-9f1e6b3c7a28"]
+pub struct LevelTable {
+    pub levels: Vec<LevelDefinition>,
+}
+
+impl RonAsset for LevelTable {
+    const EXTENSION: &'static str = "levels.ron";
+}
+
+fn load_data(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.insert_resource(DataHandle::<GameConfig>(asset_server.load(CONFIG_PATH)));
+    commands.insert_resource(DataHandle::<EnemyTable>(asset_server.load(ENEMIES_PATH)));
+    commands.insert_resource(DataHandle::<WaveTable>(asset_server.load(WAVES_PATH)));
+    commands.insert_resource(DataHandle::<DifficultyCurve>(asset_server.load(DIFFICULTY_PATH)));
+    commands.insert_resource(DataHandle::<LootTable>(asset_server.load(LOOT_PATH)));
+    commands.insert_resource(DataHandle::<LevelTable>(asset_server.load(LEVELS_PATH)));
+}
+
+// Copies the data into its resource whenever the file finishes loading or changes on disk
+fn sync_data<T: RonAsset>(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<T>>,
+    data_handle: Option<Res<DataHandle<T>>>,
+    assets: Res<Assets<T>>,
+)
+{
+    let data_handle = match data_handle {
+        Some(data_handle) => data_handle,
+        None => return,
+    };
+
+    for event in asset_events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } if *handle == data_handle.0 => {
+                if let Some(data) = assets.get(handle) {
+                    commands.insert_resource(data.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
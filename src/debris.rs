@@ -0,0 +1,196 @@
+//! Crates and scrap scattered around the arena at the start of a run. They are magnetic like anything else,
+//! so they can be pulled in and flung at enemies, hurting them by the same rules as being slammed into a wall.
+
+use bevy::{
+    prelude::*,
+    sprite::collide_aabb::{collide, Collision},
+};
+use rand::prelude::*;
+
+use crate::{
+    arena::{generate_arena, Arena, Obstacle},
+    cleanup::DespawnOnStateExit,
+    combat::{DamageEvent, DamageKind},
+    config::GameConfig,
+    enemy::{Enemy, ENEMY_MASS, SPAWN_SAFE_RADIUS},
+    fx::spawn_sparks,
+    levels::LoadingLevel,
+    magnet::{Magnetic, Polarity},
+    physics::{apply_velocity, Collider, Forces, Mass, PhysicsBundle, Velocity},
+    player::{player_spawn, Player},
+    rng::GameRng,
+    spatial::{update_spatial_hash, SpatialHash},
+    FixedUpdate, GameState, LevelEntity,
+};
+
+const CRATE_COUNT: usize = 4;
+const SCRAP_COUNT: usize = 8;
+
+pub struct DebrisPlugin;
+
+impl Plugin for DebrisPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(scatter_debris.after(generate_arena)))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(scatter_debris.after(generate_arena)))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(bounce_debris_off_walls.after(apply_velocity))
+                    .with_system(check_for_debris_hits.after(apply_velocity).after(update_spatial_hash)),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Debris {
+    // Big and heavy, slow to pull in but hitting hard
+    Crate,
+    // Small and light, quick to fling
+    Scrap,
+}
+
+impl Debris {
+    fn size(&self) -> Vec2 {
+        match self {
+            Debris::Crate => Vec2::new(26.0, 26.0),
+            Debris::Scrap => Vec2::new(14.0, 10.0),
+        }
+    }
+
+    fn mass(&self) -> f32 {
+        match self {
+            Debris::Crate => ENEMY_MASS * 2.0,
+            Debris::Scrap => ENEMY_MASS * 0.6,
+        }
+    }
+
+    fn drag(&self) -> f32 {
+        match self {
+            Debris::Crate => 2.0,
+            Debris::Scrap => 1.5,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Debris::Crate => Color::rgb(0.6, 0.45, 0.25),
+            Debris::Scrap => Color::rgb(0.55, 0.6, 0.65),
+        }
+    }
+}
+
+fn scatter_debris(mut commands: Commands, mut rng: ResMut<GameRng>, arena: Res<Arena>)
+{
+    let avoid = [player_spawn(&arena)];
+    let pieces = std::iter::repeat(Debris::Crate).take(CRATE_COUNT).chain(std::iter::repeat(Debris::Scrap).take(SCRAP_COUNT));
+    for debris in pieces {
+        let size = debris.size();
+        let position = arena.random_open_position(&mut rng.0, size / 2.0, &avoid, SPAWN_SAFE_RADIUS);
+        let rotation = Quat::from_rotation_z(rng.0.gen_range(0.0..std::f32::consts::TAU));
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: debris.color(),
+                    custom_size: Some(Vec2::new(1.0, 1.0)),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: position.extend(0.2),
+                    rotation,
+                    scale: size.extend(1.0),
+                },
+                ..default()
+            })
+            .insert(debris)
+            .insert_bundle(PhysicsBundle::new(Vec2::ZERO, debris.mass(), debris.drag()))
+            // Heavier pieces follow the magnet less readily
+            .insert(Magnetic::new(ENEMY_MASS / debris.mass(), Polarity::Positive))
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .insert(LevelEntity);
+    }
+}
+
+// Debris bounces off walls and obstacles like enemies do, without getting hurt
+fn bounce_debris_off_walls(
+    mut debris_query: Query<(&mut Transform, &mut Velocity), With<Debris>>,
+    wall_query: Query<&Transform, (With<Collider>, Without<Debris>, Without<Player>, Without<Enemy>)>,
+)
+{
+    for (mut transform, mut velocity) in &mut debris_query {
+        for wall_transform in &wall_query {
+            let collision = collide(
+                transform.translation,
+                transform.scale.truncate(),
+                wall_transform.translation,
+                wall_transform.scale.truncate(),
+            );
+            let collision = match collision {
+                Some(collision) => collision,
+                None => continue,
+            };
+
+            let wall = Obstacle {
+                center: wall_transform.translation.truncate(),
+                half_size: wall_transform.scale.truncate() / 2.0,
+            };
+            let push = wall.push_out(transform.translation.truncate(), transform.scale.truncate() / 2.0);
+            transform.translation += push.extend(0.0);
+
+            match collision {
+                Collision::Left if velocity.x > 0.0 => velocity.x = -velocity.x,
+                Collision::Right if velocity.x < 0.0 => velocity.x = -velocity.x,
+                Collision::Top if velocity.y < 0.0 => velocity.y = -velocity.y,
+                Collision::Bottom if velocity.y > 0.0 => velocity.y = -velocity.y,
+                _ => {}
+            }
+        }
+    }
+}
+
+// Debris flung into an enemy fast enough hurts it, by the wall slam rules scaled by how heavy the piece is,
+// and both bounce off each other
+fn check_for_debris_hits(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    spatial_hash: Res<SpatialHash>,
+    mut debris_query: Query<(Entity, &Transform, &Velocity, &mut Forces, &Mass), With<Debris>>,
+    mut enemy_query: Query<(&Transform, &Velocity, &mut Forces, &Mass), (With<Enemy>, Without<Debris>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+)
+{
+    for (debris_entity, debris_transform, debris_velocity, mut debris_forces, debris_mass) in &mut debris_query {
+        let center = debris_transform.translation.truncate();
+        let half_size = debris_transform.scale.truncate() / 2.0;
+        for enemy_entity in spatial_hash.query_aabb(center, half_size) {
+            let (enemy_transform, enemy_velocity, mut enemy_forces, enemy_mass) = match enemy_query.get_mut(enemy_entity) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+
+            let offset = (enemy_transform.translation - debris_transform.translation).truncate();
+            let distance = offset.length();
+            let contact_distance = (debris_transform.scale.x + enemy_transform.scale.x) / 2.0;
+            if distance >= contact_distance || distance <= 0.0 {
+                continue;
+            }
+            let normal = offset / distance;
+
+            // Only respond if they are moving toward each other
+            let impact_speed = (debris_velocity.0 - enemy_velocity.0).dot(normal);
+            if impact_speed <= 0.0 {
+                continue;
+            }
+
+            let impulse = 2.0 * impact_speed / (1.0 / debris_mass.0 + 1.0 / enemy_mass.0);
+            debris_forces.add_impulse(-normal * impulse);
+            enemy_forces.add_impulse(normal * impulse);
+
+            if impact_speed > config.wall_slam_speed {
+                let damage = ((impact_speed - config.wall_slam_speed) * config.wall_slam_damage_scale * debris_mass.0 / ENEMY_MASS).ceil() as i32;
+                damage_events.send(DamageEvent { target: enemy_entity, amount: damage, source: Some(debris_entity), kind: DamageKind::Impact });
+                spawn_sparks(&mut commands, &config, enemy_transform.translation);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! A developer overlay toggled with F3: frame rate, entity counts and enemy speeds,
+//! plus the reach of the magnet and the weapon drawn around the player. While it is up, +/- speed the game up or slow it down.
+
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    combat::WEAPON_RADIUS,
+    enemy::Enemy,
+    particles::Particle,
+    physics::Velocity,
+    pickups::PowerUps,
+    player::Player,
+    projectiles::Projectile,
+    time_scale::{TimeScale, MAX_TIME_SCALE, MIN_TIME_SCALE},
+    upgrades::PlayerStats,
+};
+
+const DEBUG_FONT_SIZE: f32 = 18.0;
+const DEBUG_TEXT_COLOR: Color = Color::rgb(0.2, 1.0, 0.2);
+const DEBUG_TEXT_PADDING: Val = Val::Px(5.0);
+const MAGNET_GIZMO_COLOR: Color = Color::rgba(0.5, 0.5, 1.0, 0.6);
+const WEAPON_GIZMO_COLOR: Color = Color::rgba(1.0, 0.5, 0.5, 0.6);
+const GIZMO_THICKNESS: f32 = 1.0;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin::default())
+            .init_resource::<DebugOverlay>()
+            .add_system(toggle_debug_overlay)
+            .add_system(adjust_time_scale.after(toggle_debug_overlay))
+            .add_system(update_debug_text.after(toggle_debug_overlay))
+            .add_system(update_gizmos.after(toggle_debug_overlay));
+    }
+}
+
+#[derive(Default)]
+struct DebugOverlay {
+    visible: bool,
+}
+
+// Everything spawned for the overlay, despawned when it is hidden
+#[derive(Component)]
+struct DebugOverlayItem;
+
+#[derive(Component)]
+struct DebugText;
+
+// A circle kept around the player, redrawn whenever its radius changes
+#[derive(Component)]
+enum Gizmo {
+    MagnetRadius,
+    WeaponRadius,
+}
+
+// Radius the gizmo's path was last built with
+#[derive(Component)]
+struct GizmoRadius(f32);
+
+fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut overlay: ResMut<DebugOverlay>,
+    item_query: Query<Entity, With<DebugOverlayItem>>,
+)
+{
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    overlay.visible = !overlay.visible;
+    if !overlay.visible {
+        for entity in &item_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: DEBUG_FONT_SIZE,
+                    color: DEBUG_TEXT_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: DEBUG_TEXT_PADDING,
+                    right: DEBUG_TEXT_PADDING,
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(DebugText)
+        .insert(DebugOverlayItem);
+
+    for (gizmo, color) in [(Gizmo::MagnetRadius, MAGNET_GIZMO_COLOR), (Gizmo::WeaponRadius, WEAPON_GIZMO_COLOR)] {
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Circle { radius: 0.0, center: Vec2::ZERO },
+                DrawMode::Stroke(StrokeMode::new(color, GIZMO_THICKNESS)),
+                Transform::default(),
+            ))
+            .insert(gizmo)
+            .insert(GizmoRadius(0.0))
+            .insert(DebugOverlayItem);
+    }
+}
+
+// Halves or doubles the speed of the game
+fn adjust_time_scale(overlay: Res<DebugOverlay>, keyboard_input: Res<Input<KeyCode>>, mut time_scale: ResMut<TimeScale>)
+{
+    if !overlay.visible {
+        return;
+    }
+
+    if keyboard_input.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        time_scale.base = (time_scale.base * 2.0).min(MAX_TIME_SCALE);
+    } else if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        time_scale.base = (time_scale.base / 2.0).max(MIN_TIME_SCALE);
+    }
+}
+
+fn update_debug_text(
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<Diagnostics>,
+    time_scale: Res<TimeScale>,
+    entity_query: Query<()>,
+    enemy_query: Query<&Velocity, With<Enemy>>,
+    projectile_query: Query<(), With<Projectile>>,
+    particle_query: Query<(), With<Particle>>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+)
+{
+    if !overlay.visible {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.0);
+
+    let speeds: Vec<f32> = enemy_query.iter().map(|velocity| velocity.0.length()).collect();
+    let enemies = speeds.len();
+    let max_speed = speeds.iter().copied().fold(0.0, f32::max);
+    let average_speed = if enemies > 0 { speeds.iter().sum::<f32>() / enemies as f32 } else { 0.0 };
+
+    for mut text in &mut text_query {
+        text.sections[0].value = format!(
+            "FPS: {:.0}\nTime scale: {:.2}x\nEntities: {}\nEnemies: {}\nProjectiles: {}\nParticles: {}\nEnemy speed: {:.0} avg, {:.0} max",
+            fps,
+            time_scale.get(),
+            entity_query.iter().count(),
+            enemies,
+            projectile_query.iter().count(),
+            particle_query.iter().count(),
+            average_speed,
+            max_speed,
+        );
+    }
+}
+
+fn update_gizmos(
+    stats: Res<PlayerStats>,
+    power_ups: Res<PowerUps>,
+    player_query: Query<(&Player, &Transform), Without<Gizmo>>,
+    mut gizmo_query: Query<(&Gizmo, &mut GizmoRadius, &mut Path, &mut Transform, &mut Visibility)>,
+)
+{
+    // Drawn around the first player
+    let player_transform = player_query.iter().find(|(player, _)| player.0 == 0).map(|(_, transform)| transform);
+    let stats = power_ups.boosted(&stats);
+
+    for (gizmo, mut drawn_radius, mut path, mut transform, mut visibility) in &mut gizmo_query {
+        visibility.is_visible = player_transform.is_some();
+        if let Some(player_transform) = player_transform {
+            transform.translation = player_transform.translation.truncate().extend(2.0);
+        }
+
+        let radius = match gizmo {
+            Gizmo::MagnetRadius => stats.magnet_radius,
+            Gizmo::WeaponRadius => WEAPON_RADIUS,
+        };
+        if radius != drawn_radius.0 {
+            *path = ShapePath::build_as(&shapes::Circle { radius, center: Vec2::ZERO });
+            drawn_radius.0 = radius;
+        }
+    }
+}
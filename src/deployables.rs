@@ -0,0 +1,169 @@
+//! Things a player places in the arena rather than fires. For now that is magnetic mines: each player carries a few,
+//! getting one back every so often, laid through the mine layer weapon. A mine arms after a moment and explodes
+//! once an enemy comes close, through the usual `ExplosionDamage`. Being magnetic, they can be dragged into place.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::ExplosionDamage,
+    fx::ExplosionToSpawn,
+    magnet::{Magnetic, Polarity},
+    physics::{apply_velocity, PhysicsBundle, Velocity},
+    spatial::{update_spatial_hash, SpatialHash},
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, LevelEntity, TIME_STEP, WALL_THICKNESS,
+};
+
+// Mines a player can have ready at once, and seconds for each spent one to come back
+const MAX_MINES: u32 = 3;
+const MINE_RECHARGE_TIME: f32 = 4.0;
+// Seconds before a mine can go off, so it doesn't blow up in the face of whoever laid it
+const MINE_ARM_TIME: f32 = 0.5;
+const MINE_TRIGGER_RADIUS: f32 = 40.0;
+const MINE_RADIUS: f32 = 8.0;
+const MINE_EXPLOSION_RADIUS: f32 = 110.0;
+// Multiplies the weapon damage
+const MINE_DAMAGE_SCALE: f32 = 1.5;
+// Light and draggy like pickups, so the magnet can move them but they don't drift off on their own
+const MINE_MASS: f32 = 0.5;
+const MINE_DRAG: f32 = 4.0;
+pub const MINE_COLOR: Color = Color::rgb(1.0, 0.6, 0.2);
+const MINE_UNARMED_COLOR: Color = Color::rgb(0.5, 0.4, 0.3);
+
+pub struct DeployablePlugin;
+
+impl Plugin for DeployablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(recharge_deployables)
+                .with_system(stop_mines_at_walls.after(apply_velocity))
+                .with_system(trigger_mines.after(stop_mines_at_walls).after(update_spatial_hash)),
+        );
+    }
+}
+
+// The mines a player has ready, recharging one at a time
+#[derive(Component)]
+pub struct Deployables {
+    pub mines: u32,
+    pub max_mines: u32,
+    recharge: Timer,
+}
+
+impl Default for Deployables {
+    fn default() -> Self {
+        Self {
+            mines: MAX_MINES,
+            max_mines: MAX_MINES,
+            recharge: Timer::from_seconds(MINE_RECHARGE_TIME, true),
+        }
+    }
+}
+
+impl Deployables {
+    // Spends a mine, if there is one ready
+    pub fn take(&mut self) -> bool {
+        if self.mines == 0 {
+            return false;
+        }
+        // Recharging starts over from the latest one spent
+        if self.mines == self.max_mines {
+            self.recharge.reset();
+        }
+        self.mines -= 1;
+        true
+    }
+}
+
+#[derive(Component)]
+struct Mine {
+    arming: Timer,
+    // A frame may run several steps before the despawn is applied, it must only go off once
+    detonated: bool,
+}
+
+pub fn spawn_mine(commands: &mut Commands, position: Vec2)
+{
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Circle { radius: MINE_RADIUS, center: Vec2::ZERO },
+            DrawMode::Fill(FillMode::color(MINE_UNARMED_COLOR)),
+            Transform::from_translation(position.extend(-0.2)),
+        ))
+        .insert(Mine { arming: Timer::from_seconds(MINE_ARM_TIME, false), detonated: false })
+        .insert_bundle(PhysicsBundle::new(Vec2::ZERO, MINE_MASS, MINE_DRAG))
+        .insert(Magnetic::new(1.0, Polarity::Positive))
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn recharge_deployables(mut query: Query<&mut Deployables>)
+{
+    for mut deployables in &mut query {
+        if deployables.mines >= deployables.max_mines {
+            continue;
+        }
+        if deployables.recharge.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+            deployables.mines += 1;
+        }
+    }
+}
+
+// Mines dragged along by the magnet stop dead against walls and obstacles
+fn stop_mines_at_walls(arena: Res<Arena>, mut query: Query<(&mut Transform, &mut Velocity), With<Mine>>)
+{
+    let half_size = Vec2::splat(MINE_RADIUS);
+    let inset = half_size + WALL_THICKNESS / 2.0;
+    let min = Vec2::new(arena.left(), arena.bottom()) + inset;
+    let max = Vec2::new(arena.right(), arena.top()) - inset;
+
+    for (mut transform, mut velocity) in &mut query {
+        let position = transform.translation.truncate();
+        let inside = arena.push_out(position, half_size).clamp(min, max);
+        if inside != position {
+            transform.translation = inside.extend(transform.translation.z);
+            velocity.0 = Vec2::ZERO;
+        }
+    }
+}
+
+// Armed mines go off as soon as an enemy comes close, hurting everything around them
+fn trigger_mines(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    spatial_hash: Res<SpatialHash>,
+    mut mine_query: Query<(Entity, &mut Mine, &Transform, &mut DrawMode)>,
+)
+{
+    for (entity, mut mine, transform, mut draw_mode) in &mut mine_query {
+        if mine.detonated {
+            continue;
+        }
+        if !mine.arming.finished() {
+            if mine.arming.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+                *draw_mode = DrawMode::Fill(FillMode::color(MINE_COLOR));
+            }
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        if spatial_hash.neighbours(position, MINE_TRIGGER_RADIUS).is_empty() {
+            continue;
+        }
+
+        mine.detonated = true;
+        commands.entity(entity).despawn();
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+        commands
+            .spawn()
+            .insert(ExplosionDamage::new(position, MINE_EXPLOSION_RADIUS, stats.weapon_damage * MINE_DAMAGE_SCALE))
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
@@ -0,0 +1,93 @@
+//! Difficulty presets picked on the main menu next to the game mode. On top of the `DifficultyCurve`,
+//! they scale how hard enemies hit, how much they take to kill, how many of them come and how often they drop pickups.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::data::DifficultyScale;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>();
+    }
+}
+
+// Picked on the main menu, kept across restarts
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+        }
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    // Multiplies the damage players take
+    pub fn enemy_damage(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    // Multiplies the health of enemies and bosses
+    pub fn enemy_health(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.35,
+        }
+    }
+
+    // Multiplies how many enemies a wave or horde holds, and how often survival spawns them
+    pub fn spawn_count(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+        }
+    }
+
+    // Multiplies the chance of a kill dropping a pickup
+    pub fn drop_chance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.7,
+        }
+    }
+
+    // The point of the difficulty curve, as tough as this preset makes it
+    pub fn apply(&self, scale: DifficultyScale) -> DifficultyScale {
+        DifficultyScale {
+            health: scale.health * self.enemy_health(),
+            spawn_rate: scale.spawn_rate * self.spawn_count(),
+            ..scale
+        }
+    }
+
+    pub fn scale_count(&self, count: u32) -> u32 {
+        ((count as f32 * self.spawn_count()).round() as u32).max(1)
+    }
+}
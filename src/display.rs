@@ -0,0 +1,133 @@
+//! How the game fits its window. The camera shows a view in the shape of the arena, as large as the window
+//! allows, and black bars cover whatever the window has to spare on the sides or at the top and bottom.
+//! Also gives the window its icon. Title, size and mode come from `Options::window_descriptor` and the settings.
+
+use bevy::{asset::LoadState, prelude::*, render::camera::ScalingMode, window::WindowResized, winit::WinitWindows};
+use winit::window::Icon;
+
+use crate::{arena::Arena, camera::FollowCamera};
+
+const WINDOW_ICON_PATH: &str = "images/player.png";
+// How much of the arena is in view from top to bottom at zoom 1. Bigger windows show it larger, not more of it
+const VIEW_HEIGHT: f32 = 720.0;
+const LETTERBOX_COLOR: Color = Color::BLACK;
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_window_icon)
+            .add_startup_system(spawn_letterbox)
+            .add_system(set_window_icon)
+            .add_system(letterbox);
+    }
+}
+
+// Waiting to be loaded, removed once the icon is set
+struct WindowIcon(Handle<Image>);
+
+// One of the two bars, the first goes on the left or at the top
+#[derive(Component)]
+struct LetterboxBar(usize);
+
+fn load_window_icon(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    commands.insert_resource(WindowIcon(asset_server.load(WINDOW_ICON_PATH)));
+}
+
+fn set_window_icon(
+    mut commands: Commands,
+    icon: Option<Res<WindowIcon>>,
+    asset_server: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    windows: Res<Windows>,
+    winit_windows: NonSend<WinitWindows>,
+)
+{
+    let icon = match icon {
+        Some(icon) => icon,
+        None => return,
+    };
+    if asset_server.get_load_state(&icon.0) == LoadState::Failed {
+        commands.remove_resource::<WindowIcon>();
+        return;
+    }
+    let image = match images.get(&icon.0) {
+        Some(image) => image,
+        None => return,
+    };
+    let window = match windows.get_primary().and_then(|window| winit_windows.get_window(window.id())) {
+        Some(window) => window,
+        None => return,
+    };
+
+    // Images are loaded as 8-bit RGBA, which is what winit wants too
+    let size = image.texture_descriptor.size;
+    match Icon::from_rgba(image.data.clone(), size.width, size.height) {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(error) => warn!("Could not use {} as the window icon: {}", WINDOW_ICON_PATH, error),
+    }
+    commands.remove_resource::<WindowIcon>();
+}
+
+// Sized by `letterbox` once the window is known
+fn spawn_letterbox(mut commands: Commands)
+{
+    for index in 0..2 {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Px(0.0), Val::Px(0.0)),
+                    ..default()
+                },
+                color: LETTERBOX_COLOR.into(),
+                ..default()
+            })
+            .insert(LetterboxBar(index));
+    }
+}
+
+// Refits the view whenever the window is resized, or a level brings an arena of a different shape
+fn letterbox(
+    mut resized_events: EventReader<WindowResized>,
+    arena: Res<Arena>,
+    windows: Res<Windows>,
+    mut camera_query: Query<&mut OrthographicProjection, With<FollowCamera>>,
+    mut bar_query: Query<(&LetterboxBar, &mut Style)>,
+)
+{
+    if resized_events.iter().count() == 0 && !arena.is_changed() {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    let aspect = (arena.right() - arena.left()) / (arena.top() - arena.bottom());
+    let view = Vec2::new(VIEW_HEIGHT * aspect, VIEW_HEIGHT);
+    // The camera shows at least the view, the bars hide what it shows beyond that
+    for mut projection in &mut camera_query {
+        projection.scaling_mode = ScalingMode::Auto { min_width: view.x, min_height: view.y };
+    }
+
+    let shown = view * (window_size / view).min_element();
+    let spare = (window_size - shown) / 2.0;
+    let bars_on_sides = spare.x > spare.y;
+    for (bar, mut style) in &mut bar_query {
+        let mut position = UiRect { left: Val::Px(0.0), top: Val::Px(0.0), ..default() };
+        if bar.0 == 1 && bars_on_sides {
+            position = UiRect { right: Val::Px(0.0), top: Val::Px(0.0), ..default() };
+        } else if bar.0 == 1 {
+            position = UiRect { left: Val::Px(0.0), bottom: Val::Px(0.0), ..default() };
+        }
+        style.position = position;
+        style.size = if bars_on_sides {
+            Size::new(Val::Px(spare.x), Val::Percent(100.0))
+        } else {
+            Size::new(Val::Percent(100.0), Val::Px(spare.y))
+        };
+    }
+}
@@ -0,0 +1,151 @@
+//! Turret drones bought in the upgrade shop. Each player gets as many as have been bought, circling them
+//! and shooting at the nearest enemy in sight every so often with the same shots enemies fire.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    enemy::Enemy,
+    physics::apply_velocity,
+    player::Player,
+    projectiles::spawn_friendly_projectile,
+    spatial::{update_spatial_hash, SpatialHash},
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const DRONE_SIZE: f32 = 10.0;
+const DRONE_COLOR: Color = Color::rgb(0.5, 1.0, 0.5);
+const DRONE_ORBIT_RADIUS: f32 = 45.0;
+// Radians per second
+const DRONE_ORBIT_SPEED: f32 = 2.5;
+const DRONE_FIRE_INTERVAL: f32 = 1.0;
+// Drones only shoot at enemies this close to them
+const DRONE_RANGE: f32 = 350.0;
+const DRONE_SHOT_SPEED: f32 = 400.0;
+// Fraction of the weapon damage each shot deals
+const DRONE_DAMAGE_SCALE: f32 = 0.4;
+
+pub struct DronePlugin;
+
+impl Plugin for DronePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Playing).with_system(sync_drones))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(orbit_drones.after(apply_velocity))
+                    .with_system(drones_fire.after(orbit_drones).after(update_spatial_hash)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Drone {
+    owner: Entity,
+    // Where along its orbit it is, in radians
+    angle: f32,
+    cooldown: Timer,
+}
+
+// Keeps as many drones around each player as have been bought, spread evenly around them
+fn sync_drones(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    player_query: Query<Entity, With<Player>>,
+    mut drone_query: Query<&mut Drone>,
+)
+{
+    for player in &player_query {
+        let count = drone_query.iter().filter(|drone| drone.owner == player).count() as u32;
+        if count >= stats.drones {
+            continue;
+        }
+
+        // The ones already there are spread out again to make room for the new ones
+        let spacing = TAU / stats.drones as f32;
+        let phase = drone_query.iter().find(|drone| drone.owner == player).map_or(0.0, |drone| drone.angle);
+        for (index, mut drone) in drone_query.iter_mut().filter(|drone| drone.owner == player).enumerate() {
+            drone.angle = phase + index as f32 * spacing;
+        }
+
+        for index in count..stats.drones {
+            // Staggered, so they don't all fire at once
+            let mut cooldown = Timer::from_seconds(DRONE_FIRE_INTERVAL, true);
+            cooldown.tick(Duration::from_secs_f32(DRONE_FIRE_INTERVAL * index as f32 / stats.drones as f32));
+            commands
+                .spawn_bundle(GeometryBuilder::build_as(
+                    &shapes::RegularPolygon {
+                        sides: 3,
+                        feature: shapes::RegularPolygonFeature::Radius(DRONE_SIZE),
+                        ..default()
+                    },
+                    DrawMode::Fill(FillMode::color(DRONE_COLOR)),
+                    Transform::default(),
+                ))
+                .insert(Drone { owner: player, angle: phase + index as f32 * spacing, cooldown })
+                .insert(DespawnOnStateExit(GameState::Playing));
+        }
+    }
+}
+
+fn orbit_drones(
+    mut commands: Commands,
+    player_query: Query<&Transform, (With<Player>, Without<Drone>)>,
+    mut drone_query: Query<(Entity, &mut Drone, &mut Transform)>,
+)
+{
+    for (entity, mut drone, mut transform) in &mut drone_query {
+        let center = match player_query.get(drone.owner) {
+            Ok(player_transform) => player_transform.translation.truncate(),
+            // Its player is gone
+            Err(_) => {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        };
+
+        drone.angle = (drone.angle + DRONE_ORBIT_SPEED * TIME_STEP) % TAU;
+        let position = center + Vec2::new(drone.angle.cos(), drone.angle.sin()) * DRONE_ORBIT_RADIUS;
+        transform.translation = position.extend(1.0);
+    }
+}
+
+fn drones_fire(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    arena: Res<Arena>,
+    spatial_hash: Res<SpatialHash>,
+    mut drone_query: Query<(&mut Drone, &mut Transform)>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Drone>)>,
+)
+{
+    let damage = ((stats.weapon_damage * DRONE_DAMAGE_SCALE).round() as i32).max(1);
+
+    for (mut drone, mut transform) in &mut drone_query {
+        if !drone.cooldown.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let target = spatial_hash
+            .query_radius(position, DRONE_RANGE)
+            .into_iter()
+            .filter_map(|entity| enemy_query.get(entity).ok())
+            .map(|enemy_transform| enemy_transform.translation.truncate())
+            .filter(|target| target.distance(position) < DRONE_RANGE && arena.line_of_sight(position, *target))
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+        let target = match target {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let direction = (target - position).normalize_or_zero();
+        transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+        spawn_friendly_projectile(&mut commands, position + direction * DRONE_SIZE, direction * DRONE_SHOT_SPEED, damage);
+    }
+}
@@ -0,0 +1,119 @@
+//! The `embedded_assets` feature: every file in `assets/` is compiled into the binary, so the game can be shipped
+//! as a single executable. An asset IO in front of the usual one answers for the embedded files, anything else
+//! (nothing, in a build that embeds everything) is still looked up on disk.
+
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::{create_platform_default_asset_io, AssetIo, AssetIoError, FileType, Metadata},
+    prelude::*,
+    utils::BoxedFuture,
+};
+
+// Paths relative to `assets/`, the way the game loads them
+macro_rules! embed {
+    ($($path:literal),* $(,)?) => {
+        &[$(($path, include_bytes!(concat!("../assets/", $path)) as &[u8])),*]
+    };
+}
+
+// The mp3 soundtrack is left out, only the ogg files are ever loaded
+const EMBEDDED_FILES: &[(&str, &[u8])] = embed![
+    "data/difficulty.ron",
+    "data/enemies.ron",
+    "data/game.config.ron",
+    "data/levels.ron",
+    "data/loot.ron",
+    "data/waves.ron",
+    "fonts/FiraMono-Medium.ttf",
+    "fonts/FiraSans-Bold.ttf",
+    "images/enemy_1.png",
+    "images/enemy_1_sheet.png",
+    "images/enemy_2.png",
+    "images/enemy_2_sheet.png",
+    "images/enemy_3.png",
+    "images/enemy_3_sheet.png",
+    "images/explo_a_sheet.png",
+    "images/player.png",
+    "images/player_sheet.png",
+    "locales/de.ron",
+    "locales/en.ron",
+    "sounds/magnet_pull.ogg",
+    "sounds/magnet_push.ogg",
+    "sounds/player_hit.ogg",
+    "sounds/soundtrack.ogg",
+];
+
+// Added ahead of `AssetPlugin`, which then uses the asset server set up here
+pub struct EmbeddedAssetPlugin;
+
+impl Plugin for EmbeddedAssetPlugin {
+    fn build(&self, app: &mut App) {
+        let fallback = create_platform_default_asset_io(app);
+        app.insert_resource(AssetServer::new(EmbeddedAssetIo { fallback }));
+    }
+}
+
+// The contents of an embedded file, by its path relative to `assets/`
+pub fn file(path: impl AsRef<Path>) -> Option<&'static [u8]>
+{
+    let path = path.as_ref();
+    EMBEDDED_FILES
+        .iter()
+        .find(|(embedded, _)| Path::new(embedded) == path)
+        .map(|(_, contents)| *contents)
+}
+
+fn is_embedded_dir(path: &Path) -> bool
+{
+    EMBEDDED_FILES.iter().any(|(embedded, _)| Path::new(embedded).parent() == Some(path))
+}
+
+struct EmbeddedAssetIo {
+    fallback: Box<dyn AssetIo>,
+}
+
+impl AssetIo for EmbeddedAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        match file(path) {
+            Some(contents) => Box::pin(async move { Ok(contents.to_vec()) }),
+            None => self.fallback.load_path(path),
+        }
+    }
+
+    fn read_directory(&self, path: &Path) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        if !is_embedded_dir(path) {
+            return self.fallback.read_directory(path);
+        }
+
+        let dir = path.to_path_buf();
+        Ok(Box::new(
+            EMBEDDED_FILES
+                .iter()
+                .map(|(embedded, _)| PathBuf::from(embedded))
+                .filter(move |embedded| embedded.parent() == Some(dir.as_path())),
+        ))
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        if file(path).is_some() {
+            Ok(Metadata::new(FileType::File))
+        } else if is_embedded_dir(path) {
+            Ok(Metadata::new(FileType::Directory))
+        } else {
+            self.fallback.get_metadata(path)
+        }
+    }
+
+    // Embedded files never change, there is nothing to watch
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        if file(path).is_some() {
+            return Ok(());
+        }
+        self.fallback.watch_path_for_changes(path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.fallback.watch_for_changes()
+    }
+}
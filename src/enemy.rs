@@ -0,0 +1,510 @@
+//! Enemies: spawning them from their `data::EnemyTable` definition, after a warning marker has shown where they
+//! will appear, and bouncing them off walls, obstacles, the player and each other.
+
+use std::{f64::consts::PI, time::Duration};
+
+use bevy::{
+    prelude::*,
+    sprite::collide_aabb::{collide, Collision},
+    utils::HashMap,
+};
+use bevy_prototype_lyon::prelude::*;
+use bevy_simple_stat_bars::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    ai::{AiState, Telegraph},
+    animation::AnimatedSprite,
+    arena::{Arena, Obstacle},
+    cleanup::DespawnOnStateExit,
+    combat::{DamageEvent, DamageKind, Hp, ENEMY_CONTACT_DAMAGE},
+    config::GameConfig,
+    data::{DifficultyScale, EnemyDefinition, EnemyTable},
+    fx::spawn_sparks,
+    magnet::{Magnetic, Polarity},
+    physics::{apply_velocity, Collider, Forces, Mass, PhysicsBundle, Velocity},
+    player::Player,
+    projectiles::ShotCooldown,
+    rng::GameRng,
+    shield::{spawn_shield_bar, Shield},
+    status::StatusEffects,
+    spatial::{update_spatial_hash, SpatialHash},
+    tween::{Ease, SpriteSize, Tween},
+    FixedUpdate, GameState, LevelEntity, Scoreboard, BACKGROUND_COLOR, TIME_STEP,
+};
+
+// How quickly an enemy slows down on its own, it keeps `exp(-ENEMY_DRAG * t)` of its velocity after `t` seconds
+pub const ENEMY_DRAG: f32 = 1.0;
+pub const ENEMY_MASS: f32 = 1.0;
+
+pub const ENEMY_HEALTH_BAR_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+
+// Seconds the marker shows before the enemy appears
+const SPAWN_TELEGRAPH_DURATION: f32 = 1.0;
+// Enemies never appear closer than this to a player
+pub const SPAWN_SAFE_RADIUS: f32 = 250.0;
+// The marker closes in from this many times the enemy's size, blinking meanwhile
+const SPAWN_TELEGRAPH_START_SCALE: f32 = 2.5;
+const SPAWN_TELEGRAPH_BLINK_INTERVAL: f32 = 0.125;
+const SPAWN_TELEGRAPH_THICKNESS: f32 = 2.0;
+// Enemies are drawn popping in from this fraction of their size once the marker is gone
+const MATERIALIZE_START_SCALE: f32 = 0.3;
+const MATERIALIZE_DURATION: f32 = 0.25;
+// Size of a frame in the enemy sprite sheets, and how fast enemies hover
+const ENEMY_FRAME_SIZE: Vec2 = Vec2::new(72.0, 72.0);
+const ENEMY_HOVER_FPS: f32 = 8.0;
+// Seconds a shielded enemy has to go unhurt before its shield recharges
+const ENEMY_SHIELD_REGEN_DELAY: f32 = 3.0;
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyCollisionEvent>()
+            .add_event::<WallSlamEvent>()
+            .init_resource::<EnemySprites>()
+            .add_system(load_enemy_sprites)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(materialize_enemies)
+                    .with_system(check_for_collisions.after(apply_velocity).after(update_spatial_hash))
+                    .with_system(
+                        check_for_enemy_collisions
+                            .after(apply_velocity)
+                            .after(update_spatial_hash)
+                            .before(check_for_collisions),
+                    ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Enemy;
+
+// How fast an enemy moves when it isn't being thrown around by the magnet
+#[derive(Component, Deref, DerefMut)]
+pub struct Speed(pub f32);
+
+// Score awarded for killing an enemy
+#[derive(Component)]
+pub struct ScoreValue(pub i32);
+
+// The color a sprite returns to after flashing
+#[derive(Component)]
+pub struct Tint(pub Color);
+
+// Sent when two enemies bump into each other
+pub struct EnemyCollisionEvent(pub Entity, pub Entity);
+
+// Sent when an enemy is flung into a wall hard enough to get hurt
+pub struct WallSlamEvent {
+    pub enemy: Entity,
+    pub position: Vec3,
+    pub damage: i32,
+}
+
+// Everything needed to bring an enemy into the arena
+#[derive(Clone)]
+pub struct EnemySpawn {
+    pub kind: EnemyKind,
+    pub definition: EnemyDefinition,
+    // Grows over the run, scaling the kind's base values
+    pub difficulty: DifficultyScale,
+}
+
+impl EnemySpawn {
+    pub fn new(enemies: &EnemyTable, kind: EnemyKind, difficulty: DifficultyScale) -> Self {
+        Self { kind, definition: enemies.get(kind).clone(), difficulty }
+    }
+
+    // Each enemy is randomly positive or negative, or neutral with the kind's `neutral_chance`
+    pub fn random_polarity(&self, rng: &mut impl Rng) -> Polarity {
+        if rng.gen_bool(self.definition.neutral_chance.clamp(0.0, 1.0)) {
+            Polarity::Neutral
+        } else if rng.gen() {
+            Polarity::Positive
+        } else {
+            Polarity::Negative
+        }
+    }
+}
+
+// Marks where an enemy is about to appear, it replaces the marker once the timer runs out
+#[derive(Component)]
+pub struct SpawnTelegraph {
+    timer: Timer,
+    spawn: EnemySpawn,
+    polarity: Polarity,
+}
+
+// Decides how an enemy moves, its looks and stats come from `data::EnemyTable`
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum EnemyKind {
+    // Runs straight at the player
+    Chaser,
+    // Circles the player at a distance, closing in slowly
+    Orbiter,
+    // Slow and heavy, barely moved by the magnet
+    Heavy,
+    // Keeps its distance and fires projectiles at the player
+    Shooter,
+}
+
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 4] = [EnemyKind::Chaser, EnemyKind::Orbiter, EnemyKind::Heavy, EnemyKind::Shooter];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            EnemyKind::Chaser => "enemy.chaser",
+            EnemyKind::Orbiter => "enemy.orbiter",
+            EnemyKind::Heavy => "enemy.heavy",
+            EnemyKind::Shooter => "enemy.shooter",
+        }
+    }
+}
+
+// One texture handle per kind, shared by all of its enemies so their sprites batch together
+#[derive(Default)]
+pub struct EnemySprites(HashMap<EnemyKind, (Handle<TextureAtlas>, usize)>);
+
+fn load_enemy_sprites(
+    asset_server: Res<AssetServer>,
+    enemies: Option<Res<EnemyTable>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut sprites: ResMut<EnemySprites>,
+)
+{
+    // Reloaded along with the data file
+    let enemies = match enemies {
+        Some(enemies) if enemies.is_changed() => enemies,
+        _ => return,
+    };
+
+    for kind in EnemyKind::ALL {
+        let definition = enemies.get(kind);
+        let frames = definition.frames.max(1);
+        let atlas = TextureAtlas::from_grid(asset_server.load(&definition.sprite), ENEMY_FRAME_SIZE, frames, 1);
+        sprites.0.insert(kind, (texture_atlases.add(atlas), frames));
+    }
+}
+
+// Picks a random position clear of obstacles and away from the players, and telegraphs an enemy spawning there
+pub fn spawn_enemy(commands: &mut Commands, rng: &mut impl Rng, arena: &Arena, players: &[Vec2], spawn: EnemySpawn)
+{
+    let size = Vec2::splat(spawn.definition.size);
+    let position = arena.random_open_position(rng, size / 2.0, players, SPAWN_SAFE_RADIUS);
+    spawn_enemy_at(commands, rng, position, spawn);
+}
+
+// Like `spawn_enemy`, for a spot already picked
+pub fn spawn_enemy_at(commands: &mut Commands, rng: &mut impl Rng, position: Vec2, spawn: EnemySpawn)
+{
+    let polarity = spawn.random_polarity(rng);
+
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Circle { radius: spawn.definition.size / 2.0, center: Vec2::ZERO },
+            DrawMode::Stroke(StrokeMode::new(polarity.color(), SPAWN_TELEGRAPH_THICKNESS)),
+            Transform {
+                translation: position.extend(-0.5),
+                scale: Vec3::splat(SPAWN_TELEGRAPH_START_SCALE),
+                ..default()
+            },
+        ))
+        .insert(SpawnTelegraph {
+            timer: Timer::from_seconds(SPAWN_TELEGRAPH_DURATION, false),
+            spawn,
+            polarity,
+        })
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn materialize_enemies(
+    mut commands: Commands,
+    sprites: Res<EnemySprites>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<(Entity, &mut SpawnTelegraph, &mut Transform, &mut DrawMode)>,
+)
+{
+    for (entity, mut telegraph, mut transform, mut draw_mode) in &mut query {
+        telegraph.timer.tick(Duration::from_secs_f32(TIME_STEP));
+        if telegraph.timer.finished() {
+            commands.entity(entity).despawn();
+            spawn_sparks(&mut commands, &config, transform.translation);
+            let enemy = materialize_enemy(
+                &mut commands,
+                &sprites,
+                &mut rng.0,
+                transform.translation.truncate(),
+                &telegraph.spawn,
+                telegraph.polarity,
+            );
+            spawn_enemy_health_bar(&mut commands, enemy, telegraph.spawn.definition.size);
+            if telegraph.spawn.definition.shield > 0.0 {
+                let size = telegraph.spawn.definition.size;
+                spawn_shield_bar(&mut commands, enemy, size, size / 2.0 + 6.0);
+            }
+            continue;
+        }
+
+        let scale = 1.0 + (SPAWN_TELEGRAPH_START_SCALE - 1.0) * telegraph.timer.percent_left();
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        if let DrawMode::Stroke(ref mut stroke) = *draw_mode {
+            let blink = (telegraph.timer.elapsed_secs() / SPAWN_TELEGRAPH_BLINK_INTERVAL) as u32 % 2 == 0;
+            stroke.color.set_a(if blink { 1.0 } else { 0.3 });
+        }
+    }
+}
+
+// The enemy itself, appearing straight away and moving off in a random direction.
+// Telegraphed enemies get a health bar on top, horde enemies go without.
+pub fn materialize_enemy(
+    commands: &mut Commands,
+    sprites: &EnemySprites,
+    rng: &mut impl Rng,
+    enemy_position: Vec2,
+    spawn: &EnemySpawn,
+    polarity: Polarity,
+) -> Entity
+{
+    let definition = &spawn.definition;
+    let health = ((definition.health * spawn.difficulty.health) as i32).max(1);
+    let speed = definition.speed * spawn.difficulty.speed;
+    let size = Vec2::splat(definition.size);
+    let color = definition.color * Vec4::from(polarity.color().as_rgba_f32());
+    let (atlas, frames) = sprites.0.get(&spawn.kind).cloned().unwrap_or_else(|| (Handle::default(), 1));
+
+    let enemy = commands
+        .spawn()
+        .insert(Enemy)
+        .insert(spawn.kind)
+        .insert(ScoreValue(definition.score))
+        .insert(Magnetic::new(1.0 / spawn.difficulty.magnet_resistance, polarity))
+        .insert(Tint(color))
+        .insert(Hp { current: health, max: health })
+        .insert(StatusEffects::default())
+        .insert_bundle(SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color,
+                custom_size: Some(Vec2::splat(MATERIALIZE_START_SCALE)),
+                flip_x: rng.gen(),
+                flip_y: rng.gen(),
+                ..default()
+            },
+            transform: Transform {
+                translation: enemy_position.extend(0.0),
+                scale: size.extend(1.0),
+                rotation: Quat::from_rotation_z(rng.gen_range(0.0..2.0 * PI) as f32),
+                ..default()
+            },
+            texture_atlas: atlas,
+            ..default()
+        })
+        .insert(AnimatedSprite::new(0, frames, ENEMY_HOVER_FPS, true).with_frame(rng.gen_range(0..frames)))
+        .insert_bundle(PhysicsBundle::new(
+            Vec2::new(rng.gen_range(-speed..speed), rng.gen_range(-speed..speed)),
+            definition.mass,
+            ENEMY_DRAG,
+        ))
+        // Only the sprite grows in, the enemy collides at its full size from the start
+        .insert(Tween::<SpriteSize>::new(
+            Vec2::splat(MATERIALIZE_START_SCALE),
+            Vec2::ONE,
+            MATERIALIZE_DURATION,
+            Ease::BackOut,
+        ))
+        .insert(Speed(speed))
+        .insert(AiState::default())
+        .insert(Collider)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .id();
+
+    if spawn.kind == EnemyKind::Shooter {
+        commands.entity(enemy).insert(ShotCooldown::default());
+    }
+    let shield = (definition.shield * spawn.difficulty.health) as i32;
+    if shield > 0 {
+        commands.entity(enemy).insert(Shield::new(shield, ENEMY_SHIELD_REGEN_DELAY));
+    }
+
+    enemy
+}
+
+// Health bar, floating above the enemy
+pub fn spawn_enemy_health_bar(commands: &mut Commands, enemy: Entity, size: f32)
+{
+    commands
+        .spawn_bundle((
+            StatBarColor(ENEMY_HEALTH_BAR_COLOR),
+            StatBarEmptyColor(BACKGROUND_COLOR),
+            StatBarValue(1.0),
+            StatBarSize { full_length: size, thickness: 3.0 },
+            StatBarSubject(enemy),
+            StatBarPosition((size / 2.0 + 6.0) * Vec2::Y),
+            component_observer(|hp: &Hp| hp.current as f32 / hp.max as f32),
+        ))
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+// check collisions for enemies with walls
+pub fn check_for_collisions(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    spatial_hash: Res<SpatialHash>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut enemy_query: Query<(Entity, &mut Velocity, &mut Forces, &mut Transform, Option<&AiState>, Option<&Telegraph>), With<Enemy>>,
+    // Enemies bounce off each other in `check_for_enemy_collisions`
+    collider_query: Query<(Entity, &Transform, Option<&Player>), (With<Collider>, Without<Enemy>)>,
+    mut wall_slam_events: EventWriter<WallSlamEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+)
+{
+    for (collider_entity, collider_transform, maybe_player) in collider_query.iter() {
+        // Only the enemies around a collider can touch it
+        let nearby = spatial_hash.query_aabb(collider_transform.translation.truncate(), collider_transform.scale.truncate() / 2.0);
+        for entity in nearby {
+            let enemy = match enemy_query.get_mut(entity) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+            let (enemy_entity, mut enemy_velocity, mut enemy_forces, mut enemy_transform, state, telegraph) = enemy;
+            let collision = collide(
+                enemy_transform.translation,
+                enemy_transform.scale.truncate(),
+                collider_transform.translation,
+                collider_transform.scale.truncate(),
+            );
+
+            if let Some(collision) = collision {
+                if maybe_player.is_some() {
+                    // Only an attack that has been wound up hurts, bosses always do
+                    let attacking = state.map_or(true, |state| *state == AiState::Attack)
+                        && !telegraph.map_or(false, Telegraph::winding_up);
+                    if attacking {
+                        damage_events.send(DamageEvent {
+                            target: collider_entity,
+                            amount: ENEMY_CONTACT_DAMAGE as i32,
+                            source: Some(enemy_entity),
+                            kind: DamageKind::Contact,
+                        });
+                        scoreboard.score -= 1;
+                    }
+
+                    // Knock the enemy away from the player
+                    let direction = (enemy_transform.translation - collider_transform.translation).truncate();
+                    enemy_forces.add_impulse(direction.normalize_or_zero() * config.player_knockback);
+                } else {
+                    // Walls and obstacles don't move, so put the enemy back outside of them
+                    let wall = Obstacle {
+                        center: collider_transform.translation.truncate(),
+                        half_size: collider_transform.scale.truncate() / 2.0,
+                    };
+                    let push = wall.push_out(enemy_transform.translation.truncate(), enemy_transform.scale.truncate() / 2.0);
+                    enemy_transform.translation += push.extend(0.0);
+                }
+
+                // reflect the ball when it collides
+                let mut reflect_x = false;
+                let mut reflect_y = false;
+
+                // only reflect if the ball's velocity is going in the opposite direction of the
+                // collision
+                match collision {
+                    Collision::Left => reflect_x = enemy_velocity.x > 0.0,
+                    Collision::Right => reflect_x = enemy_velocity.x < 0.0,
+                    Collision::Top => reflect_y = enemy_velocity.y < 0.0,
+                    Collision::Bottom => reflect_y = enemy_velocity.y > 0.0,
+                    Collision::Inside => { /* do nothing */ }
+                }
+
+                // enemies flung into a wall hard enough get hurt by the impact
+                let impact_speed = if reflect_x { enemy_velocity.x.abs() } else if reflect_y { enemy_velocity.y.abs() } else { 0.0 };
+                if maybe_player.is_none() && impact_speed > config.wall_slam_speed {
+                    let damage = ((impact_speed - config.wall_slam_speed) * config.wall_slam_damage_scale).ceil() as i32;
+                    damage_events.send(DamageEvent { target: enemy_entity, amount: damage, source: None, kind: DamageKind::Impact });
+                    wall_slam_events.send(WallSlamEvent { enemy: enemy_entity, position: enemy_transform.translation, damage });
+                    spawn_sparks(&mut commands, &config, enemy_transform.translation);
+                }
+
+                // reflect velocity on the x-axis if we hit something on the x-axis
+                if reflect_x {
+                    enemy_velocity.x = -enemy_velocity.x;
+                }
+
+                // reflect velocity on the y-axis if we hit something on the y-axis
+                if reflect_y {
+                    enemy_velocity.y = -enemy_velocity.y;
+                }
+            }
+        }
+    }
+}
+
+// Bounce enemies off each other, hurting both when they are smashed together
+pub fn check_for_enemy_collisions(
+    config: Res<GameConfig>,
+    spatial_hash: Res<SpatialHash>,
+    mut enemy_query: Query<(Entity, &mut Transform, &Velocity, &mut Forces, &Mass), With<Enemy>>,
+    mut enemy_collision_events: EventWriter<EnemyCollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+)
+{
+    let enemies: Vec<(Entity, Vec2, Vec2)> = enemy_query
+        .iter()
+        .map(|(entity, transform, ..)| (entity, transform.translation.truncate(), transform.scale.truncate() / 2.0))
+        .collect();
+
+    for (entity_a, center, half_size) in enemies {
+        for entity_b in spatial_hash.query_aabb(center, half_size) {
+            // Each pair once, and never an enemy with itself
+            if entity_b <= entity_a {
+                continue;
+            }
+            let [a, b] = match enemy_query.get_many_mut([entity_a, entity_b]) {
+                Ok(pair) => pair,
+                Err(_) => continue,
+            };
+            let (entity_a, mut transform_a, velocity_a, mut forces_a, mass_a) = a;
+            let (entity_b, mut transform_b, velocity_b, mut forces_b, mass_b) = b;
+
+            let offset = (transform_b.translation - transform_a.translation).truncate();
+            let distance = offset.length();
+            // Sprites are scaled to the enemy's size, so half the scale is its radius
+            let contact_distance = (transform_a.scale.x + transform_b.scale.x) / 2.0;
+            if distance >= contact_distance || distance <= 0.0 {
+                continue;
+            }
+            let normal = offset / distance;
+            enemy_collision_events.send(EnemyCollisionEvent(entity_a, entity_b));
+
+            // Move them apart, the lighter one moving further
+            let overlap = contact_distance - distance;
+            let total_mass = mass_a.0 + mass_b.0;
+            transform_a.translation -= (normal * overlap * mass_b.0 / total_mass).extend(0.0);
+            transform_b.translation += (normal * overlap * mass_a.0 / total_mass).extend(0.0);
+
+            // Only respond if they are moving toward each other
+            let impact_speed = (velocity_a.0 - velocity_b.0).dot(normal);
+            if impact_speed <= 0.0 {
+                continue;
+            }
+
+            // Elastic collision, exchanging momentum along the normal
+            let impulse = 2.0 * impact_speed / (1.0 / mass_a.0 + 1.0 / mass_b.0);
+            forces_a.add_impulse(-normal * impulse);
+            forces_b.add_impulse(normal * impulse);
+
+            if impact_speed > config.enemy_smash_speed {
+                let damage = ((impact_speed - config.enemy_smash_speed) * config.enemy_smash_damage_scale).ceil() as i32;
+                damage_events.send(DamageEvent { target: entity_a, amount: damage, source: Some(entity_b), kind: DamageKind::Impact });
+                damage_events.send(DamageEvent { target: entity_b, amount: damage, source: Some(entity_a), kind: DamageKind::Impact });
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Visual effects that aren't tied to any one feature: explosions and impact sparks.
+//! Explosion sprites are pooled, they are hidden once their animation ends and reused by the next one.
+
+use std::f64::consts::PI;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    animation::{animate_sprites, AnimatedSprite},
+    config::GameConfig,
+    particles::spawn_particle,
+    pool::Pool,
+};
+
+const EXPLOSION_SHEET: &str = "images/explo_a_sheet.png";
+const EXPLOSION_LEN: usize = 16;
+const EXPLOSION_FPS: f32 = 20.0;
+
+pub struct FxPlugin;
+
+impl Plugin for FxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pool<Explosion>>()
+            .add_startup_system(load_explosion_texture)
+            .add_system(explosion_to_spawn_system)
+            .add_system(explosion_animation_system.after(animate_sprites));
+    }
+}
+
+#[derive(Component)]
+pub struct Explosion;
+
+// Spawned wherever an explosion should go off, replaced by the animated explosion next frame
+#[derive(Component)]
+pub struct ExplosionToSpawn(pub Vec3);
+
+struct ExplosionTexture(Handle<TextureAtlas>);
+
+fn load_explosion_texture(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+)
+{
+    commands.insert_resource(ExplosionTexture(
+        texture_atlases.add(TextureAtlas::from_grid(
+            asset_server.load(EXPLOSION_SHEET),
+            Vec2::new(64.0, 64.0),
+            EXPLOSION_LEN,
+            EXPLOSION_LEN,
+        ))
+    ));
+}
+
+fn explosion_to_spawn_system(
+    mut commands: Commands,
+    explosion_texture: Res<ExplosionTexture>,
+    mut pool: ResMut<Pool<Explosion>>,
+    query: Query<(Entity, &ExplosionToSpawn)>,
+    mut explosion_query: Query<
+        (&mut Transform, &mut AnimatedSprite, &mut Visibility),
+        With<Explosion>,
+    >,
+) {
+    for (explosion_spawn_entity, explosion_to_spawn) in query.iter() {
+        // reuse a finished explosion if there is one, or spawn the explosion sprite
+        let reused = pool.acquire().and_then(|entity| explosion_query.get_mut(entity).ok());
+        match reused {
+            Some((mut transform, mut animation, mut visibility)) => {
+                transform.translation = explosion_to_spawn.0;
+                animation.restart();
+                visibility.is_visible = true;
+            }
+            None => {
+                commands
+                    .spawn_bundle(SpriteSheetBundle {
+                        texture_atlas: explosion_texture.0.clone(),
+                        transform: Transform {
+                            translation: explosion_to_spawn.0,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(Explosion)
+                    .insert(AnimatedSprite::new(0, EXPLOSION_LEN, EXPLOSION_FPS, false));
+            }
+        }
+
+        // despawn the explosionToSpawn
+        commands.entity(explosion_spawn_entity).despawn();
+    }
+}
+
+// Explosions play once, then go back to the pool rather than being despawned
+fn explosion_animation_system(
+    mut pool: ResMut<Pool<Explosion>>,
+    mut query: Query<(Entity, &AnimatedSprite, &mut Visibility), With<Explosion>>,
+) {
+    for (entity, animation, mut visibility) in query.iter_mut() {
+        if visibility.is_visible && animation.finished() {
+            visibility.is_visible = false;
+            pool.release(entity);
+        }
+    }
+}
+
+// Small burst of sparks flying out from an impact
+pub fn spawn_sparks(commands: &mut Commands, config: &GameConfig, position: Vec3)
+{
+    for _ in 0..config.spark_count {
+        let angle = thread_rng().gen_range(0.0..2.0 * PI) as f32;
+        let direction = Vec2::new(angle.cos(), angle.sin());
+        let velocity = direction * config.spark_speed * thread_rng().gen_range(0.5..1.0);
+        spawn_particle(commands, position + Vec3::Z, velocity, config.spark_color, config.spark_size, config.spark_lifetime);
+    }
+}
@@ -0,0 +1,238 @@
+//! Hazards placed around the arena: spinning saws and electric fences switching on and off, at random or where a level puts them.
+//! They hurt players and enemies alike on contact, so pushing enemies into them is as good as hitting them.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    arena::{generate_arena, Arena},
+    cleanup::DespawnOnStateExit,
+    combat::{DamageEvent, DamageKind, Hp},
+    data::LevelTable,
+    enemy::{Enemy, SPAWN_SAFE_RADIUS},
+    levels::{LoadingLevel, SelectedLevel},
+    physics::apply_velocity,
+    player::{player_spawn, Player},
+    rng::GameRng,
+    FixedUpdate, GameState, LevelEntity, TIME_STEP,
+};
+
+const SAW_COUNT: usize = 2;
+const SAW_RADIUS: f32 = 22.0;
+const SAW_TEETH: usize = 10;
+const SAW_COLOR: Color = Color::rgb(0.75, 0.75, 0.8);
+// Radians per second
+const SAW_SPIN_SPEED: f32 = 12.0;
+const SAW_DAMAGE: i32 = 3;
+
+const FENCE_COUNT: usize = 1;
+const FENCE_LENGTH: f32 = 180.0;
+const FENCE_THICKNESS: f32 = 4.0;
+const FENCE_COLOR: Color = Color::rgb(0.4, 0.8, 1.0);
+const FENCE_DAMAGE: i32 = 2;
+// Fences stay live for a while, then switch off for a while
+const FENCE_ON_DURATION: f32 = 2.5;
+const FENCE_OFF_DURATION: f32 = 1.5;
+// How visible a fence is while switched off, and how fast it flickers while live
+const FENCE_OFF_ALPHA: f32 = 0.15;
+const FENCE_FLICKER_INTERVAL: f32 = 0.05;
+
+// Anything touching a hazard is hurt again every this many seconds
+const HAZARD_INTERVAL: f32 = 0.5;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_hazards.after(generate_arena)))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(spawn_hazards.after(generate_arena)))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(spin_saws)
+                    .with_system(cycle_fences)
+                    .with_system(hazard_contact.after(apply_velocity).after(cycle_fences)),
+            );
+    }
+}
+
+// Hurts whatever touches it, once every `HAZARD_INTERVAL`
+#[derive(Component)]
+pub struct Hazard {
+    damage: i32,
+    timer: Timer,
+}
+
+impl Hazard {
+    fn new(damage: i32) -> Self {
+        Self { damage, timer: Timer::from_seconds(HAZARD_INTERVAL, true) }
+    }
+}
+
+#[derive(Component)]
+struct Saw;
+
+// Where a level puts a hazard
+#[derive(Clone, Copy, Deserialize)]
+pub enum HazardPlacement {
+    Saw(Vec2),
+    Fence { center: Vec2, vertical: bool },
+}
+
+// A straight fence between two posts, only hurting anything while live
+#[derive(Component)]
+struct ElectricFence {
+    half_extent: Vec2,
+    live: bool,
+    timer: Timer,
+}
+
+fn spawn_hazards(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+    selected: Res<SelectedLevel>,
+    levels: Option<Res<LevelTable>>,
+)
+{
+    if let Some(level) = selected.get(levels.as_deref()) {
+        for hazard in &level.hazards {
+            match *hazard {
+                HazardPlacement::Saw(position) => spawn_saw(&mut commands, position),
+                HazardPlacement::Fence { center, vertical } => {
+                    let direction = if vertical { Vec2::Y } else { Vec2::X };
+                    spawn_fence(&mut commands, center, direction * FENCE_LENGTH / 2.0);
+                }
+            }
+        }
+        return;
+    }
+
+    let avoid = [player_spawn(&arena)];
+
+    for _ in 0..SAW_COUNT {
+        let position = arena.random_open_position(&mut rng.0, Vec2::splat(SAW_RADIUS), &avoid, SPAWN_SAFE_RADIUS);
+        spawn_saw(&mut commands, position);
+    }
+
+    for _ in 0..FENCE_COUNT {
+        let direction = if rng.0.gen_bool(0.5) { Vec2::X } else { Vec2::Y };
+        let half_extent = direction * FENCE_LENGTH / 2.0;
+        let position = arena.random_open_position(&mut rng.0, half_extent.abs() + FENCE_THICKNESS, &avoid, SPAWN_SAFE_RADIUS);
+        spawn_fence(&mut commands, position, half_extent);
+    }
+}
+
+fn spawn_saw(commands: &mut Commands, position: Vec2)
+{
+    // A star, every other point pulled in between the teeth
+    let points = (0..SAW_TEETH * 2)
+        .map(|index| {
+            let angle = index as f32 / (SAW_TEETH * 2) as f32 * TAU;
+            let radius = if index % 2 == 0 { SAW_RADIUS } else { SAW_RADIUS * 0.7 };
+            Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect();
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Polygon { points, closed: true },
+            DrawMode::Fill(FillMode::color(SAW_COLOR)),
+            Transform::from_translation(position.extend(0.1)),
+        ))
+        .insert(Hazard::new(SAW_DAMAGE))
+        .insert(Saw)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn spawn_fence(commands: &mut Commands, position: Vec2, half_extent: Vec2)
+{
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Line(-half_extent, half_extent),
+            DrawMode::Stroke(StrokeMode::new(FENCE_COLOR, FENCE_THICKNESS)),
+            Transform::from_translation(position.extend(0.1)),
+        ))
+        .insert(Hazard::new(FENCE_DAMAGE))
+        .insert(ElectricFence {
+            half_extent,
+            live: true,
+            timer: Timer::from_seconds(FENCE_ON_DURATION, false),
+        })
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn spin_saws(mut query: Query<&mut Transform, With<Saw>>)
+{
+    for mut transform in &mut query {
+        transform.rotate_z(SAW_SPIN_SPEED * TIME_STEP);
+    }
+}
+
+// Switches fences on and off, flickering while they are live
+fn cycle_fences(mut query: Query<(&mut ElectricFence, &mut DrawMode)>)
+{
+    for (mut fence, mut draw_mode) in &mut query {
+        if fence.timer.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            fence.live = !fence.live;
+            let duration = if fence.live { FENCE_ON_DURATION } else { FENCE_OFF_DURATION };
+            fence.timer = Timer::from_seconds(duration, false);
+        }
+
+        let alpha = if !fence.live {
+            FENCE_OFF_ALPHA
+        } else if (fence.timer.elapsed_secs() / FENCE_FLICKER_INTERVAL) as u32 % 2 == 0 {
+            1.0
+        } else {
+            0.6
+        };
+        if let DrawMode::Stroke(ref mut stroke) = *draw_mode {
+            stroke.color.set_a(alpha);
+        }
+    }
+}
+
+fn hazard_contact(
+    mut hazard_query: Query<(Entity, &mut Hazard, &Transform, Option<&ElectricFence>)>,
+    target_query: Query<(Entity, &Transform), (With<Hp>, Or<(With<Player>, With<Enemy>)>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+)
+{
+    for (hazard_entity, mut hazard, hazard_transform, fence) in &mut hazard_query {
+        if !hazard.timer.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+            continue;
+        }
+        if fence.map_or(false, |fence| !fence.live) {
+            continue;
+        }
+
+        let center = hazard_transform.translation.truncate();
+        for (target, transform) in &target_query {
+            let position = transform.translation.truncate();
+            // Sprites are scaled to their size, so half the scale is the radius
+            let radius = transform.scale.x / 2.0;
+            let touching = match fence {
+                Some(fence) => {
+                    // Closest point on the fence
+                    let along = (position - center).dot(fence.half_extent) / fence.half_extent.length_squared();
+                    let closest = center + fence.half_extent * along.clamp(-1.0, 1.0);
+                    position.distance(closest) < radius + FENCE_THICKNESS / 2.0
+                }
+                None => position.distance(center) < radius + SAW_RADIUS,
+            };
+            if touching {
+                damage_events.send(DamageEvent {
+                    target,
+                    amount: hazard.damage,
+                    source: Some(hazard_entity),
+                    kind: DamageKind::Hazard,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Horde, another alternate game mode: hundreds of weak enemies on the field at once, topped back up as they die.
+//! They skip the spawn telegraph and their health bars to stay cheap, and a run is scored like the wave mode.
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    arena::Arena,
+    data::{DifficultyCurve, EnemyTable},
+    difficulty::Difficulty,
+    enemy::{materialize_enemy, Enemy, EnemyKind, EnemySpawn, EnemySprites, SPAWN_SAFE_RADIUS},
+    leaderboard::RunClock,
+    player::Player,
+    rng::GameRng,
+    survival::GameMode,
+    tutorial::Tutorial,
+    FixedUpdate,
+};
+
+// Enemies kept alive at once
+const HORDE_SIZE: usize = 500;
+// Enemies added per step while short of `HORDE_SIZE`, the horde builds up over a couple of seconds rather than in one go
+const HORDE_BATCH: usize = 25;
+// Horde enemies are this much frailer than the usual ones
+const HORDE_HEALTH_SCALE: f32 = 0.3;
+// Shooters and heavies would make a horde unplayable
+const HORDE_KINDS: [EnemyKind; 2] = [EnemyKind::Chaser, EnemyKind::Orbiter];
+
+pub struct HordePlugin;
+
+impl Plugin for HordePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::new().with_run_criteria(FixedUpdate).with_system(spawn_horde));
+    }
+}
+
+fn spawn_horde(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    arena: Res<Arena>,
+    clock: Res<RunClock>,
+    preset: Res<Difficulty>,
+    tutorial: Res<Tutorial>,
+    sprites: Res<EnemySprites>,
+    enemies: Option<Res<EnemyTable>>,
+    curve: Option<Res<DifficultyCurve>>,
+    mut rng: ResMut<GameRng>,
+    enemy_query: Query<(), With<Enemy>>,
+    player_query: Query<&Transform, With<Player>>,
+)
+{
+    if *mode != GameMode::Horde || tutorial.active() {
+        return;
+    }
+
+    let (enemies, curve) = match (enemies, curve) {
+        (Some(enemies), Some(curve)) => (enemies, curve),
+        _ => return,
+    };
+
+    let missing = (preset.scale_count(HORDE_SIZE as u32) as usize).saturating_sub(enemy_query.iter().count()).min(HORDE_BATCH);
+    if missing == 0 {
+        return;
+    }
+
+    // Like survival, only the clock drives the curve
+    let mut difficulty = preset.apply(curve.at(0, clock.0));
+    difficulty.health *= HORDE_HEALTH_SCALE;
+    let spawns: Vec<EnemySpawn> = HORDE_KINDS.iter().map(|kind| EnemySpawn::new(&enemies, *kind, difficulty)).collect();
+
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+    let rng = &mut rng.0;
+    for _ in 0..missing {
+        let spawn = &spawns[rng.gen_range(0..spawns.len())];
+        let half_size = Vec2::splat(spawn.definition.size / 2.0);
+        let position = arena.random_open_position(rng, half_size, &players, SPAWN_SAFE_RADIUS);
+        let polarity = spawn.random_polarity(rng);
+        materialize_enemy(&mut commands, &sprites, rng, position, spawn, polarity);
+    }
+}
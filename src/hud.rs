@@ -0,0 +1,277 @@
+//! The in-run heads-up display: score, wave (or time survived) and the player's health, each its own text with its own marker,
+//! so more UI can be added without the update systems tripping over each other.
+//! An icon per weapon sits in the bottom right corner, the one the first player holds lit up.
+//! The mines each player has ready show above their health, and the wave's objective below the wave.
+
+use bevy::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    combat::Hp,
+    deployables::Deployables,
+    leaderboard::{format_duration, RunClock},
+    locale::Locale,
+    objectives::{CurrentObjective, Objective},
+    player::Player,
+    survival::GameMode,
+    waves::WaveSpawner,
+    weapons::Weapon,
+    GameState, Scoreboard, SCORE_COLOR, TEXT_COLOR,
+};
+
+pub const HUD_FONT_SIZE: f32 = 40.0;
+pub const HUD_TEXT_PADDING: Val = Val::Px(5.0);
+const WEAPON_ICON_SIZE: f32 = 40.0;
+const WEAPON_ICON_FONT_SIZE: f32 = 24.0;
+// Weapons not in hand are dimmed down to this alpha
+const WEAPON_ICON_IDLE_ALPHA: f32 = 0.25;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_hud))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_score_text)
+                    .with_system(update_wave_text)
+                    .with_system(update_objective_text)
+                    .with_system(update_time_text)
+                    .with_system(update_hp_text)
+                    .with_system(update_mines_text)
+                    .with_system(update_weapon_icons),
+            );
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct WaveText;
+
+#[derive(Component)]
+struct ObjectiveText;
+
+#[derive(Component)]
+struct TimeText;
+
+#[derive(Component)]
+struct HpText;
+
+#[derive(Component)]
+struct MinesText;
+
+#[derive(Component)]
+struct WeaponIcon(Weapon);
+
+// A label followed by a value, filled in by the update systems, pinned to a corner of the screen
+fn hud_text(asset_server: &AssetServer, label: &str, position: UiRect<Val>) -> TextBundle
+{
+    TextBundle::from_sections([
+        TextSection::new(
+            label,
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: HUD_FONT_SIZE,
+                color: TEXT_COLOR,
+            },
+        ),
+        TextSection::from_style(TextStyle {
+            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+            font_size: HUD_FONT_SIZE,
+            color: SCORE_COLOR,
+        }),
+    ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position,
+            ..default()
+        })
+}
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>, mode: Res<GameMode>)
+{
+    commands
+        .spawn_bundle(hud_text(
+            &asset_server,
+            locale.get("hud.score"),
+            UiRect { top: HUD_TEXT_PADDING, left: HUD_TEXT_PADDING, ..default() },
+        ))
+        .insert(ScoreText)
+        .insert(DespawnOnStateExit(GameState::Playing));
+
+    // Survival and horde have no waves, only the clock
+    let top_right = UiRect { top: HUD_TEXT_PADDING, right: HUD_TEXT_PADDING, ..default() };
+    match *mode {
+        GameMode::Waves => {
+            commands
+                .spawn_bundle(hud_text(&asset_server, locale.get("hud.wave"), top_right))
+                .insert(WaveText)
+                .insert(DespawnOnStateExit(GameState::Playing));
+            commands
+                .spawn_bundle(hud_text(
+                    &asset_server,
+                    "",
+                    UiRect { top: Val::Px(HUD_FONT_SIZE + 10.0), right: HUD_TEXT_PADDING, ..default() },
+                ))
+                .insert(ObjectiveText)
+                .insert(DespawnOnStateExit(GameState::Playing));
+        }
+        GameMode::Survival | GameMode::Horde => {
+            commands
+                .spawn_bundle(hud_text(&asset_server, locale.get("hud.time"), top_right))
+                .insert(TimeText)
+                .insert(DespawnOnStateExit(GameState::Playing));
+        }
+    }
+
+    commands
+        .spawn_bundle(hud_text(
+            &asset_server,
+            locale.get("hud.hp"),
+            UiRect { bottom: HUD_TEXT_PADDING, left: HUD_TEXT_PADDING, ..default() },
+        ))
+        .insert(HpText)
+        .insert(DespawnOnStateExit(GameState::Playing));
+
+    commands
+        .spawn_bundle(hud_text(
+            &asset_server,
+            locale.get("hud.mines"),
+            UiRect { bottom: Val::Px(HUD_FONT_SIZE + 10.0), left: HUD_TEXT_PADDING, ..default() },
+        ))
+        .insert(MinesText)
+        .insert(DespawnOnStateExit(GameState::Playing));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { bottom: HUD_TEXT_PADDING, right: HUD_TEXT_PADDING, ..default() },
+                flex_direction: FlexDirection::Row,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .with_children(|row| {
+            for (index, weapon) in Weapon::ALL.into_iter().enumerate() {
+                row.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(WEAPON_ICON_SIZE), Val::Px(WEAPON_ICON_SIZE)),
+                        margin: UiRect::all(HUD_TEXT_PADDING),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: weapon.color().into(),
+                    ..default()
+                })
+                    .insert(WeaponIcon(weapon))
+                    .with_children(|icon| {
+                        // The number key selecting it
+                        icon.spawn_bundle(TextBundle::from_section(
+                            (index + 1).to_string(),
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                                font_size: WEAPON_ICON_FONT_SIZE,
+                                color: Color::BLACK,
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_score_text(scoreboard: Res<Scoreboard>, mut query: Query<&mut Text, With<ScoreText>>)
+{
+    for mut text in &mut query {
+        text.sections[1].value = scoreboard.score.to_string();
+    }
+}
+
+fn update_wave_text(spawner: Res<WaveSpawner>, mut query: Query<&mut Text, With<WaveText>>)
+{
+    for mut text in &mut query {
+        text.sections[1].value = spawner.wave.to_string();
+    }
+}
+
+// The label names the objective, the value what is left of it
+fn update_objective_text(
+    current: Res<CurrentObjective>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ObjectiveText>>,
+)
+{
+    let (label, value) = match current.objective {
+        None => ("", String::new()),
+        Some(_) if current.complete => (locale.get("objective.complete"), String::new()),
+        Some(objective) => {
+            let value = match objective {
+                Objective::ClearAllEnemies | Objective::DestroySpawners => (current.remaining as u32).to_string(),
+                Objective::SurviveTimer(_) => format_duration(current.remaining.ceil()),
+                Objective::EscortPayload => format!("{:.0}%", (1.0 - current.remaining) * 100.0),
+            };
+            (locale.get(objective.key()), value)
+        }
+    };
+
+    for mut text in &mut query {
+        text.sections[0].value = label.to_string();
+        text.sections[1].value = value.clone();
+    }
+}
+
+fn update_time_text(clock: Res<RunClock>, mut query: Query<&mut Text, With<TimeText>>)
+{
+    for mut text in &mut query {
+        text.sections[1].value = format_duration(clock.0);
+    }
+}
+
+// Every player's, first player first
+fn update_hp_text(player_query: Query<(&Player, &Hp)>, mut query: Query<&mut Text, With<HpText>>)
+{
+    let mut players: Vec<(&Player, &Hp)> = player_query.iter().collect();
+    players.sort_by_key(|(player, _)| player.0);
+    let value = players
+        .iter()
+        .map(|(_, hp)| format!("{}/{}", hp.current.max(0), hp.max))
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    for mut text in &mut query {
+        text.sections[1].value = value.clone();
+    }
+}
+
+// Same order as the health
+fn update_mines_text(player_query: Query<(&Player, &Deployables)>, mut query: Query<&mut Text, With<MinesText>>)
+{
+    let mut players: Vec<(&Player, &Deployables)> = player_query.iter().collect();
+    players.sort_by_key(|(player, _)| player.0);
+    let value = players
+        .iter()
+        .map(|(_, deployables)| format!("{}/{}", deployables.mines, deployables.max_mines))
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    for mut text in &mut query {
+        text.sections[1].value = value.clone();
+    }
+}
+
+fn update_weapon_icons(player_query: Query<(&Player, &Weapon)>, mut query: Query<(&WeaponIcon, &mut UiColor)>)
+{
+    let held = player_query.iter().min_by_key(|(player, _)| player.0).map(|(_, weapon)| *weapon);
+    for (icon, mut color) in &mut query {
+        let mut icon_color = icon.0.color();
+        if held != Some(icon.0) {
+            icon_color.set_a(WEAPON_ICON_IDLE_ALPHA);
+        }
+        color.0 = icon_color;
+    }
+}
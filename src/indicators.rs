@@ -0,0 +1,119 @@
+//! Arrows along the edge of the view pointing at every enemy outside of it, in the color of its kind,
+//! so nothing comes at the players unannounced on an arena bigger than the screen. Horde runs go without them,
+//! hundreds of enemies are everywhere anyway, and an arrow for each would cost more than the enemies themselves.
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    camera::{follow_player, visible_size, FollowCamera},
+    cleanup::DespawnOnStateExit,
+    data::EnemyTable,
+    enemy::{Enemy, EnemyKind},
+    survival::GameMode,
+    GameState, LevelEntity, WALL_COLOR,
+};
+
+const ARROW_LENGTH: f32 = 22.0;
+const ARROW_WIDTH: f32 = 16.0;
+// Kept between the arrows and the edge of the view, in screen pixels
+const INDICATOR_MARGIN: f32 = 24.0;
+// Above everything in the arena
+const INDICATOR_Z: f32 = 10.0;
+
+pub struct IndicatorPlugin;
+
+impl Plugin for IndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_indicators)
+            .add_system(update_indicators.after(spawn_indicators).after(follow_player));
+    }
+}
+
+// Points at `0`, and goes once it is gone
+#[derive(Component)]
+struct OffscreenIndicator(Entity);
+
+fn spawn_indicators(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    enemies: Option<Res<EnemyTable>>,
+    enemy_query: Query<(Entity, Option<&EnemyKind>), Added<Enemy>>,
+)
+{
+    if *mode == GameMode::Horde {
+        return;
+    }
+    for (enemy, kind) in &enemy_query {
+        // Bosses have no kind of their own
+        let color = match (kind, enemies.as_ref()) {
+            (Some(kind), Some(enemies)) => enemies.get(*kind).color,
+            _ => WALL_COLOR,
+        };
+        // Pointing right, it is turned towards its enemy
+        let arrow = shapes::Polygon {
+            points: vec![
+                Vec2::new(ARROW_LENGTH / 2.0, 0.0),
+                Vec2::new(-ARROW_LENGTH / 2.0, ARROW_WIDTH / 2.0),
+                Vec2::new(-ARROW_LENGTH / 2.0, -ARROW_WIDTH / 2.0),
+            ],
+            closed: true,
+        };
+
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &arrow,
+                DrawMode::Fill(FillMode::color(color)),
+                Transform::default(),
+            ))
+            .insert(Visibility { is_visible: false })
+            .insert(OffscreenIndicator(enemy))
+            .insert(LevelEntity)
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+// Each arrow sits where the line from the middle of the view to its enemy leaves the view, and keeps its size
+// on screen whatever the zoom. Hidden while the enemy is in view, and outside of play.
+fn update_indicators(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<FollowCamera>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<OffscreenIndicator>)>,
+    mut indicator_query: Query<
+        (Entity, &OffscreenIndicator, &mut Transform, &mut Visibility),
+        (Without<FollowCamera>, Without<Enemy>),
+    >,
+)
+{
+    let (center, projection) = match camera_query.get_single() {
+        Ok((transform, projection)) => (transform.translation.truncate(), projection),
+        Err(_) => return,
+    };
+    let playing = *state.current() == GameState::Playing;
+    let half_view = visible_size(projection) / 2.0;
+    let inset = (half_view - Vec2::splat(INDICATOR_MARGIN * projection.scale)).max(Vec2::ONE);
+
+    for (entity, indicator, mut transform, mut visibility) in &mut indicator_query {
+        let enemy = match enemy_query.get(indicator.0) {
+            Ok(enemy) => enemy.translation.truncate(),
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+
+        let offset = enemy - center;
+        let on_screen = offset.x.abs() <= half_view.x && offset.y.abs() <= half_view.y;
+        visibility.is_visible = playing && !on_screen;
+        if on_screen {
+            continue;
+        }
+
+        // Scaled down until it touches the inset edge it reaches first
+        let reach = (inset / offset.abs()).min_element();
+        transform.translation = (center + offset * reach).extend(INDICATOR_Z);
+        transform.rotation = Quat::from_rotation_z(offset.y.atan2(offset.x));
+        transform.scale = Vec3::splat(projection.scale);
+    }
+}
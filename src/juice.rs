@@ -0,0 +1,167 @@
+//! Screen shake, hit-stop and the red flash when the player is hurt, making hits and kills feel heavier.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{cleanup::DespawnOnStateExit, combat::PlayerHurtEvent, GameState};
+
+// How far the camera is thrown at full trauma, in pixels
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+// Trauma lost per second
+const TRAUMA_DECAY: f32 = 2.0;
+
+pub const ENEMY_DEATH_TRAUMA: f32 = 0.2;
+pub const BOSS_HIT_TRAUMA: f32 = 0.3;
+pub const PLAYER_DAMAGE_TRAUMA: f32 = 0.5;
+
+// Seconds the simulation freezes for when an enemy is killed
+const HIT_STOP_DURATION: f32 = 0.05;
+
+// The screen flashes this red when the player is hurt, fading out over `DAMAGE_FLASH_DURATION` seconds
+const DAMAGE_FLASH_COLOR: Color = Color::rgba(0.8, 0.0, 0.0, 0.35);
+const DAMAGE_FLASH_DURATION: f32 = 0.3;
+
+pub struct JuicePlugin;
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JuiceSettings>()
+            .init_resource::<ScreenShake>()
+            .init_resource::<HitStop>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(reset_juice)
+                    .with_system(spawn_damage_flash),
+            )
+            .add_system(update_screen_shake)
+            .add_system(tick_hit_stop)
+            .add_system(flash_on_damage);
+    }
+}
+
+// Tuning for the effects, either can be turned off by setting it to zero
+pub struct JuiceSettings {
+    // Multiplies how far the camera shakes
+    pub shake_intensity: f32,
+    // Multiplies how long kills freeze the game
+    pub hit_stop_scale: f32,
+}
+
+impl Default for JuiceSettings {
+    fn default() -> Self {
+        Self {
+            shake_intensity: 1.0,
+            hit_stop_scale: 1.0,
+        }
+    }
+}
+
+// Trauma builds up with every hit and decays over time, the camera shakes with its square
+#[derive(Default)]
+pub struct ScreenShake {
+    trauma: f32,
+    offset: Vec2,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    // How far the camera is thrown this frame, added on top of where it is looking
+    pub fn offset(&self) -> Vec2 {
+        self.offset
+    }
+}
+
+// Freezes the fixed timestep for a moment
+pub struct HitStop(Timer);
+
+impl Default for HitStop {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(HIT_STOP_DURATION, false);
+        timer.tick(Duration::from_secs_f32(HIT_STOP_DURATION));
+        Self(timer)
+    }
+}
+
+impl HitStop {
+    pub fn trigger(&mut self, settings: &JuiceSettings) {
+        let duration = HIT_STOP_DURATION * settings.hit_stop_scale;
+        if duration > 0.0 {
+            self.0 = Timer::from_seconds(duration, false);
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        !self.0.finished()
+    }
+}
+
+// Full screen overlay, invisible until the player gets hurt
+#[derive(Component)]
+struct DamageFlash(Timer);
+
+fn reset_juice(mut shake: ResMut<ScreenShake>, mut hit_stop: ResMut<HitStop>)
+{
+    *shake = ScreenShake::default();
+    *hit_stop = HitStop::default();
+}
+
+// Hit-stop runs on real time, since the fixed timestep is what it freezes
+fn tick_hit_stop(time: Res<Time>, mut hit_stop: ResMut<HitStop>)
+{
+    hit_stop.0.tick(time.delta());
+}
+
+pub fn update_screen_shake(time: Res<Time>, settings: Res<JuiceSettings>, mut shake: ResMut<ScreenShake>)
+{
+    shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+
+    let strength = MAX_SHAKE_OFFSET * settings.shake_intensity * shake.trauma * shake.trauma;
+    shake.offset = Vec2::new(
+        thread_rng().gen_range(-1.0..=1.0),
+        thread_rng().gen_range(-1.0..=1.0),
+    ) * strength;
+}
+
+fn spawn_damage_flash(mut commands: Commands)
+{
+    let mut timer = Timer::from_seconds(DAMAGE_FLASH_DURATION, false);
+    timer.tick(Duration::from_secs_f32(DAMAGE_FLASH_DURATION));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(DamageFlash(timer))
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+fn flash_on_damage(
+    time: Res<Time>,
+    mut player_hurt_events: EventReader<PlayerHurtEvent>,
+    mut query: Query<(&mut DamageFlash, &mut UiColor)>,
+)
+{
+    let hurt = player_hurt_events.iter().count() > 0;
+
+    for (mut flash, mut color) in &mut query {
+        if hurt {
+            flash.0.reset();
+        }
+        flash.0.tick(time.delta());
+
+        let mut flash_color = DAMAGE_FLASH_COLOR;
+        flash_color.set_a(DAMAGE_FLASH_COLOR.a() * flash.0.percent_left());
+        color.0 = flash_color;
+    }
+}
@@ -0,0 +1,316 @@
+//! The best runs so far, kept in a local leaderboard file with a table per game mode. A run good enough
+//! to make its table asks for a name on the game over screen.
+
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    locale::Locale,
+    survival::GameMode,
+    ui::{menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE},
+    waves::WaveSpawner,
+    FixedUpdate, GameState, Scoreboard, SCORE_COLOR, TEXT_COLOR, TIME_STEP, WALL_COLOR,
+};
+
+const LEADERBOARD_PATH: &str = "leaderboard.ron";
+const LEADERBOARD_SIZE: usize = 10;
+const MAX_NAME_LENGTH: usize = 12;
+const LEADERBOARD_FONT_SIZE: f32 = 24.0;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load())
+            .init_resource::<RunClock>()
+            .init_resource::<NameEntry>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_run_clock))
+            .add_system_set(SystemSet::new().with_run_criteria(FixedUpdate).with_system(tick_run_clock))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(enter_name)
+                    .with_system(update_leaderboard_text),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Leaderboard).with_system(setup_leaderboard_screen))
+            .add_system_set(
+                SystemSet::on_update(GameState::Leaderboard)
+                    .with_system(leaderboard_menu)
+                    .with_system(update_leaderboard_text),
+            );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: i32,
+    pub wave: u32,
+    // Seconds the run lasted, not counting time spent paused
+    pub duration: f32,
+    // Missing from entries written before survival existed
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+impl LeaderboardEntry {
+    // Wave and horde runs are ranked by score, survival runs by how long they lasted
+    fn rank(&self) -> f32 {
+        match self.mode {
+            GameMode::Waves | GameMode::Horde => self.score as f32,
+            GameMode::Survival => self.duration,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    // Best first, the tables of all modes mixed together
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    fn load() -> Leaderboard {
+        let contents = match fs::read_to_string(LEADERBOARD_PATH) {
+            Ok(contents) => contents,
+            Err(_) => return Leaderboard::default(),
+        };
+
+        match ron::from_str(&contents) {
+            Ok(leaderboard) => leaderboard,
+            Err(error) => {
+                warn!("Ignoring unreadable leaderboard {}: {}", LEADERBOARD_PATH, error);
+                Leaderboard::default()
+            }
+        }
+    }
+
+    fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(LEADERBOARD_PATH, contents).map_err(|error| error.to_string()));
+
+        if let Err(error) = result {
+            error!("Failed to write leaderboard {}: {}", LEADERBOARD_PATH, error);
+        }
+    }
+
+    fn entries(&self, mode: GameMode) -> impl Iterator<Item = &LeaderboardEntry> {
+        self.entries.iter().filter(move |entry| entry.mode == mode)
+    }
+
+    pub fn qualifies(&self, candidate: &LeaderboardEntry) -> bool {
+        self.entries(candidate.mode).count() < LEADERBOARD_SIZE
+            || self.entries(candidate.mode).any(|entry| candidate.rank() > entry.rank())
+    }
+
+    fn insert(&mut self, entry: LeaderboardEntry) {
+        let mode = entry.mode;
+        let index = self.entries.partition_point(|existing| existing.rank() >= entry.rank());
+        self.entries.insert(index, entry);
+
+        // Only the table the entry went into can have grown too long
+        let mut kept = 0;
+        self.entries.retain(|existing| {
+            if existing.mode != mode {
+                return true;
+            }
+            kept += 1;
+            kept <= LEADERBOARD_SIZE
+        });
+    }
+
+    fn table(&self, mode: GameMode, locale: &Locale) -> String {
+        if self.entries(mode).next().is_none() {
+            return locale.get("leaderboard.empty").to_string();
+        }
+
+        self.entries(mode)
+            .enumerate()
+            .map(|(rank, entry)| {
+                format!(
+                    "{:>2}. {:<12} {:>6}  {} {:>2}  {}",
+                    rank + 1,
+                    entry.name,
+                    entry.score,
+                    locale.get("leaderboard.wave"),
+                    entry.wave,
+                    format_duration(entry.duration),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Time spent in the current run
+#[derive(Default)]
+pub struct RunClock(pub f32);
+
+// As minutes and seconds, e.g. "3:07"
+pub fn format_duration(seconds: f32) -> String
+{
+    format!("{}:{:02}", seconds as u32 / 60, seconds as u32 % 60)
+}
+
+// The name being typed for a new leaderboard entry
+#[derive(Default)]
+pub struct NameEntry {
+    pub active: bool,
+    name: String,
+}
+
+#[derive(Component)]
+struct LeaderboardText;
+
+#[derive(Component)]
+struct NameEntryText;
+
+fn reset_run_clock(mut clock: ResMut<RunClock>)
+{
+    clock.0 = 0.0;
+}
+
+fn tick_run_clock(mut clock: ResMut<RunClock>)
+{
+    clock.0 += TIME_STEP;
+}
+
+// The finished run, under a name still to be entered
+fn run_entry(name: String, mode: GameMode, scoreboard: &Scoreboard, spawner: &WaveSpawner, clock: &RunClock) -> LeaderboardEntry
+{
+    LeaderboardEntry {
+        name,
+        score: scoreboard.score,
+        wave: spawner.wave,
+        duration: clock.0,
+        mode,
+    }
+}
+
+// Starts name entry if the run made the table, run before the game over screen is built
+pub fn begin_name_entry(
+    leaderboard: Res<Leaderboard>,
+    mode: Res<GameMode>,
+    scoreboard: Res<Scoreboard>,
+    spawner: Res<WaveSpawner>,
+    clock: Res<RunClock>,
+    mut name_entry: ResMut<NameEntry>,
+)
+{
+    *name_entry = NameEntry {
+        active: leaderboard.qualifies(&run_entry(String::new(), *mode, &scoreboard, &spawner, &clock)),
+        name: String::new(),
+    };
+}
+
+// The leaderboard and, if a name is being entered, its prompt
+pub fn spawn_leaderboard(parent: &mut ChildBuilder, asset_server: &AssetServer, name_entry: &NameEntry)
+{
+    if name_entry.active {
+        parent
+            .spawn_bundle(menu_text(asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(NameEntryText);
+    }
+
+    parent
+        .spawn_bundle(TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: LEADERBOARD_FONT_SIZE,
+                color: WALL_COLOR,
+            },
+        ))
+        .insert(LeaderboardText);
+}
+
+fn enter_name(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut name_entry: ResMut<NameEntry>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mode: Res<GameMode>,
+    scoreboard: Res<Scoreboard>,
+    spawner: Res<WaveSpawner>,
+    clock: Res<RunClock>,
+    locale: Res<Locale>,
+    mut text_query: Query<(Entity, &mut Text), With<NameEntryText>>,
+)
+{
+    if !name_entry.active {
+        characters.clear();
+        return;
+    }
+
+    for character in characters.iter() {
+        if character.char.is_alphanumeric() && name_entry.name.chars().count() < MAX_NAME_LENGTH {
+            name_entry.name.push(character.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        name_entry.name.pop();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) && !name_entry.name.is_empty() {
+        leaderboard.insert(run_entry(name_entry.name.clone(), *mode, &scoreboard, &spawner, &clock));
+        leaderboard.write();
+        name_entry.active = false;
+
+        for (entity, _) in &text_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    for (_, mut text) in &mut text_query {
+        text.sections[0].value = locale.format("leaderboard.name_entry", &[("name", name_entry.name.clone())]);
+    }
+}
+
+// Shows the table of the mode picked on the main menu
+fn update_leaderboard_text(
+    leaderboard: Res<Leaderboard>,
+    mode: Res<GameMode>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<LeaderboardText>>,
+)
+{
+    if !leaderboard.is_changed() && !mode.is_changed() && !locale.is_changed() && query.iter().all(|text| !text.sections[0].value.is_empty()) {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.sections[0].value = leaderboard.table(*mode, &locale);
+    }
+}
+
+fn setup_leaderboard_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mode: Res<GameMode>,
+)
+{
+    let title = match *mode {
+        GameMode::Waves => "leaderboard.title.waves",
+        GameMode::Survival => "leaderboard.title.survival",
+        GameMode::Horde => "leaderboard.title.horde",
+    };
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::Leaderboard), Color::NONE, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get(title), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        spawn_leaderboard(parent, &asset_server, &NameEntry::default());
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("leaderboard.back"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+    });
+}
+
+fn leaderboard_menu(keyboard_input: Res<Input<KeyCode>>, mut state: ResMut<State<GameState>>)
+{
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        let _ = state.set(GameState::MainMenu);
+    }
+}
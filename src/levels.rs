@@ -0,0 +1,319 @@
+//! The level select: picks one of the handcrafted levels in `assets/data/levels.ron`, or a random arena as before.
+//! The pick is kept across restarts; the arena, hazards and the level's wave are then built from it.
+//!
+//! Levels are played in order from the one picked. Clearing one shows a transition screen and heals the players
+//! part of the way, then everything belonging to the level is torn down and the next one is built around them,
+//! with the shop in between. Score and upgrades carry over, and a restart picks up from the start of the level.
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    combat::Hp,
+    data::{LevelDefinition, LevelTable},
+    locale::Locale,
+    player::Player,
+    stats::{spawn_run_stats, RunStats},
+    survival::GameMode,
+    ui::{gamepad_just_pressed, menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE, OVERLAY_COLOR},
+    upgrades::{reset_player_stats, PlayerStats},
+    GameState, Scoreboard, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+};
+
+// Fraction of their missing health the players get back between levels
+const TRANSITION_HEAL: f32 = 0.5;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedLevel>()
+            .init_resource::<LevelCursor>()
+            .init_resource::<RunState>()
+            .add_event::<LevelClearedEvent>()
+            .add_event::<LoadLevelEvent>()
+            .add_system_set(SystemSet::new().with_run_criteria(loading_level.label(LoadingLevel)))
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(rewind_run))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(restore_checkpoint.after(reset_player_stats)))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(open_transition_on_level_cleared))
+            .add_system_set(SystemSet::on_exit(GameState::Upgrade).with_system(update_checkpoint))
+            .add_system_set(SystemSet::on_enter(GameState::LevelSelect).with_system(setup_level_select))
+            .add_system_set(
+                SystemSet::on_update(GameState::LevelSelect)
+                    .with_system(level_select_menu)
+                    .with_system(update_level_text.after(level_select_menu)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::LevelTransition).with_system(setup_level_transition))
+            .add_system_set(SystemSet::on_update(GameState::LevelTransition).with_system(level_transition_menu));
+    }
+}
+
+// Sent once the wave of a level has been won
+pub struct LevelClearedEvent;
+
+// Sent to build the selected level around the players, once the last one has been torn down
+pub struct LoadLevelEvent;
+
+// Runs whatever builds a level, once for every `LoadLevelEvent`, the same way entering `Playing` does for the first one
+#[derive(RunCriteriaLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoadingLevel;
+
+fn loading_level(mut events: EventReader<LoadLevelEvent>) -> ShouldRun
+{
+    if events.iter().count() > 0 {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// What the run carries from one level to the next
+#[derive(Default)]
+pub struct RunState {
+    // The start of the level being played, restored when it is restarted. None on the first level
+    checkpoint: Option<Checkpoint>,
+    // The level the run started on, played from again once the last one is cleared
+    first_level: Option<usize>,
+    // Whether the run ended by clearing the last level
+    completed: bool,
+}
+
+impl RunState {
+    pub fn completed(&self) -> bool {
+        self.completed
+    }
+}
+
+struct Checkpoint {
+    level: usize,
+    score: i32,
+    stats: PlayerStats,
+}
+
+// The level picked on the level select, kept across restarts. None plays a random arena
+#[derive(Default)]
+pub struct SelectedLevel(pub Option<usize>);
+
+impl SelectedLevel {
+    pub fn get<'a>(&self, levels: Option<&'a LevelTable>) -> Option<&'a LevelDefinition> {
+        self.0.and_then(|index| levels?.levels.get(index))
+    }
+
+    // As shown on the main menu, translated
+    pub fn name<'a>(&self, levels: Option<&'a LevelTable>, locale: &'a Locale) -> &'a str {
+        match self.get(levels) {
+            Some(level) => locale.get(&level.name),
+            None => locale.get("levels.random"),
+        }
+    }
+}
+
+// The highlighted entry, the random arena first and then each level in order
+#[derive(Default)]
+struct LevelCursor(usize);
+
+#[derive(Component)]
+struct LevelEntryText(usize);
+
+fn setup_level_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    levels: Option<Res<LevelTable>>,
+    selected: Res<SelectedLevel>,
+    mut cursor: ResMut<LevelCursor>,
+)
+{
+    cursor.0 = selected.0.map_or(0, |index| index + 1);
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::LevelSelect), Color::NONE, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("levels.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        parent
+            .spawn_bundle(menu_text(&asset_server, locale.get("levels.random"), MENU_TEXT_FONT_SIZE, WALL_COLOR))
+            .insert(LevelEntryText(0));
+        for (index, level) in levels.iter().flat_map(|levels| levels.levels.iter()).enumerate() {
+            parent
+                .spawn_bundle(menu_text(&asset_server, locale.get(&level.name), MENU_TEXT_FONT_SIZE, WALL_COLOR))
+                .insert(LevelEntryText(index + 1));
+        }
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("levels.hint"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+    });
+}
+
+// Navigated with the arrow keys or the d-pad, started with Enter or the south button
+fn level_select_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    levels: Option<Res<LevelTable>>,
+    mut cursor: ResMut<LevelCursor>,
+    mut selected: ResMut<SelectedLevel>,
+    mut mode: ResMut<GameMode>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    let pressed = |key, button| {
+        keyboard_input.just_pressed(key) || gamepad_just_pressed(&gamepads, &gamepad_buttons, button)
+    };
+
+    let count = levels.map_or(0, |levels| levels.levels.len()) + 1;
+    if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    } else if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+    } else if pressed(KeyCode::Return, GamepadButtonType::South) {
+        selected.0 = cursor.0.checked_sub(1);
+        // Levels are played as waves, whatever mode was picked before
+        if selected.0.is_some() {
+            *mode = GameMode::Waves;
+        }
+        let _ = state.set(GameState::Playing);
+    } else if pressed(KeyCode::Back, GamepadButtonType::East) {
+        let _ = state.set(GameState::MainMenu);
+    }
+}
+
+fn update_level_text(cursor: Res<LevelCursor>, mut query: Query<(&mut Text, &LevelEntryText)>)
+{
+    for (mut text, entry) in &mut query {
+        text.sections[0].style.color = if entry.0 == cursor.0 { SCORE_COLOR } else { WALL_COLOR };
+    }
+}
+
+// Back on the main menu, the next run starts over from the level the last one started on
+fn rewind_run(mut run: ResMut<RunState>, mut selected: ResMut<SelectedLevel>)
+{
+    if run.checkpoint.take().is_some() {
+        selected.0 = run.first_level;
+    }
+}
+
+// A restart of the level the checkpoint was taken for goes back to it, any other run starts out fresh
+fn restore_checkpoint(
+    selected: Res<SelectedLevel>,
+    mut run: ResMut<RunState>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut stats: ResMut<PlayerStats>,
+)
+{
+    run.completed = false;
+    match &run.checkpoint {
+        Some(checkpoint) if selected.0 == Some(checkpoint.level) => {
+            scoreboard.score = checkpoint.score;
+            *stats = checkpoint.stats.clone();
+        }
+        _ => {
+            run.checkpoint = None;
+            run.first_level = selected.0;
+        }
+    }
+}
+
+// What is bought in the shop between levels is kept by the checkpoint too
+fn update_checkpoint(scoreboard: Res<Scoreboard>, stats: Res<PlayerStats>, mut run: ResMut<RunState>)
+{
+    if let Some(checkpoint) = &mut run.checkpoint {
+        checkpoint.score = scoreboard.score;
+        checkpoint.stats = stats.clone();
+    }
+}
+
+fn open_transition_on_level_cleared(
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if level_cleared_events.iter().last().is_some() {
+        let _ = state.push(GameState::LevelTransition);
+    }
+}
+
+// Patches the players up and sums up the level just cleared
+fn setup_level_transition(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    levels: Option<Res<LevelTable>>,
+    selected: Res<SelectedLevel>,
+    scoreboard: Res<Scoreboard>,
+    run_stats: Res<RunStats>,
+    mut player_query: Query<&mut Hp, With<Player>>,
+)
+{
+    let mut healed = 0;
+    for mut hp in &mut player_query {
+        let gained = ((hp.max - hp.current) as f32 * TRANSITION_HEAL).round() as i32;
+        hp.current += gained;
+        healed = healed.max(gained);
+    }
+
+    let next = selected.0.and_then(|index| levels.as_ref()?.levels.get(index + 1));
+    let hint = match next {
+        Some(next) => locale.format("transition.continue", &[("level", locale.get(&next.name).to_string())]),
+        None => locale.get("transition.finish").to_string(),
+    };
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::LevelTransition), OVERLAY_COLOR, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("transition.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            selected.name(levels.as_deref(), &locale),
+            MENU_TEXT_FONT_SIZE,
+            SCORE_COLOR,
+        ));
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            locale.format("transition.score", &[("score", scoreboard.score.to_string())]),
+            MENU_TEXT_FONT_SIZE,
+            WALL_COLOR,
+        ));
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            locale.format("transition.healed", &[("hp", healed.to_string())]),
+            MENU_TEXT_FONT_SIZE,
+            WALL_COLOR,
+        ));
+        spawn_run_stats(parent, &asset_server, &locale, &run_stats);
+        parent.spawn_bundle(menu_text(&asset_server, hint, MENU_TEXT_FONT_SIZE, WALL_COLOR));
+    });
+}
+
+// Enter or the south button moves on to the shop and then the next level, or ends the run after the last one
+fn level_transition_menu(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    levels: Option<Res<LevelTable>>,
+    scoreboard: Res<Scoreboard>,
+    stats: Res<PlayerStats>,
+    mut selected: ResMut<SelectedLevel>,
+    mut run: ResMut<RunState>,
+    mut load_events: EventWriter<LoadLevelEvent>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if !keyboard_input.just_pressed(KeyCode::Return)
+        && !gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::South)
+    {
+        return;
+    }
+    // Otherwise the shop opening next would take the same press as its own and close straight away
+    keyboard_input.reset(KeyCode::Return);
+
+    let count = levels.map_or(0, |levels| levels.levels.len());
+    match selected.0.map(|index| index + 1).filter(|next| *next < count) {
+        Some(next) => {
+            selected.0 = Some(next);
+            run.checkpoint = Some(Checkpoint { level: next, score: scoreboard.score, stats: stats.clone() });
+            load_events.send(LoadLevelEvent);
+            // The last level is torn down and the next one built underneath the shop, ready once it is closed
+            let _ = state.set(GameState::Upgrade);
+        }
+        None => {
+            selected.0 = run.first_level;
+            run.checkpoint = None;
+            run.completed = true;
+            let _ = state.set(GameState::GameOver);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! A top-down arena brawler where the only weapon is a magnet: pull enemies in, fling them into
+//! walls and each other, and survive wave after wave.
+//!
+//! The game is built here and run from `main.rs`. `headless_app` builds the same game without a window,
+//! rendering or audio, so integration tests can step it and check on the run.
+
+use std::time::Duration;
+
+use bevy::{
+    asset::AssetServerSettings,
+    audio::{AddAudioSource, AudioSink},
+    ecs::schedule::ShouldRun,
+    input::InputPlugin,
+    prelude::*,
+    time::TimePlugin,
+    window::WindowPlugin,
+};
+use bevy_prototype_lyon::prelude::*;
+use bevy_simple_stat_bars::prelude::*;
+
+pub mod achievements;
+pub mod actions;
+pub mod ai;
+pub mod animation;
+pub mod anomalies;
+pub mod arena;
+pub mod boss;
+pub mod bullet_time;
+pub mod camera;
+pub mod cleanup;
+pub mod cli;
+pub mod combat;
+pub mod combo;
+pub mod config;
+pub mod cutscene;
+pub mod damage_numbers;
+pub mod data;
+pub mod debris;
+pub mod debug;
+pub mod deployables;
+pub mod display;
+pub mod difficulty;
+pub mod drones;
+#[cfg(feature = "embedded_assets")]
+pub mod embedded;
+pub mod enemy;
+pub mod fx;
+pub mod hazards;
+pub mod horde;
+pub mod hud;
+pub mod indicators;
+pub mod juice;
+pub mod leaderboard;
+pub mod levels;
+pub mod link;
+pub mod loading;
+pub mod locale;
+pub mod lock_on;
+pub mod loot;
+pub mod magnet;
+pub mod magnet_railgun;
+pub mod objectives;
+pub mod particles;
+pub mod payload;
+pub mod photo;
+pub mod physics;
+pub mod pickups;
+pub mod player;
+pub mod pool;
+pub mod projectiles;
+pub mod rng;
+pub mod save;
+pub mod settings;
+pub mod shield;
+pub mod sound;
+pub mod spatial;
+pub mod spawners;
+pub mod stats;
+pub mod status;
+pub mod survival;
+pub mod teleporters;
+pub mod time_scale;
+pub mod toast;
+pub mod touch;
+pub mod tutorial;
+pub mod tween;
+pub mod ui;
+pub mod upgrades;
+pub mod waves;
+pub mod weapons;
+
+use achievements::AchievementPlugin;
+use actions::ActionsPlugin;
+use ai::EnemyAiPlugin;
+use animation::AnimationPlugin;
+use anomalies::AnomalyPlugin;
+use arena::{Arena, ArenaPlugin, DEFAULT_ARENA_SIZE};
+use boss::BossPlugin;
+use bullet_time::BulletTimePlugin;
+use camera::{FollowCamera, FollowCameraPlugin};
+use cleanup::CleanupPlugin;
+use cli::Options;
+use combat::CombatPlugin;
+use combo::ComboPlugin;
+use cutscene::{CameraDirector, CutscenePlugin};
+use damage_numbers::DamageNumberPlugin;
+use data::DataPlugin;
+use debris::DebrisPlugin;
+use debug::DebugPlugin;
+use deployables::DeployablePlugin;
+use display::DisplayPlugin;
+use difficulty::DifficultyPlugin;
+use drones::DronePlugin;
+use enemy::EnemyPlugin;
+use fx::FxPlugin;
+use hazards::HazardPlugin;
+use horde::HordePlugin;
+use hud::HudPlugin;
+use indicators::IndicatorPlugin;
+use juice::{HitStop, JuicePlugin};
+use leaderboard::LeaderboardPlugin;
+use levels::LevelPlugin;
+use link::LinkPlugin;
+use loading::LoadingPlugin;
+use locale::LocalePlugin;
+use lock_on::LockOnPlugin;
+use loot::LootPlugin;
+use magnet::MagnetPlugin;
+use magnet_railgun::MagnetRailgunPlugin;
+use objectives::ObjectivePlugin;
+use particles::ParticlePlugin;
+use payload::PayloadPlugin;
+use photo::PhotoModePlugin;
+use physics::PhysicsPlugin;
+use pickups::PickupPlugin;
+use player::PlayerPlugin;
+use projectiles::ProjectilePlugin;
+use rng::RngPlugin;
+use save::SavePlugin;
+use settings::SettingsPlugin;
+use shield::ShieldPlugin;
+use sound::{PannedSound, SoundPlugin};
+use spatial::SpatialHashPlugin;
+use spawners::SpawnerPlugin;
+use stats::RunStatsPlugin;
+use status::StatusPlugin;
+use survival::SurvivalPlugin;
+use teleporters::TeleporterPlugin;
+use time_scale::{scaled_fixed_timestep, TimeScalePlugin};
+use toast::ToastPlugin;
+use touch::TouchPlugin;
+use tutorial::TutorialPlugin;
+use tween::TweenPlugin;
+use ui::UiPlugin;
+use upgrades::UpgradePlugin;
+use waves::WavePlugin;
+use weapons::WeaponPlugin;
+
+// Defines the amount of time that should elapse between each physics step.
+const TIME_STEP: f32 = 1.0 / 60.0;
+
+const WALL_THICKNESS: f32 = 10.0;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.05, 0.05, 0.05);
+const WALL_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
+const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+// The whole game, in a window
+pub fn app(options: Options) -> App
+{
+    let mut app = App::new();
+    app.insert_resource(options.window_descriptor())
+        // Lets `GameConfig` and the other data files be edited while the game runs
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: true,
+            ..default()
+        })
+        .add_plugins_with(DefaultPlugins, |group| {
+            // Reads the assets compiled into the binary rather than the `assets` folder
+            #[cfg(feature = "embedded_assets")]
+            group.add_before::<AssetPlugin, _>(embedded::EmbeddedAssetPlugin);
+            group
+        })
+        .add_state(GameState::Loading)
+        // The fixed timestep every plugin steps its gameplay systems with, through the `FixedUpdate` label,
+        // as often as `TimeScale` has it run
+        .add_system_set(
+            SystemSet::new().with_run_criteria(
+                scaled_fixed_timestep
+                    .chain(run_if_playing)
+                    .label(FixedUpdate)
+            )
+        )
+        // Sound effects heard from where they happen, see `sound`
+        .add_audio_source::<PannedSound>()
+        .add_plugin(ShapePlugin)
+        .add_plugin(StatBarsPlugin);
+    add_game_plugins(&mut app, options);
+    app.add_plugin(LoadingPlugin)
+        .add_plugin(DisplayPlugin)
+        .add_plugin(PhotoModePlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(DebugPlugin)
+        .add_system(bevy::window::close_on_esc);
+    app
+}
+
+// The game without a window, rendering or audio, straight into a run. Each `update` is exactly one fixed step,
+// with time advancing by `TIME_STEP`, so tests can step it a known number of times.
+// Images and sounds have no loader here and never load, nothing in the gameplay waits on them.
+// The data files load as usual, the first wave spawns once they have.
+pub fn headless_app(options: Options) -> App
+{
+    let mut app = App::new();
+    app.add_plugins_with(MinimalPlugins, |group| group.disable::<TimePlugin>())
+        .init_resource::<Time>()
+        .add_system_to_stage(CoreStage::First, step_time)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(WindowPlugin {
+            add_primary_window: false,
+            exit_on_all_closed: false,
+            close_when_requested: false,
+        })
+        .add_plugin(AssetPlugin)
+        // What the presentation plugins look up, without the render and audio plugins that would fill them
+        .add_asset::<TextureAtlas>()
+        .add_asset::<AudioSource>()
+        .add_asset::<AudioSink>()
+        .init_resource::<Audio>()
+        .add_asset::<PannedSound>()
+        .init_resource::<Audio<PannedSound>>()
+        .add_state(GameState::Playing)
+        .add_system_set(
+            SystemSet::new().with_run_criteria(
+                (|| ShouldRun::Yes)
+                    .chain(run_if_playing)
+                    .label(FixedUpdate)
+            )
+        );
+    add_game_plugins(&mut app, options);
+    app
+}
+
+// Shared by both apps. The run is never saved from a headless one, and it has no debug overlay to show.
+fn add_game_plugins(app: &mut App, options: Options)
+{
+    // Inserted ahead of the plugins, so their `init_resource` keeps these
+    app.insert_resource(Arena::new(options.arena_size.unwrap_or(DEFAULT_ARENA_SIZE)))
+        .insert_resource(options.mode.unwrap_or_default())
+        .insert_resource(options)
+        .insert_resource(Scoreboard { score: 0 })
+        .insert_resource(ClearColor(BACKGROUND_COLOR))
+        .add_plugin(ActionsPlugin)
+        .add_plugin(TouchPlugin)
+        .add_plugin(CleanupPlugin)
+        .add_plugin(DataPlugin)
+        .add_plugin(RngPlugin)
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(PhysicsPlugin)
+        .add_plugin(SpatialHashPlugin)
+        .add_plugin(ArenaPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(MagnetPlugin)
+        .add_plugin(MagnetRailgunPlugin)
+        .add_plugin(LockOnPlugin)
+        .add_plugin(IndicatorPlugin)
+        .add_plugin(BulletTimePlugin)
+        .add_plugin(DebrisPlugin)
+        .add_plugin(HazardPlugin)
+        .add_plugin(AnomalyPlugin)
+        .add_plugin(TeleporterPlugin)
+        .add_plugin(LinkPlugin)
+        .add_plugin(CombatPlugin)
+        .add_plugin(ShieldPlugin)
+        .add_plugin(WeaponPlugin)
+        .add_plugin(DeployablePlugin)
+        .add_plugin(DronePlugin)
+        .add_plugin(LootPlugin)
+        .add_plugin(RunStatsPlugin)
+        .add_plugin(AchievementPlugin)
+        .add_plugin(ComboPlugin)
+        .add_plugin(StatusPlugin)
+        .add_plugin(EnemyAiPlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(SpawnerPlugin)
+        .add_plugin(PayloadPlugin)
+        .add_plugin(ObjectivePlugin)
+        .add_plugin(SurvivalPlugin)
+        .add_plugin(HordePlugin)
+        .add_plugin(ProjectilePlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(PickupPlugin)
+        .add_plugin(JuicePlugin)
+        .add_plugin(TimeScalePlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(FxPlugin)
+        .add_plugin(DamageNumberPlugin)
+        .add_plugin(FollowCameraPlugin)
+        .add_plugin(CutscenePlugin)
+        .add_plugin(ParticlePlugin)
+        .add_plugin(UiPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(ToastPlugin)
+        .add_plugin(TutorialPlugin)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(LocalePlugin)
+        .add_plugin(SoundPlugin)
+        .add_plugin(LeaderboardPlugin)
+        .add_plugin(LevelPlugin)
+        .add_plugin(UpgradePlugin)
+        .add_startup_system(setup);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    // Only shown once, while every asset is loaded up front
+    Loading,
+    MainMenu,
+    Controls,
+    Playing,
+    // Pushed on top of `Playing`, so the run is kept intact while paused
+    Paused,
+    // Pushed on top of `Playing` between waves
+    Upgrade,
+    // Pushed on top of the main or pause menu
+    Settings,
+    Leaderboard,
+    LevelSelect,
+    // Pushed on top of `Playing` once a level is cleared, before the next one is loaded
+    LevelTransition,
+    // Pushed on top of `Playing`, so the arena stays in view until the run is restarted
+    GameOver,
+    // Pushed on top of `Playing`, the run is frozen while the camera roams
+    PhotoMode,
+}
+
+impl GameState {
+    pub const ALL: [GameState; 12] = [
+        GameState::Loading,
+        GameState::MainMenu,
+        GameState::Controls,
+        GameState::Playing,
+        GameState::Paused,
+        GameState::Upgrade,
+        GameState::Settings,
+        GameState::Leaderboard,
+        GameState::LevelSelect,
+        GameState::LevelTransition,
+        GameState::GameOver,
+        GameState::PhotoMode,
+    ];
+}
+
+// Shared by every system set that should step with the physics
+#[derive(RunCriteriaLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FixedUpdate;
+
+// Everything belonging to the level being played rather than to the run, torn down between levels
+#[derive(Component)]
+pub struct LevelEntity;
+
+// This resource tracks the game's score
+pub struct Scoreboard {
+    pub score: i32,
+}
+
+fn setup(mut commands: Commands)
+{
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(FollowCamera::default());
+}
+
+// Only let the fixed timestep through while a run is actually being played,
+// and not while a hit-stop or a cutscene is freezing it
+fn run_if_playing(
+    In(input): In<ShouldRun>,
+    state: Res<State<GameState>>,
+    hit_stop: Res<HitStop>,
+    director: Res<CameraDirector>,
+) -> ShouldRun
+{
+    if *state.current() == GameState::Playing && !hit_stop.active() && !director.active() {
+        input
+    } else {
+        ShouldRun::No
+    }
+}
+
+// Stands in for the real clock in a headless app
+fn step_time(mut time: ResMut<Time>)
+{
+    let last_update = time.last_update().unwrap_or_else(|| time.startup());
+    time.update_with_instant(last_update + Duration::from_secs_f32(TIME_STEP));
+}
@@ -0,0 +1,181 @@
+//! Magnetized enemies of opposite polarity that bump into each other get chained together:
+//! a tether keeps them moving as a pair, and slamming either into a wall hurts everything nearby.
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, DamageEvent, DamageKind},
+    config::GameConfig,
+    enemy::{check_for_collisions, check_for_enemy_collisions, Enemy, EnemyCollisionEvent, WallSlamEvent},
+    fx::{spawn_sparks, ExplosionToSpawn},
+    juice::{ScreenShake, ENEMY_DEATH_TRAUMA},
+    magnet::{magnet, Magnetic, Magnetized, Polarity},
+    physics::{integrate_forces, Forces, Velocity},
+    FixedUpdate, GameState,
+};
+
+// The tether rests at this multiple of the distance the pair touches at
+const LINK_SLACK: f32 = 1.5;
+// Pull of the tether per unit it is stretched, and damping of the pair moving apart or together
+const LINK_STIFFNESS: f32 = 30.0;
+const LINK_DAMPING: f32 = 4.0;
+// Linked enemies slammed into a wall hurt everything within this radius of the impact
+const LINK_SLAM_RADIUS: f32 = 80.0;
+// Multiplies the damage of the slam itself
+const LINK_SLAM_DAMAGE_SCALE: f32 = 1.0;
+const TETHER_COLOR: Color = Color::rgba(1.0, 0.9, 0.4, 0.8);
+const TETHER_THICKNESS: f32 = 2.0;
+
+pub struct LinkPlugin;
+
+impl Plugin for LinkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(link_enemies.after(check_for_enemy_collisions))
+                .with_system(pull_linked_enemies.after(magnet).before(integrate_forces))
+                .with_system(link_slams.after(check_for_collisions).before(apply_damage))
+                .with_system(break_links),
+        )
+        .add_system(update_tethers);
+    }
+}
+
+// The enemy this one is chained to, set on both of them
+#[derive(Component)]
+pub struct Linked(pub Entity);
+
+// The line drawn between a linked pair
+#[derive(Component)]
+struct Tether(Entity, Entity);
+
+fn link_enemies(
+    mut commands: Commands,
+    mut enemy_collision_events: EventReader<EnemyCollisionEvent>,
+    enemy_query: Query<(&Magnetic, &Transform, Option<&Linked>), (With<Enemy>, With<Magnetized>)>,
+)
+{
+    // Linked this step, the `Linked` components only show up once commands are applied
+    let mut linked = HashSet::default();
+
+    for EnemyCollisionEvent(a, b) in enemy_collision_events.iter() {
+        let ((magnetic_a, transform_a, link_a), (magnetic_b, transform_b, link_b)) =
+            match (enemy_query.get(*a), enemy_query.get(*b)) {
+                (Ok(enemy_a), Ok(enemy_b)) => (enemy_a, enemy_b),
+                _ => continue,
+            };
+
+        let opposite = matches!(
+            (magnetic_a.polarity, magnetic_b.polarity),
+            (Polarity::Positive, Polarity::Negative) | (Polarity::Negative, Polarity::Positive)
+        );
+        if !opposite || link_a.is_some() || link_b.is_some() || linked.contains(a) || linked.contains(b) {
+            continue;
+        }
+
+        linked.insert(*a);
+        linked.insert(*b);
+        commands.entity(*a).insert(Linked(*b));
+        commands.entity(*b).insert(Linked(*a));
+
+        let line = shapes::Line(transform_a.translation.truncate(), transform_b.translation.truncate());
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &line,
+                DrawMode::Stroke(StrokeMode::new(TETHER_COLOR, TETHER_THICKNESS)),
+                Transform::from_xyz(0.0, 0.0, 0.5),
+            ))
+            .insert(Tether(*a, *b))
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+// A damped spring between the pair, so they drag each other around
+fn pull_linked_enemies(
+    link_query: Query<(Entity, &Linked, &Transform, &Velocity)>,
+    partner_query: Query<(&Transform, &Velocity)>,
+    mut forces_query: Query<&mut Forces>,
+)
+{
+    for (entity, linked, transform, velocity) in &link_query {
+        let (partner_transform, partner_velocity) = match partner_query.get(linked.0) {
+            Ok(partner) => partner,
+            Err(_) => continue,
+        };
+
+        let offset = (partner_transform.translation - transform.translation).truncate();
+        let distance = offset.length();
+        if distance <= 0.0 {
+            continue;
+        }
+        let direction = offset / distance;
+
+        let rest_length = (transform.scale.x + partner_transform.scale.x) / 2.0 * LINK_SLACK;
+        let stretch = distance - rest_length;
+        let separating_speed = (partner_velocity.0 - velocity.0).dot(direction);
+        let force = direction * (stretch * LINK_STIFFNESS + separating_speed * LINK_DAMPING);
+
+        if let Ok(mut forces) = forces_query.get_mut(entity) {
+            forces.add_force(force);
+        }
+    }
+}
+
+fn link_slams(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut shake: ResMut<ScreenShake>,
+    mut wall_slam_events: EventReader<WallSlamEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    linked_query: Query<(), With<Linked>>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+)
+{
+    for slam in wall_slam_events.iter() {
+        if linked_query.get(slam.enemy).is_err() {
+            continue;
+        }
+
+        let damage = (slam.damage as f32 * LINK_SLAM_DAMAGE_SCALE).ceil() as i32;
+        for (entity, transform) in &enemy_query {
+            // The slammed enemy has already taken the hit
+            if entity == slam.enemy || transform.translation.truncate().distance(slam.position.truncate()) > LINK_SLAM_RADIUS {
+                continue;
+            }
+            damage_events.send(DamageEvent { target: entity, amount: damage, source: Some(slam.enemy), kind: DamageKind::Impact });
+        }
+
+        commands.spawn().insert(ExplosionToSpawn(slam.position));
+        spawn_sparks(&mut commands, &config, slam.position);
+        shake.add_trauma(ENEMY_DEATH_TRAUMA);
+    }
+}
+
+// Frees the survivor once its partner is gone
+fn break_links(mut commands: Commands, link_query: Query<(Entity, &Linked)>, enemy_query: Query<(), With<Enemy>>)
+{
+    for (entity, linked) in &link_query {
+        if enemy_query.get(linked.0).is_err() {
+            commands.entity(entity).remove::<Linked>();
+        }
+    }
+}
+
+fn update_tethers(
+    mut commands: Commands,
+    mut tether_query: Query<(Entity, &Tether, &mut Path)>,
+    enemy_query: Query<&Transform, With<Linked>>,
+)
+{
+    for (entity, tether, mut path) in &mut tether_query {
+        match (enemy_query.get(tether.0), enemy_query.get(tether.1)) {
+            (Ok(a), Ok(b)) => {
+                *path = ShapePath::build_as(&shapes::Line(a.translation.truncate(), b.translation.truncate()));
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+}
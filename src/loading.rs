@@ -0,0 +1,107 @@
+//! The loading screen the game opens on: every image, sound and font is loaded up front behind a progress bar,
+//! so nothing hitches the first time it shows up in a run. The handles are kept for the rest of the session,
+//! later loads of the same paths get the asset straight away.
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    locale::Locale,
+    ui::{menu_text, spawn_screen, MENU_TEXT_FONT_SIZE},
+    GameState, BACKGROUND_COLOR, SCORE_COLOR, TEXT_COLOR,
+};
+
+// Everything in these asset folders is loaded, whatever loader its extension has
+const PRELOAD_FOLDERS: [&str; 3] = ["images", "sounds", "fonts"];
+const LOADING_BAR_SIZE: Size<Val> = Size { width: Val::Percent(40.0), height: Val::Px(16.0) };
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Loading)
+                .with_system(preload_assets)
+                .with_system(setup_loading_screen),
+        )
+        .add_system_set(SystemSet::on_update(GameState::Loading).with_system(track_loading));
+    }
+}
+
+// Holds on to every preloaded asset, so none is unloaded before it is first used
+pub struct PreloadedAssets(Vec<HandleUntyped>);
+
+#[derive(Component)]
+struct LoadingBarFill;
+
+fn preload_assets(mut commands: Commands, asset_server: Res<AssetServer>)
+{
+    let mut handles = Vec::new();
+    for folder in PRELOAD_FOLDERS {
+        match asset_server.load_folder(folder) {
+            Ok(folder_handles) => handles.extend(folder_handles),
+            Err(error) => warn!("Could not preload {}: {}", folder, error),
+        }
+    }
+    commands.insert_resource(PreloadedAssets(handles));
+}
+
+fn setup_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>)
+{
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::Loading), Color::NONE, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("loading.title"), MENU_TEXT_FONT_SIZE, TEXT_COLOR));
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: LOADING_BAR_SIZE,
+                    margin: UiRect::all(Val::Px(10.0)),
+                    padding: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                color: BACKGROUND_COLOR.into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                            ..default()
+                        },
+                        color: SCORE_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(LoadingBarFill);
+            });
+    });
+}
+
+// Moves on to the main menu once everything is in. Assets that failed to load don't hold it up,
+// Bevy has already logged why
+fn track_loading(
+    asset_server: Res<AssetServer>,
+    preloaded: Option<Res<PreloadedAssets>>,
+    mut fill_query: Query<&mut Style, With<LoadingBarFill>>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    // Inserted once the loads have been started
+    let preloaded = match preloaded {
+        Some(preloaded) => preloaded,
+        None => return,
+    };
+
+    let done = preloaded
+        .0
+        .iter()
+        .filter(|handle| matches!(asset_server.get_load_state(handle.id), LoadState::Loaded | LoadState::Failed))
+        .count();
+    let progress = if preloaded.0.is_empty() { 1.0 } else { done as f32 / preloaded.0.len() as f32 };
+    for mut style in &mut fill_query {
+        style.size.width = Val::Percent(progress * 100.0);
+    }
+
+    if done == preloaded.0.len() {
+        let _ = state.set(GameState::MainMenu);
+    }
+}
@@ -0,0 +1,100 @@
+//! Translated UI text. Every string shown to the player is looked up by key in the string table of the language
+//! picked in the settings, loaded from `assets/locales/`. English is built in, and fills in keys a translation is missing.
+
+use std::{collections::HashMap, fs};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+
+use crate::settings::Settings;
+
+const LOCALES_DIR: &str = "assets/locales";
+pub const DEFAULT_LANGUAGE: &str = "en";
+// Every language a string table ships for, by code and by its own name for itself
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("de", "Deutsch")];
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        // Loaded before the first menu is built, and again whenever the language setting changes
+        app.init_resource::<Locale>()
+            .add_startup_system(sync_language)
+            .add_system(sync_language);
+    }
+}
+
+pub struct Locale {
+    language: String,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            strings: HashMap::new(),
+            fallback: parse_table(DEFAULT_LANGUAGE, include_str!("../assets/locales/en.ron")),
+        }
+    }
+}
+
+impl Locale {
+    fn load(language: &str) -> Locale {
+        let mut locale = Locale {
+            language: language.to_string(),
+            ..default()
+        };
+        if language == DEFAULT_LANGUAGE {
+            return locale;
+        }
+
+        #[cfg(feature = "embedded_assets")]
+        if let Some(contents) = crate::embedded::file(format!("locales/{}.ron", language)) {
+            locale.strings = parse_table(language, &String::from_utf8_lossy(contents));
+            return locale;
+        }
+
+        let path = FileAssetIo::get_base_path().join(LOCALES_DIR).join(format!("{}.ron", language));
+        match fs::read_to_string(&path) {
+            Ok(contents) => locale.strings = parse_table(language, &contents),
+            Err(error) => warn!("Missing string table {}: {}", path.display(), error),
+        }
+        locale
+    }
+
+    // The text for `key`, in English if the language has none, or the key itself if nothing has
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, |value| value.as_str())
+    }
+
+    // The text for `key` with each `{name}` in it replaced by its value
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+fn parse_table(language: &str, contents: &str) -> HashMap<String, String>
+{
+    match ron::from_str(contents) {
+        Ok(strings) => strings,
+        Err(error) => {
+            warn!("Ignoring unreadable string table for {}: {}", language, error);
+            HashMap::new()
+        }
+    }
+}
+
+fn sync_language(settings: Res<Settings>, mut locale: ResMut<Locale>)
+{
+    if settings.language != locale.language {
+        *locale = Locale::load(&settings.language);
+    }
+}
@@ -0,0 +1,158 @@
+//! Locking onto a single enemy: the first player cycles through the enemies in sight, nearest first,
+//! and a reticle marks whichever is locked. Directional abilities turn toward it while it is within their reach:
+//! the magnet's cone, the railgun and the magnet railgun.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    enemy::Enemy,
+    physics::apply_velocity,
+    player::Player,
+    FixedUpdate, GameState,
+};
+
+// Only enemies this close to the first player can be locked onto
+const LOCK_ON_RANGE: f32 = 700.0;
+const RETICLE_RADIUS: f32 = 28.0;
+const RETICLE_THICKNESS: f32 = 2.0;
+const RETICLE_COLOR: Color = Color::rgba(1.0, 0.9, 0.3, 0.9);
+// Left open between the four arcs of the ring, in radians
+const RETICLE_GAP: f32 = 0.6;
+// Radians per second
+const RETICLE_SPIN: f32 = 2.0;
+
+pub struct LockOnPlugin;
+
+impl Plugin for LockOnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LockedTarget>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_reticle))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(track_locked_target.after(apply_velocity)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(cycle_target)
+                    .with_system(update_reticle.after(cycle_target)),
+            );
+    }
+}
+
+// The enemy currently locked onto, if any, and where it was as of the last step
+#[derive(Default)]
+pub struct LockedTarget {
+    pub entity: Option<Entity>,
+    position: Vec2,
+}
+
+impl LockedTarget {
+    pub fn position(&self) -> Option<Vec2> {
+        self.entity.map(|_| self.position)
+    }
+
+    // Which way to face from `from` to the target, if it is locked and no further than `range`
+    pub fn direction_from(&self, from: Vec2, range: f32) -> Option<Vec2> {
+        self.position()
+            .filter(|position| position.distance(from) <= range)
+            .map(|position| (position - from).normalize_or_zero())
+    }
+}
+
+#[derive(Component)]
+struct Reticle;
+
+fn spawn_reticle(mut commands: Commands, mut locked: ResMut<LockedTarget>)
+{
+    *locked = LockedTarget::default();
+
+    // A broken ring, so its spin can be seen
+    let mut path = PathBuilder::new();
+    for quarter in 0..4 {
+        let start = quarter as f32 * FRAC_PI_2 + RETICLE_GAP / 2.0;
+        path.move_to(Vec2::new(start.cos(), start.sin()) * RETICLE_RADIUS);
+        path.arc(Vec2::ZERO, Vec2::splat(RETICLE_RADIUS), FRAC_PI_2 - RETICLE_GAP, 0.0);
+    }
+
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &path.build(),
+            DrawMode::Stroke(StrokeMode::new(RETICLE_COLOR, RETICLE_THICKNESS)),
+            Transform::default(),
+        ))
+        .insert(Reticle)
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+// Moves the lock to the next enemy in sight, ordered by distance from the first player
+fn cycle_target(
+    actions: Res<ActionState>,
+    arena: Res<Arena>,
+    mut locked: ResMut<LockedTarget>,
+    player_query: Query<(&Player, &Transform)>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+)
+{
+    if !actions.just_pressed(Action::CycleTarget) {
+        return;
+    }
+    let origin = match player_query.iter().min_by_key(|(player, _)| player.0) {
+        Some((_, transform)) => transform.translation.truncate(),
+        None => return,
+    };
+
+    let mut candidates: Vec<(Entity, Vec2)> = enemy_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .filter(|(_, position)| position.distance(origin) < LOCK_ON_RANGE && arena.line_of_sight(origin, *position))
+        .collect();
+    candidates.sort_by(|(_, a), (_, b)| a.distance(origin).total_cmp(&b.distance(origin)));
+
+    // Past the last one it wraps around to the nearest, with nobody in sight it lets go
+    let next = match locked.entity.and_then(|current| candidates.iter().position(|(entity, _)| *entity == current)) {
+        Some(index) => candidates.get((index + 1) % candidates.len()),
+        None => candidates.first(),
+    };
+    *locked = match next {
+        Some((entity, position)) => LockedTarget { entity: Some(*entity), position: *position },
+        None => LockedTarget::default(),
+    };
+}
+
+// Follows the target around, and lets go of it once it is gone
+fn track_locked_target(mut locked: ResMut<LockedTarget>, enemy_query: Query<&Transform, With<Enemy>>)
+{
+    let entity = match locked.entity {
+        Some(entity) => entity,
+        None => return,
+    };
+    match enemy_query.get(entity) {
+        Ok(transform) => locked.position = transform.translation.truncate(),
+        Err(_) => *locked = LockedTarget::default(),
+    }
+}
+
+fn update_reticle(
+    locked: Res<LockedTarget>,
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut Visibility), With<Reticle>>,
+)
+{
+    for (mut transform, mut visibility) in &mut query {
+        match locked.position() {
+            Some(position) => {
+                visibility.is_visible = true;
+                transform.translation = position.extend(2.0);
+                transform.rotate_z(RETICLE_SPIN * time.delta_seconds());
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! What a kill is worth: every `DeathEvent` awards its score through the combo and any active multiplier,
+//! rolls for a drop in `assets/data/loot.ron`, and counts towards the kills of its kind for the run.
+//! Its sound rings out a little higher with every step of the combo.
+
+use bevy::prelude::*;
+
+use crate::{
+    combat::{enemy_death, DeathEvent},
+    combo::Combo,
+    data::LootTable,
+    difficulty::Difficulty,
+    pickups::{spawn_pickup, PowerUps},
+    rng::GameRng,
+    sound::{PlaySfx, Sfx},
+    stats::RunStats,
+    FixedUpdate, Scoreboard,
+};
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(reward_kills.after(enemy_death)),
+        );
+    }
+}
+
+fn reward_kills(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    loot: Option<Res<LootTable>>,
+    difficulty: Res<Difficulty>,
+    power_ups: Res<PowerUps>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut combo: ResMut<Combo>,
+    mut run_stats: ResMut<RunStats>,
+    mut rng: ResMut<GameRng>,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    for death in death_events.iter() {
+        scoreboard.score += death.score * power_ups.score_multiplier() * combo.register_kill();
+        sfx_events.send(PlaySfx::new(Sfx::Kill).with_pitch(combo.kill_pitch()).at(death.position.truncate()));
+        *run_stats.kills.entry(death.kind).or_default() += 1;
+
+        // Nothing drops until the loot table has loaded
+        if let Some(pickup) = loot.as_ref().and_then(|loot| loot.roll(&mut rng.0, death.kind, difficulty.drop_chance())) {
+            spawn_pickup(&mut commands, pickup, death.position);
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! The player's magnet: pulling or pushing anything `Magnetic` within its radius,
+//! draining energy while held and regenerating it otherwise. It either reaches all around
+//! the player or, toggled with M, only a narrower cone towards where the player aims, or at the locked target.
+//! Enemies carry a polarity deciding whether pulling attracts or repels them.
+
+use std::{f32::consts::FRAC_PI_6, time::Duration};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_simple_stat_bars::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    config::GameConfig,
+    enemy::Enemy,
+    lock_on::LockedTarget,
+    particles::ParticleEmitter,
+    physics::Forces,
+    pickups::PowerUps,
+    player::{aim_with_cursor, aim_with_movement, Aim, Player, PLAYER_SIZE},
+    projectiles::Projectile,
+    spatial::SpatialHash,
+    sound::MagnetHold,
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, BACKGROUND_COLOR, TIME_STEP, WALL_COLOR,
+};
+
+// Starting values, upgraded over the course of a run through `PlayerStats`
+pub const MAGNET_RADIUS: f32 = 400.0;
+pub const MAGNET_FORCE: f32 = 100.0;
+
+// Half the opening of the cone, either side of the aim
+const MAGNET_CONE_HALF_ANGLE: f32 = FRAC_PI_6;
+// Focusing the field into a cone makes it that much stronger
+const MAGNET_CONE_FORCE_SCALE: f32 = 1.5;
+const MAGNET_CONE_SEGMENTS: usize = 16;
+const MAGNET_CONE_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.08);
+// Alpha of the cone while the magnet is held, tinted with the pull or push color
+const MAGNET_CONE_ACTIVE_ALPHA: f32 = 0.2;
+
+const POSITIVE_COLOR: Color = Color::rgb(1.0, 0.55, 0.55);
+const NEGATIVE_COLOR: Color = Color::rgb(0.55, 0.65, 1.0);
+const NEUTRAL_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+
+// Seconds an enemy stays magnetized after the magnet lets go of it
+const MAGNETIZED_DURATION: f32 = 0.3;
+
+pub struct MagnetPlugin;
+
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MagnetMode>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(magnet)
+                    .with_system(demagnetize.before(magnet)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(spawn_magnet_energy_bar)
+                    .with_system(update_magnet_energy_bar)
+                    .with_system(spawn_magnet_cones)
+                    .with_system(toggle_magnet_mode)
+                    .with_system(
+                        update_magnet_cones
+                            .after(toggle_magnet_mode)
+                            .after(aim_with_cursor)
+                            .after(aim_with_movement),
+                    ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct MagnetEnergy {
+    pub current: f32,
+    pub max: f32,
+    // Set when the energy runs out, cleared once enough has regenerated
+    pub depleted: bool,
+}
+
+impl MagnetEnergy {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max, depleted: false }
+    }
+}
+
+#[derive(Component)]
+struct MagnetEnergyBar;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum MagnetMode {
+    // Reaches everything within the magnet radius
+    #[default]
+    Radial,
+    // Only reaches what is in front of the player, towards where they aim
+    Cone,
+}
+
+// Shows where a player's magnet reaches while in cone mode
+#[derive(Component)]
+struct MagnetCone(Entity);
+
+// Opts an entity into magnet physics: enemies, projectiles, pickups and debris alike.
+// The magnet's force on it is multiplied by `strength`, enemies grow more stubborn as the run goes on.
+#[derive(Component, Clone, Copy)]
+pub struct Magnetic {
+    pub strength: f32,
+    pub polarity: Polarity,
+}
+
+impl Magnetic {
+    pub fn new(strength: f32, polarity: Polarity) -> Self {
+        Self { strength, polarity }
+    }
+}
+
+// Magnetic entities with this are left alone by the magnet for the time being
+#[derive(Component)]
+pub struct MagnetImmune;
+
+// Set on whatever the magnet is acting on, lingering for a moment after it lets go
+#[derive(Component)]
+pub struct Magnetized(Timer);
+
+impl Default for Magnetized {
+    fn default() -> Self {
+        Self(Timer::from_seconds(MAGNETIZED_DURATION, false))
+    }
+}
+
+// Pulling attracts positive things and repels negative ones, pushing does the opposite.
+// Neutral ones don't react to the magnet at all.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum Polarity {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+impl Polarity {
+    // Whether the magnet pushes this away, or `None` if it isn't affected
+    fn pushed(self, pushing: bool) -> Option<bool> {
+        match self {
+            Polarity::Positive => Some(pushing),
+            Polarity::Negative => Some(!pushing),
+            Polarity::Neutral => None,
+        }
+    }
+
+    // Enemies are tinted with this so their polarity can be told apart at a glance
+    pub fn color(self) -> Color {
+        match self {
+            Polarity::Positive => POSITIVE_COLOR,
+            Polarity::Negative => NEGATIVE_COLOR,
+            Polarity::Neutral => NEUTRAL_COLOR,
+        }
+    }
+}
+
+// Magnet energy bar, floating below whoever carries the magnet
+fn spawn_magnet_energy_bar(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    query: Query<Entity, Added<MagnetEnergy>>,
+)
+{
+    for entity in &query {
+        commands
+            .spawn_bundle((
+                StatBarColor(config.magnet_energy_color),
+                StatBarEmptyColor(BACKGROUND_COLOR),
+                StatBarBorder { color: WALL_COLOR, thickness: 1.0 },
+                StatBarValue(1.0),
+                StatBarSize { full_length: PLAYER_SIZE.x, thickness: 4.0 },
+                StatBarSubject(entity),
+                StatBarPosition(-(PLAYER_SIZE.y / 2.0 + 8.0) * Vec2::Y),
+                component_observer(|energy: &MagnetEnergy| energy.current / energy.max),
+            ))
+            .insert(MagnetEnergyBar)
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+fn toggle_magnet_mode(actions: Res<ActionState>, mut mode: ResMut<MagnetMode>)
+{
+    if actions.just_pressed(Action::ToggleMagnetMode) {
+        *mode = match *mode {
+            MagnetMode::Radial => MagnetMode::Cone,
+            MagnetMode::Cone => MagnetMode::Radial,
+        };
+    }
+}
+
+// One cone for whoever carries a magnet
+fn spawn_magnet_cones(mut commands: Commands, query: Query<Entity, Added<MagnetEnergy>>)
+{
+    for entity in &query {
+        // A fan of unit radius pointing along x, rotated towards the aim and scaled to the magnet radius
+        let mut points = vec![Vec2::ZERO];
+        for segment in 0..=MAGNET_CONE_SEGMENTS {
+            let angle = -MAGNET_CONE_HALF_ANGLE + 2.0 * MAGNET_CONE_HALF_ANGLE * segment as f32 / MAGNET_CONE_SEGMENTS as f32;
+            points.push(Vec2::new(angle.cos(), angle.sin()));
+        }
+
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Polygon { points, closed: true },
+                DrawMode::Fill(FillMode::color(MAGNET_CONE_COLOR)),
+                Transform::default(),
+            ))
+            .insert(MagnetCone(entity))
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+fn update_magnet_cones(
+    mut commands: Commands,
+    mode: Res<MagnetMode>,
+    config: Res<GameConfig>,
+    stats: Res<PlayerStats>,
+    power_ups: Res<PowerUps>,
+    locked: Res<LockedTarget>,
+    player_query: Query<(&Transform, &Aim, &ActionState, &MagnetEnergy), Without<MagnetCone>>,
+    mut cone_query: Query<(Entity, &MagnetCone, &mut Transform, &mut DrawMode, &mut Visibility)>,
+)
+{
+    let stats = power_ups.boosted(&stats);
+
+    for (entity, cone, mut transform, mut draw_mode, mut visibility) in &mut cone_query {
+        let (player_transform, aim, actions, energy) = match player_query.get(cone.0) {
+            Ok(player) => player,
+            // Its player is gone
+            Err(_) => {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        };
+
+        visibility.is_visible = *mode == MagnetMode::Cone;
+        let player_position = player_transform.translation.truncate();
+        let aim = locked.direction_from(player_position, stats.magnet_radius).unwrap_or(aim.0);
+        transform.translation = player_position.extend(-0.5);
+        transform.rotation = Quat::from_rotation_z(aim.y.atan2(aim.x));
+        transform.scale = Vec3::new(stats.magnet_radius, stats.magnet_radius, 1.0);
+
+        let pulling = actions.pressed(Action::Pull) && !energy.depleted;
+        let pushing = actions.pressed(Action::Push) && !energy.depleted;
+        let mut color = if pulling {
+            config.pull_color
+        } else if pushing {
+            config.push_color
+        } else {
+            MAGNET_CONE_COLOR
+        };
+        if pulling || pushing {
+            color.set_a(MAGNET_CONE_ACTIVE_ALPHA);
+        }
+        *draw_mode = DrawMode::Fill(FillMode::color(color));
+    }
+}
+
+pub fn magnet(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    stats: Res<PlayerStats>,
+    power_ups: Res<PowerUps>,
+    mode: Res<MagnetMode>,
+    locked: Res<LockedTarget>,
+    arena: Res<Arena>,
+    spatial_hash: Res<SpatialHash>,
+    mut query: Query<
+        (&ActionState, &Aim, &mut TextureAtlasSprite, &Transform, &mut MagnetEnergy, &mut ParticleEmitter),
+        With<Player>,
+    >,
+    mut magnetic_query: Query<
+        (&Transform, &Magnetic, &mut Forces, Option<&mut Projectile>),
+        (Without<Player>, Without<MagnetImmune>),
+    >,
+    // Enemies are looked up through the spatial hash, everything else magnetic is few enough to go through one by one
+    loose_query: Query<Entity, (With<Magnetic>, Without<Enemy>)>,
+    mut hold: ResMut<MagnetHold>,
+)
+{
+    let stats = power_ups.boosted(&stats);
+    // The hum plays while anyone holds their magnet
+    hold.pulling = false;
+    hold.pushing = false;
+
+    for (actions, aim, mut player_sprite, player_transform, mut energy, mut emitter) in &mut query {
+        // The cone turns toward the locked target while it is within reach
+        let player_position = player_transform.translation.truncate();
+        let cone = (*mode == MagnetMode::Cone)
+            .then(|| locked.direction_from(player_position, stats.magnet_radius).unwrap_or(aim.0));
+        let pulling = actions.pressed(Action::Pull) && !energy.depleted;
+        let pushing = actions.pressed(Action::Push) && !energy.depleted;
+
+        if pulling || pushing {
+            energy.current = (energy.current - config.magnet_energy_drain * TIME_STEP).max(0.0);
+            if energy.current <= 0.0 {
+                energy.depleted = true;
+            }
+        } else {
+            energy.current = (energy.current + config.magnet_energy_regen * TIME_STEP).min(energy.max);
+            if energy.depleted && energy.current >= energy.max * config.magnet_reenable_fraction {
+                energy.depleted = false;
+            }
+        }
+
+        hold.pulling |= pulling;
+        hold.pushing |= pushing;
+
+        player_sprite.flip_y = pulling;
+
+        // Particles stream in from the edge of the field while pulling, and out from the player while pushing
+        // In cone mode the cone itself lights up instead
+        emitter.active = (pulling || pushing) && cone.is_none();
+        emitter.rate = config.magnet_particle_rate;
+        emitter.size = config.magnet_particle_size;
+        if pulling {
+            emitter.spawn_radius = stats.magnet_radius;
+            emitter.radial_speed = -config.magnet_particle_speed;
+            emitter.color = config.pull_color;
+        } else if pushing {
+            emitter.spawn_radius = PLAYER_SIZE.x / 2.0;
+            emitter.radial_speed = config.magnet_particle_speed;
+            emitter.color = config.push_color;
+        }
+        emitter.lifetime = (stats.magnet_radius - PLAYER_SIZE.x / 2.0) / config.magnet_particle_speed;
+        if !(pulling || pushing) {
+            continue;
+        }
+
+        // The field doesn't reach through obstacles
+        let nearby = spatial_hash.query_radius(player_position, stats.magnet_radius);
+        for entity in nearby.into_iter().chain(loose_query.iter()) {
+            let (transform, magnetic, mut forces, projectile) = match magnetic_query.get_mut(entity) {
+                Ok(magnetic) => magnetic,
+                Err(_) => continue,
+            };
+            if !arena.line_of_sight(player_position, transform.translation.truncate()) {
+                continue;
+            }
+            if !apply_magnet_force(&config, &stats, player_transform, transform, magnetic, &mut forces, cone, pushing) {
+                continue;
+            }
+            commands.entity(entity).insert(Magnetized::default());
+
+            // Pushing a projectile away turns it against the enemies
+            if let Some(mut projectile) = projectile {
+                if pushing {
+                    projectile.deflect();
+                }
+            }
+        }
+    }
+}
+
+fn update_magnet_energy_bar(
+    config: Res<GameConfig>,
+    energy_query: Query<&MagnetEnergy>,
+    mut bar_query: Query<(&StatBarSubject, &mut StatBarColor), With<MagnetEnergyBar>>,
+)
+{
+    for (subject, mut color) in &mut bar_query {
+        if let Ok(energy) = energy_query.get(subject.0) {
+            color.0 = if energy.depleted { config.magnet_depleted_color } else { config.magnet_energy_color };
+        }
+    }
+}
+
+fn point_in_radius(point: Vec2, center: Vec2, radius: f32) -> bool
+{
+    let distance = point.distance(center);
+    distance < radius
+}
+
+// Force the magnet exerts on something at `target`, if it is within reach.
+// With a `cone` aim, only targets within the cone around it are reached.
+fn magnet_force(
+    config: &GameConfig,
+    stats: &PlayerStats,
+    player: Vec3,
+    target: Vec3,
+    cone: Option<Vec2>,
+    is_push: bool,
+) -> Option<Vec2>
+{
+    if let Some(aim) = cone {
+        if aim.angle_between((target - player).truncate()).abs() > MAGNET_CONE_HALF_ANGLE {
+            return None;
+        }
+    }
+    let focus = if cone.is_some() { MAGNET_CONE_FORCE_SCALE } else { 1.0 };
+
+    field_force(config, stats.magnet_radius, stats.magnet_force * focus, player, target, is_push)
+}
+
+// Force a magnetic field of the given radius and strength centered on `source` exerts on something at `target`,
+// if it is within reach. Shared by the player's magnet and anything else acting like one.
+pub fn field_force(config: &GameConfig, radius: f32, strength: f32, source: Vec3, target: Vec3, is_push: bool) -> Option<Vec2>
+{
+    if !point_in_radius(target.truncate(), source.truncate(), radius) {
+        return None;
+    }
+
+    let direction;
+    if is_push {
+        direction = (target - source).truncate();
+    } else {
+        direction = (source - target).truncate();
+    }
+    let distance = direction.length();
+    let normalized_direction = direction.normalize_or_zero();
+
+    // The closer the target, the stronger the pull or push
+    let falloff = (radius / distance).min(config.magnet_max_falloff);
+    Some(normalized_direction * strength * config.magnet_force_scale * falloff)
+}
+
+// Pushes or pulls something magnetic by its polarity and strength, it keeps its own tint rather than the magnet's.
+// Returns whether the magnet reached it.
+fn apply_magnet_force(
+    config: &GameConfig,
+    stats: &PlayerStats,
+    player_transform: &Transform,
+    target_transform: &Transform,
+    magnetic: &Magnetic,
+    forces: &mut Forces,
+    cone: Option<Vec2>,
+    pushing: bool,
+) -> bool
+{
+    let is_push = match magnetic.polarity.pushed(pushing) {
+        Some(is_push) => is_push,
+        None => return false,
+    };
+    match magnet_force(config, stats, player_transform.translation, target_transform.translation, cone, is_push) {
+        Some(force) => {
+            forces.add_force(force * magnetic.strength);
+            true
+        }
+        None => false,
+    }
+}
+
+fn demagnetize(mut commands: Commands, mut query: Query<(Entity, &mut Magnetized)>)
+{
+    for (entity, mut magnetized) in &mut query {
+        if magnetized.0.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            commands.entity(entity).remove::<Magnetized>();
+        }
+    }
+}
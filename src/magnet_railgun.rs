@@ -0,0 +1,316 @@
+//! The magnet railgun: pulling on a single enemy for long enough loads it, held in front of the player,
+//! and pushing then fires it along the aim. The shot hurts every enemy it passes through and whatever it
+//! finally slams into, obstacles included. The locked target, when within reach, is always the one charged.
+
+use std::{f32::consts::FRAC_PI_8, time::Duration};
+
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, DamageEvent, DamageKind},
+    config::GameConfig,
+    enemy::{check_for_collisions, Enemy},
+    lock_on::LockedTarget,
+    magnet::{magnet, MagnetEnergy, MagnetImmune},
+    physics::{apply_velocity, integrate_forces, Collider, Velocity},
+    pickups::PowerUps,
+    player::{Aim, Player, PLAYER_SIZE},
+    sound::{PlaySfx, Sfx},
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+// Seconds an enemy has to be pulled on before it is loaded
+const CHARGE_TIME: f32 = 1.5;
+// Half the opening, either side of the aim, within which an enemy can be locked onto
+const LOCK_ON_HALF_ANGLE: f32 = FRAC_PI_8;
+// Space kept between the player and the enemy they hold
+const HOLD_GAP: f32 = 6.0;
+const SHOT_SPEED: f32 = 900.0;
+// Seconds a shot stays dangerous if it doesn't hit a wall first
+const SHOT_LIFETIME: f32 = 1.0;
+// Multiplies the weapon damage for everything the shot hits
+const SHOT_DAMAGE_SCALE: f32 = 4.0;
+// The disc under a charging enemy grows up to this many times its size, turning solid once loaded
+const INDICATOR_SCALE: f32 = 1.5;
+const CHARGING_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.3);
+const LOADED_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.7);
+
+pub struct MagnetRailgunPlugin;
+
+impl Plugin for MagnetRailgunPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(charge_magnet_railguns.after(magnet).before(integrate_forces))
+                .with_system(hold_loaded_enemies.after(apply_velocity).before(check_for_collisions))
+                .with_system(
+                    magnet_shot_hits
+                        .after(apply_velocity)
+                        .before(check_for_collisions)
+                        .before(apply_damage),
+                ),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(spawn_railgun_indicators)
+                .with_system(update_railgun_indicators),
+        );
+    }
+}
+
+// What a player's magnet railgun is up to, each player has their own
+#[derive(Component, Default)]
+pub enum MagnetRailgun {
+    #[default]
+    Idle,
+    // Pulling on an enemy, loaded once the timer runs out
+    Charging { target: Entity, timer: Timer },
+    // Holding an enemy in front of the player, ready to fire
+    Loaded(Entity),
+}
+
+// An enemy fired by the magnet railgun, hurting what it hits while it lasts
+#[derive(Component)]
+struct MagnetShot {
+    shooter: Entity,
+    lifetime: Timer,
+    // Each enemy is only hit once on the way through
+    hit: Vec<Entity>,
+}
+
+// Shows which enemy a player is charging, and that it is loaded
+#[derive(Component)]
+struct RailgunIndicator(Entity);
+
+// The locked target if it is within reach of the magnet, otherwise the enemy within reach closest to the aim,
+// if any is close enough to it
+fn lock_on(
+    position: Vec2,
+    aim: Vec2,
+    radius: f32,
+    arena: &Arena,
+    locked: &LockedTarget,
+    enemy_query: &Query<(Entity, &Transform, &mut Velocity), (With<Enemy>, Without<Player>)>,
+) -> Option<Entity>
+{
+    if let (Some(target), Some(target_position)) = (locked.entity, locked.position()) {
+        if target_position.distance(position) < radius && arena.line_of_sight(position, target_position) {
+            return Some(target);
+        }
+    }
+
+    let mut best = None;
+    let mut best_angle = LOCK_ON_HALF_ANGLE;
+    for (entity, transform, _) in enemy_query.iter() {
+        let target = transform.translation.truncate();
+        if target.distance(position) > radius || !arena.line_of_sight(position, target) {
+            continue;
+        }
+        let angle = aim.angle_between(target - position).abs();
+        if angle < best_angle {
+            best = Some(entity);
+            best_angle = angle;
+        }
+    }
+    best
+}
+
+fn charge_magnet_railguns(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    power_ups: Res<PowerUps>,
+    arena: Res<Arena>,
+    locked: Res<LockedTarget>,
+    mut player_query: Query<(Entity, &ActionState, &Aim, &Transform, &MagnetEnergy, &mut MagnetRailgun), With<Player>>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Velocity), (With<Enemy>, Without<Player>)>,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    let stats = power_ups.boosted(&stats);
+
+    for (player, actions, aim, transform, energy, mut railgun) in &mut player_query {
+        let position = transform.translation.truncate();
+        let pulling = actions.pressed(Action::Pull) && !energy.depleted;
+
+        let next = match &mut *railgun {
+            MagnetRailgun::Idle => {
+                if !pulling {
+                    continue;
+                }
+                match lock_on(position, aim.0, stats.magnet_radius, &arena, &locked, &enemy_query) {
+                    Some(target) => MagnetRailgun::Charging { target, timer: Timer::from_seconds(CHARGE_TIME, false) },
+                    None => continue,
+                }
+            }
+            MagnetRailgun::Charging { target, timer } => {
+                // Letting go, or the enemy getting out of reach, starts over
+                let in_reach = enemy_query.get(*target).map_or(false, |(_, enemy_transform, _)| {
+                    let enemy_position = enemy_transform.translation.truncate();
+                    enemy_position.distance(position) < stats.magnet_radius && arena.line_of_sight(position, enemy_position)
+                });
+                if !pulling || !in_reach {
+                    MagnetRailgun::Idle
+                } else if timer.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+                    // The rest of the field leaves it alone while it is held
+                    commands.entity(*target).insert(MagnetImmune);
+                    MagnetRailgun::Loaded(*target)
+                } else {
+                    continue;
+                }
+            }
+            MagnetRailgun::Loaded(target) => match enemy_query.get_mut(*target) {
+                // Died while held
+                Err(_) => MagnetRailgun::Idle,
+                Ok(_) if !actions.just_pressed(Action::Push) => continue,
+                Ok((_, target_transform, mut velocity)) => {
+                    // Fired at the locked target, unless that is the very enemy being fired
+                    let origin = target_transform.translation.truncate();
+                    let aim = match locked.position().filter(|position| *position != origin) {
+                        Some(position) => (position - origin).normalize_or_zero(),
+                        None => aim.0,
+                    };
+                    velocity.0 = aim * SHOT_SPEED;
+                    commands.entity(*target).remove::<MagnetImmune>().insert(MagnetShot {
+                        shooter: player,
+                        lifetime: Timer::from_seconds(SHOT_LIFETIME, false),
+                        hit: Vec::new(),
+                    });
+                    sfx_events.send(PlaySfx::new(Sfx::MagnetPush));
+                    MagnetRailgun::Idle
+                }
+            },
+        };
+        *railgun = next;
+    }
+}
+
+// Keeps loaded enemies right in front of whoever holds them, whatever else pushes them around
+fn hold_loaded_enemies(
+    player_query: Query<(&Aim, &Transform, &MagnetRailgun), With<Player>>,
+    mut enemy_query: Query<(&mut Transform, &mut Velocity), (With<Enemy>, Without<Player>)>,
+)
+{
+    for (aim, player_transform, railgun) in &player_query {
+        let target = match railgun {
+            MagnetRailgun::Loaded(target) => *target,
+            _ => continue,
+        };
+        let (mut transform, mut velocity) = match enemy_query.get_mut(target) {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+
+        let distance = PLAYER_SIZE.x / 2.0 + transform.scale.x / 2.0 + HOLD_GAP;
+        let position = player_transform.translation.truncate() + aim.0 * distance;
+        transform.translation = position.extend(transform.translation.z);
+        velocity.0 = Vec2::ZERO;
+    }
+}
+
+// Fired enemies hurt every enemy they pass through, and stop being dangerous once they slam into something solid.
+// The slam itself, and the bounce off it, are left to `check_for_collisions`.
+fn magnet_shot_hits(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    mut shot_query: Query<(Entity, &mut MagnetShot, &Transform)>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    collider_query: Query<(Entity, &Transform), (With<Collider>, Without<Enemy>, Without<Player>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+)
+{
+    let damage = (stats.weapon_damage * SHOT_DAMAGE_SCALE) as i32;
+
+    for (entity, mut shot, transform) in &mut shot_query {
+        if shot.lifetime.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            commands.entity(entity).remove::<MagnetShot>();
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        for (enemy, enemy_transform) in &enemy_query {
+            let reach = (transform.scale.x + enemy_transform.scale.x) / 2.0;
+            if enemy == entity || shot.hit.contains(&enemy) || position.distance(enemy_transform.translation.truncate()) > reach {
+                continue;
+            }
+            shot.hit.push(enemy);
+            damage_events.send(DamageEvent { target: enemy, amount: damage, source: Some(shot.shooter), kind: DamageKind::Impact });
+        }
+
+        // Walls, obstacles and bosses. Only the ones with health take the damage.
+        let solid = collider_query.iter().find(|(_, collider_transform)| {
+            collide(
+                transform.translation,
+                transform.scale.truncate(),
+                collider_transform.translation,
+                collider_transform.scale.truncate(),
+            )
+            .is_some()
+        });
+        if let Some((collider, _)) = solid {
+            damage_events.send(DamageEvent { target: collider, amount: damage, source: Some(shot.shooter), kind: DamageKind::Impact });
+            commands.entity(entity).remove::<MagnetShot>();
+        }
+    }
+}
+
+// One indicator for whoever carries a magnet railgun
+fn spawn_railgun_indicators(mut commands: Commands, query: Query<Entity, Added<MagnetRailgun>>)
+{
+    for entity in &query {
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Circle { radius: 0.5, center: Vec2::ZERO },
+                DrawMode::Fill(FillMode::color(CHARGING_COLOR)),
+                Transform::default(),
+            ))
+            .insert(RailgunIndicator(entity))
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+fn update_railgun_indicators(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    player_query: Query<&MagnetRailgun>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<RailgunIndicator>)>,
+    mut indicator_query: Query<(Entity, &RailgunIndicator, &mut Transform, &mut DrawMode, &mut Visibility)>,
+)
+{
+    for (entity, indicator, mut transform, mut draw_mode, mut visibility) in &mut indicator_query {
+        let railgun = match player_query.get(indicator.0) {
+            Ok(railgun) => railgun,
+            // Its player is gone
+            Err(_) => {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        };
+
+        let (target, progress, color) = match railgun {
+            MagnetRailgun::Idle => (None, 0.0, CHARGING_COLOR),
+            MagnetRailgun::Charging { target, timer } => (Some(*target), timer.percent(), config.pull_color),
+            MagnetRailgun::Loaded(target) => (Some(*target), 1.0, LOADED_COLOR),
+        };
+        let target_transform = match target.and_then(|target| enemy_query.get(target).ok()) {
+            Some(target_transform) => target_transform,
+            None => {
+                visibility.is_visible = false;
+                continue;
+            }
+        };
+
+        visibility.is_visible = true;
+        let mut color = color;
+        color.set_a(CHARGING_COLOR.a() + (LOADED_COLOR.a() - CHARGING_COLOR.a()) * progress);
+        *draw_mode = DrawMode::Fill(FillMode::color(color));
+        let size = target_transform.scale.x * (1.0 + (INDICATOR_SCALE - 1.0) * progress);
+        transform.translation = target_transform.translation.truncate().extend(-0.3);
+        transform.scale = Vec3::new(size, size, 1.0);
+    }
+}
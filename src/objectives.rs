@@ -0,0 +1,146 @@
+//! What a wave asks of the players before it counts as won: clearing out every enemy, destroying the spawners,
+//! holding out until a timer runs down, or escorting the payload across the arena. The current objective is
+//! evaluated every step and shown in the HUD; once it is met, whatever enemies are left crumble and the wave ends.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    combat::{enemy_death, Hp},
+    enemy::{Enemy, SpawnTelegraph},
+    fx::ExplosionToSpawn,
+    payload::{Payload, PayloadTrack},
+    spawners::{destroy_spawners, Spawner},
+    toast::Toast,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const COMPLETE_COLOR: Color = Color::rgb(0.5, 1.0, 0.5);
+
+pub struct ObjectivePlugin;
+
+impl Plugin for ObjectivePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentObjective>()
+            .add_event::<ObjectiveCompleteEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_objective))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(evaluate_objective.after(enemy_death).after(destroy_spawners)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum Objective {
+    ClearAllEnemies,
+    DestroySpawners,
+    // Seconds to hold out
+    SurviveTimer(f32),
+    EscortPayload,
+}
+
+impl Objective {
+    // The HUD label in front of what is left to do
+    pub fn key(&self) -> &'static str {
+        match self {
+            Objective::ClearAllEnemies => "objective.clear_all_enemies",
+            Objective::DestroySpawners => "objective.destroy_spawners",
+            Objective::SurviveTimer(_) => "objective.survive_timer",
+            Objective::EscortPayload => "objective.escort_payload",
+        }
+    }
+
+    // Announced along with the wave, clearing it out goes without saying
+    pub fn toast_key(&self) -> Option<&'static str> {
+        match self {
+            Objective::ClearAllEnemies => None,
+            Objective::DestroySpawners => Some("toast.spawners"),
+            Objective::SurviveTimer(_) => Some("toast.survive"),
+            Objective::EscortPayload => Some("toast.escort"),
+        }
+    }
+}
+
+// The objective being played for, if any, and how far along it is
+#[derive(Default)]
+pub struct CurrentObjective {
+    pub objective: Option<Objective>,
+    // What is still left to do: enemies or spawners standing, seconds to hold out, or the payload's way to go from 1 to 0
+    pub remaining: f32,
+    pub complete: bool,
+    // Whether there has been anything to clear out yet, as what a wave spawns only shows up a step later
+    begun: bool,
+    elapsed: f32,
+}
+
+impl CurrentObjective {
+    pub fn start(&mut self, objective: Objective) {
+        *self = CurrentObjective { objective: Some(objective), ..default() };
+    }
+}
+
+// Sent once the current objective has been met
+pub struct ObjectiveCompleteEvent(pub Objective);
+
+fn reset_objective(mut objective: ResMut<CurrentObjective>)
+{
+    *objective = CurrentObjective::default();
+}
+
+fn evaluate_objective(
+    mut commands: Commands,
+    mut current: ResMut<CurrentObjective>,
+    // Enemies still being telegraphed count as alive, those already dying don't
+    enemy_query: Query<(Entity, &Transform, Option<&Hp>), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    spawner_query: Query<&Spawner>,
+    payload_query: Query<(&Payload, &Transform)>,
+    track_query: Query<Entity, Or<(With<Payload>, With<PayloadTrack>)>>,
+    mut complete_events: EventWriter<ObjectiveCompleteEvent>,
+    mut toasts: EventWriter<Toast>,
+)
+{
+    let objective = match current.objective {
+        Some(objective) if !current.complete => objective,
+        _ => return,
+    };
+
+    current.remaining = match objective {
+        Objective::ClearAllEnemies => {
+            enemy_query.iter().filter(|(_, _, hp)| hp.map_or(true, |hp| hp.current > 0)).count() as f32
+        }
+        Objective::DestroySpawners => spawner_query.iter().filter(|spawner| spawner.standing()).count() as f32,
+        Objective::SurviveTimer(duration) => {
+            current.elapsed += TIME_STEP;
+            (duration - current.elapsed).max(0.0)
+        }
+        Objective::EscortPayload => payload_query
+            .iter()
+            .map(|(payload, transform)| payload.remaining(transform.translation.truncate()))
+            .reduce(f32::min)
+            .unwrap_or(1.0),
+    };
+    if current.remaining > 0.0 {
+        current.begun = true;
+        return;
+    }
+    if !current.begun {
+        return;
+    }
+
+    current.complete = true;
+    complete_events.send(ObjectiveCompleteEvent(objective));
+    toasts.send(Toast::new("toast.objective_complete").with_color(COMPLETE_COLOR));
+
+    // Whatever the wave left behind goes with it
+    for (entity, transform, hp) in enemy_query.iter() {
+        if hp.map_or(true, |hp| hp.current > 0) {
+            commands.entity(entity).despawn();
+            commands.spawn().insert(ExplosionToSpawn(transform.translation));
+        }
+    }
+    for entity in &track_query {
+        commands.entity(entity).despawn();
+    }
+}
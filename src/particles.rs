@@ -0,0 +1,118 @@
+//! A small particle system: short-lived sprites that drift, fade out and despawn,
+//! and emitters that keep spawning them around an entity.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    physics::{apply_velocity, Velocity},
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(emit_particles.after(apply_velocity))
+                .with_system(update_particles),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Particle(Timer);
+
+// Spawns particles in a ring around its entity, flying straight toward or away from it
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub active: bool,
+    // Particles per second
+    pub rate: f32,
+    // Distance from the entity particles appear at
+    pub spawn_radius: f32,
+    // Outward speed, negative to fly inward
+    pub radial_speed: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    pub color: Color,
+    // Fraction of a particle carried over between steps
+    pending: f32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            active: false,
+            rate: 0.0,
+            spawn_radius: 0.0,
+            radial_speed: 0.0,
+            lifetime: 1.0,
+            size: 2.0,
+            color: Color::WHITE,
+            pending: 0.0,
+        }
+    }
+}
+
+pub fn spawn_particle(commands: &mut Commands, position: Vec3, velocity: Vec2, color: Color, size: f32, lifetime: f32)
+{
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(size)),
+                ..default()
+            },
+            transform: Transform::from_translation(position),
+            ..default()
+        })
+        .insert(Velocity(velocity))
+        .insert(Particle(Timer::from_seconds(lifetime, false)))
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+fn emit_particles(mut commands: Commands, mut emitter_query: Query<(&mut ParticleEmitter, &Transform)>)
+{
+    for (mut emitter, transform) in &mut emitter_query {
+        if !emitter.active {
+            emitter.pending = 0.0;
+            continue;
+        }
+
+        emitter.pending += emitter.rate * TIME_STEP;
+        while emitter.pending >= 1.0 {
+            emitter.pending -= 1.0;
+
+            let angle = thread_rng().gen_range(0.0..TAU);
+            let direction = Vec2::new(angle.cos(), angle.sin());
+            let position = transform.translation + (direction * emitter.spawn_radius).extend(1.0);
+            spawn_particle(
+                &mut commands,
+                position,
+                direction * emitter.radial_speed,
+                emitter.color,
+                emitter.size,
+                emitter.lifetime,
+            );
+        }
+    }
+}
+
+fn update_particles(mut commands: Commands, mut query: Query<(Entity, &mut Particle, &mut Sprite)>)
+{
+    for (entity, mut particle, mut sprite) in &mut query {
+        particle.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if particle.0.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_a(particle.0.percent_left());
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! The payload of escort waves: a slow cart crossing the arena from one side to the other, rolling only while
+//! a player stays close to it. Enemies bumping into it wear it down, and losing it ends the run.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, Bystander, DamageEvent, DamageKind, Hp},
+    enemy::{spawn_enemy_health_bar, Tint},
+    fx::ExplosionToSpawn,
+    physics::apply_velocity,
+    player::Player,
+    spatial::{update_spatial_hash, SpatialHash},
+    FixedUpdate, GameState, LevelEntity, TIME_STEP, WALL_THICKNESS,
+};
+
+const PAYLOAD_SIZE: f32 = 36.0;
+const PAYLOAD_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+const PAYLOAD_HEALTH: i32 = 30;
+const PAYLOAD_SPEED: f32 = 40.0;
+// It only moves while a player is this close
+const ESCORT_RADIUS: f32 = 150.0;
+// Kept this far from the side walls at either end of its way
+const TRACK_MARGIN: f32 = 100.0;
+const TRACK_THICKNESS: f32 = 2.0;
+const TRACK_COLOR: Color = Color::rgba(1.0, 0.85, 0.3, 0.3);
+// Seconds between two hits from enemies touching it
+const PAYLOAD_HIT_INTERVAL: f32 = 0.5;
+
+pub struct PayloadPlugin;
+
+impl Plugin for PayloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(move_payload.after(apply_velocity))
+                .with_system(hurt_payload.after(move_payload).after(update_spatial_hash).before(apply_damage))
+                .with_system(lose_payload.after(apply_damage)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Payload {
+    start: Vec2,
+    end: Vec2,
+    hit_cooldown: Timer,
+    // A frame may run several steps before the despawn is applied, it must only be lost once
+    lost: bool,
+}
+
+impl Payload {
+    // How much of its way is still ahead of it, from 1 at the start to 0 once it has arrived
+    pub fn remaining(&self, position: Vec2) -> f32 {
+        position.distance(self.end) / self.start.distance(self.end).max(1.0)
+    }
+}
+
+// The line the payload follows, torn down along with it
+#[derive(Component)]
+pub struct PayloadTrack;
+
+// Sets the payload off at one side of the arena, headed straight across to the other
+pub fn spawn_payload(commands: &mut Commands, arena: &Arena)
+{
+    let inset = WALL_THICKNESS / 2.0 + TRACK_MARGIN;
+    let start = Vec2::new(arena.left() + inset, 0.0);
+    let end = Vec2::new(arena.right() - inset, 0.0);
+
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Line(start, end),
+            DrawMode::Stroke(StrokeMode::new(TRACK_COLOR, TRACK_THICKNESS)),
+            Transform::from_xyz(0.0, 0.0, -0.3),
+        ))
+        .insert(PayloadTrack)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+
+    // Drawn above the obstacles it rolls over
+    let payload = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite { color: PAYLOAD_COLOR, ..default() },
+            transform: Transform {
+                translation: start.extend(0.5),
+                scale: Vec3::new(PAYLOAD_SIZE, PAYLOAD_SIZE, 1.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Payload {
+            start,
+            end,
+            hit_cooldown: Timer::from_seconds(PAYLOAD_HIT_INTERVAL, false),
+            lost: false,
+        })
+        .insert(Hp { current: PAYLOAD_HEALTH, max: PAYLOAD_HEALTH })
+        .insert(Bystander)
+        .insert(Tint(PAYLOAD_COLOR))
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .id();
+    spawn_enemy_health_bar(commands, payload, PAYLOAD_SIZE);
+}
+
+fn move_payload(
+    player_query: Query<&Transform, (With<Player>, Without<Payload>)>,
+    mut payload_query: Query<(&Payload, &mut Transform)>,
+)
+{
+    for (payload, mut transform) in &mut payload_query {
+        let position = transform.translation.truncate();
+        let escorted = player_query
+            .iter()
+            .any(|player_transform| player_transform.translation.truncate().distance(position) < ESCORT_RADIUS);
+        if !escorted {
+            continue;
+        }
+
+        let to_end = payload.end - position;
+        let step = PAYLOAD_SPEED * TIME_STEP;
+        let position = if to_end.length() <= step { payload.end } else { position + to_end.normalize() * step };
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+// Enemies touching it chip away at it, no more than once in a while. The hit itself is dealt in `apply_damage`
+fn hurt_payload(
+    spatial_hash: Res<SpatialHash>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut payload_query: Query<(Entity, &mut Payload, &Transform)>,
+)
+{
+    for (entity, mut payload, transform) in &mut payload_query {
+        if !payload.hit_cooldown.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            continue;
+        }
+        let enemy = match spatial_hash.neighbours(transform.translation.truncate(), PAYLOAD_SIZE).first() {
+            Some((enemy, _)) => *enemy,
+            None => continue,
+        };
+
+        payload.hit_cooldown.reset();
+        damage_events.send(DamageEvent { target: entity, amount: 1, source: Some(enemy), kind: DamageKind::Contact });
+    }
+}
+
+fn lose_payload(
+    mut commands: Commands,
+    mut state: ResMut<State<GameState>>,
+    mut payload_query: Query<(Entity, &mut Payload, &Hp, &Transform)>,
+    track_query: Query<Entity, With<PayloadTrack>>,
+)
+{
+    for (entity, mut payload, hp, transform) in &mut payload_query {
+        if hp.current > 0 || payload.lost {
+            continue;
+        }
+
+        payload.lost = true;
+        commands.entity(entity).despawn();
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+        for track in &track_query {
+            commands.entity(track).despawn();
+        }
+        let _ = state.push(GameState::GameOver);
+    }
+}
@@ -0,0 +1,354 @@
+//! Photo mode: F12 freezes the run and hides the UI, the camera can then be moved freely with the movement keys
+//! and zoomed with the scroll wheel. Enter saves what is in view as a timestamped PNG in `SCREENSHOT_DIR`,
+//! F12 goes back to the run.
+//!
+//! Bevy can't read the window back, so while in photo mode a second camera renders the same view into an image.
+//! Taking a screenshot copies that image out of the GPU in the render world, right after the frame is drawn.
+
+use std::{
+    fs,
+    num::NonZeroU32,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{Buffer, Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages},
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        RenderApp, RenderStage,
+    },
+    ui::entity::UiCameraConfig,
+};
+use wgpu::{BufferDescriptor, BufferUsages, ImageCopyBuffer, ImageDataLayout, Maintain, MapMode};
+
+use crate::{actions::ActionState, camera::FollowCamera, cleanup::DespawnOnStateExit, GameState};
+
+const PHOTO_MODE_KEY: KeyCode = KeyCode::F12;
+const SCREENSHOT_KEY: KeyCode = KeyCode::Return;
+const SCREENSHOT_DIR: &str = "screenshots";
+// How fast the free camera pans at zoom 1, in pixels per second
+const PAN_SPEED: f32 = 600.0;
+// Rows copied out of a texture have to be padded to a multiple of this many bytes
+const COPY_ROW_ALIGNMENT: u32 = 256;
+const SCREENSHOT_NODE: &str = "screenshot";
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenshotRequest>()
+            .add_plugin(ExtractResourcePlugin::<ScreenshotRequest>::default())
+            .add_system_to_stage(CoreStage::First, clear_screenshot_request)
+            .add_system(toggle_photo_mode)
+            .add_system_set(
+                SystemSet::on_enter(GameState::PhotoMode)
+                    .with_system(spawn_photo_camera)
+                    .with_system(hide_ui),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PhotoMode)
+                    .with_system(pan_camera)
+                    .with_system(sync_photo_camera.after(pan_camera))
+                    .with_system(take_screenshot),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::PhotoMode).with_system(show_ui));
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<PendingScreenshot>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_screenshot)
+            .add_system_to_stage(RenderStage::Cleanup, save_screenshot);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(SCREENSHOT_NODE, ScreenshotNode);
+        graph
+            .add_node_edge(CAMERA_DRIVER, SCREENSHOT_NODE)
+            .expect("the camera driver is part of every render graph");
+    }
+}
+
+// Renders what the follow camera sees into the image screenshots are taken from
+#[derive(Component)]
+struct PhotoCamera;
+
+// A screenshot to take this frame, of `image` to `path`. Cleared again at the start of the next frame
+#[derive(Clone, Default)]
+struct ScreenshotRequest(Option<(Handle<Image>, PathBuf)>);
+
+impl ExtractResource for ScreenshotRequest {
+    type Source = ScreenshotRequest;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
+}
+
+// The buffer a requested screenshot is copied into, in the render world
+#[derive(Default)]
+struct PendingScreenshot(Option<Screenshot>);
+
+struct Screenshot {
+    image: Handle<Image>,
+    path: PathBuf,
+    buffer: Buffer,
+    size: Extent3d,
+    format: TextureFormat,
+    padded_row_bytes: u32,
+}
+
+fn toggle_photo_mode(mut keyboard_input: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>)
+{
+    if !keyboard_input.just_pressed(PHOTO_MODE_KEY) {
+        return;
+    }
+
+    match state.current() {
+        GameState::Playing => {
+            let _ = state.push(GameState::PhotoMode);
+        }
+        GameState::PhotoMode => {
+            let _ = state.pop();
+        }
+        _ => {}
+    }
+    keyboard_input.reset(PHOTO_MODE_KEY);
+}
+
+fn spawn_photo_camera(mut commands: Commands, windows: Res<Windows>, mut images: ResMut<Assets<Image>>)
+{
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let size = Extent3d {
+        width: window.physical_width(),
+        height: window.physical_height(),
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            // What the 2D pipelines render into
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+
+    commands
+        .spawn_bundle(Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(images.add(image)),
+                // Drawn ahead of the window's camera
+                priority: -1,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(PhotoCamera)
+        .insert(DespawnOnStateExit(GameState::PhotoMode));
+}
+
+fn hide_ui(mut commands: Commands, camera_query: Query<Entity, With<FollowCamera>>)
+{
+    for camera in &camera_query {
+        commands.entity(camera).insert(UiCameraConfig { show_ui: false });
+    }
+}
+
+fn show_ui(mut commands: Commands, camera_query: Query<Entity, With<FollowCamera>>)
+{
+    for camera in &camera_query {
+        commands.entity(camera).remove::<UiCameraConfig>();
+    }
+}
+
+// `follow_player` leaves the camera alone in photo mode, zooming works as usual
+fn pan_camera(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<FollowCamera>>,
+)
+{
+    for (mut transform, projection) in &mut camera_query {
+        let pan = actions.movement() * PAN_SPEED * projection.scale * time.delta_seconds();
+        transform.translation += pan.extend(0.0);
+    }
+}
+
+fn sync_photo_camera(
+    follow_query: Query<(&Transform, &OrthographicProjection), (With<FollowCamera>, Without<PhotoCamera>)>,
+    mut photo_query: Query<(&mut Transform, &mut OrthographicProjection), With<PhotoCamera>>,
+)
+{
+    let (follow_transform, follow_projection) = match follow_query.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    for (mut transform, mut projection) in &mut photo_query {
+        *transform = *follow_transform;
+        projection.scaling_mode = follow_projection.scaling_mode.clone();
+        projection.scale = follow_projection.scale;
+    }
+}
+
+fn take_screenshot(
+    keyboard_input: Res<Input<KeyCode>>,
+    camera_query: Query<&Camera, With<PhotoCamera>>,
+    mut request: ResMut<ScreenshotRequest>,
+)
+{
+    if !keyboard_input.just_pressed(SCREENSHOT_KEY) {
+        return;
+    }
+    let image = match camera_query.get_single().map(|camera| &camera.target) {
+        Ok(RenderTarget::Image(image)) => image.clone(),
+        _ => return,
+    };
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let path = PathBuf::from(SCREENSHOT_DIR).join(format!("screenshot-{}.png", timestamp));
+    request.0 = Some((image, path));
+}
+
+fn clear_screenshot_request(mut request: ResMut<ScreenshotRequest>)
+{
+    if request.0.is_some() {
+        request.0 = None;
+    }
+}
+
+fn prepare_screenshot(
+    request: Res<ScreenshotRequest>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    mut pending: ResMut<PendingScreenshot>,
+)
+{
+    let (image, path) = match &request.0 {
+        Some(request) => request,
+        None => return,
+    };
+    // Only once the photo camera's image has made it to the GPU
+    let gpu_image = match images.get(image) {
+        Some(gpu_image) => gpu_image,
+        None => return,
+    };
+
+    let size = Extent3d {
+        width: gpu_image.size.x as u32,
+        height: gpu_image.size.y as u32,
+        depth_or_array_layers: 1,
+    };
+    let row_bytes = size.width * 4;
+    let padded_row_bytes = (row_bytes + COPY_ROW_ALIGNMENT - 1) / COPY_ROW_ALIGNMENT * COPY_ROW_ALIGNMENT;
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("screenshot_buffer"),
+        size: (padded_row_bytes * size.height) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    pending.0 = Some(Screenshot {
+        image: image.clone(),
+        path: path.clone(),
+        buffer,
+        size,
+        format: gpu_image.texture_format,
+        padded_row_bytes,
+    });
+}
+
+// Copies the photo camera's image into the screenshot buffer, once every camera has been drawn
+struct ScreenshotNode;
+
+impl Node for ScreenshotNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let screenshot = match &world.resource::<PendingScreenshot>().0 {
+            Some(screenshot) => screenshot,
+            None => return Ok(()),
+        };
+        let gpu_image = match world.resource::<RenderAssets<Image>>().get(&screenshot.image) {
+            Some(gpu_image) => gpu_image,
+            None => return Ok(()),
+        };
+
+        render_context.command_encoder.copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &screenshot.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(screenshot.padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            screenshot.size,
+        );
+        Ok(())
+    }
+}
+
+// The frame has been submitted by now, so the copy is waited on and written out
+fn save_screenshot(render_device: Res<RenderDevice>, mut pending: ResMut<PendingScreenshot>)
+{
+    let screenshot = match pending.0.take() {
+        Some(screenshot) => screenshot,
+        None => return,
+    };
+
+    let slice = screenshot.buffer.slice(..);
+    slice.map_async(MapMode::Read, |_| ());
+    render_device.wgpu_device().poll(Maintain::Wait);
+
+    let row_bytes = (screenshot.size.width * 4) as usize;
+    let swap_red_blue = matches!(screenshot.format, TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb);
+    let mut pixels = Vec::with_capacity(row_bytes * screenshot.size.height as usize);
+    for row in slice.get_mapped_range().chunks(screenshot.padded_row_bytes as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    screenshot.buffer.unmap();
+    if swap_red_blue {
+        for pixel in pixels.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    let result = fs::create_dir_all(SCREENSHOT_DIR).map_err(|error| error.to_string()).and_then(|_| {
+        image::save_buffer(
+            &screenshot.path,
+            &pixels,
+            screenshot.size.width,
+            screenshot.size.height,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|error| error.to_string())
+    });
+    match result {
+        Ok(()) => info!("Saved screenshot {}", screenshot.path.display()),
+        Err(error) => error!("Failed to save screenshot {}: {}", screenshot.path.display(), error),
+    }
+}
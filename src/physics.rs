@@ -0,0 +1,90 @@
+//! Forces and impulses accumulated over a step, integrated into velocity with mass and drag,
+//! and velocity integrated into position.
+
+use bevy::prelude::*;
+
+use crate::{magnet::magnet, FixedUpdate, TIME_STEP};
+
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(integrate_forces.after(magnet).before(apply_velocity))
+                .with_system(apply_velocity),
+        );
+    }
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+// Walls, obstacles and anything else enemies bounce off
+#[derive(Component)]
+pub struct Collider;
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Mass(pub f32);
+
+// How quickly a body slows down on its own: after `t` seconds it keeps `exp(-drag * t)` of its velocity,
+// however that time is split into steps
+#[derive(Component, Deref, DerefMut)]
+pub struct Drag(pub f32);
+
+// Everything pushing a body this step, cleared once integrated
+#[derive(Component, Default)]
+pub struct Forces {
+    force: Vec2,
+    impulse: Vec2,
+}
+
+impl Forces {
+    // Continuous push, scaled by the timestep when integrated
+    pub fn add_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    // Instant change in momentum, e.g. a hit or a bounce
+    pub fn add_impulse(&mut self, impulse: Vec2) {
+        self.impulse += impulse;
+    }
+}
+
+#[derive(Bundle)]
+pub struct PhysicsBundle {
+    pub velocity: Velocity,
+    pub mass: Mass,
+    pub drag: Drag,
+    pub forces: Forces,
+}
+
+impl PhysicsBundle {
+    pub fn new(velocity: Vec2, mass: f32, drag: f32) -> PhysicsBundle {
+        PhysicsBundle {
+            velocity: Velocity(velocity),
+            mass: Mass(mass),
+            drag: Drag(drag),
+            forces: Forces::default(),
+        }
+    }
+}
+
+// Forces and impulses become acceleration through the body's mass, the magnet included
+pub fn integrate_forces(mut query: Query<(&mut Velocity, &mut Forces, &Mass, &Drag)>)
+{
+    for (mut velocity, mut forces, mass, drag) in &mut query {
+        velocity.0 += (forces.impulse + forces.force * TIME_STEP) / mass.0;
+        velocity.0 *= (-drag.0 * TIME_STEP).exp();
+        *forces = Forces::default();
+    }
+}
+
+pub fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>)
+{
+    for (mut transform, velocity) in &mut query {
+        transform.translation.x += velocity.x * TIME_STEP;
+        transform.translation.y += velocity.y * TIME_STEP;
+    }
+}
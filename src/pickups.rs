@@ -0,0 +1,212 @@
+//! Pickups occasionally dropped by dying enemies, as rolled in `loot`, drawn in by the magnet like anything magnetic and collected on contact.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    combat::Hp,
+    enemy::Enemy,
+    magnet::{Magnetic, Polarity},
+    physics::{apply_velocity, PhysicsBundle},
+    player::{Player, PLAYER_SIZE},
+    status::{StatusEffects, StatusKind, SLOW_DURATION},
+    time_scale::TimeScale,
+    toast::Toast,
+    tween::{Ease, Scale, Tween, TweenMode},
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, LevelEntity, TIME_STEP,
+};
+
+const PICKUP_SIZE: f32 = 12.0;
+// Pickups left lying around disappear after this many seconds
+const PICKUP_LIFETIME: f32 = 10.0;
+// Light and draggy, so the magnet draws them in quickly and they come to rest soon after it lets go
+const PICKUP_MASS: f32 = 0.5;
+const PICKUP_DRAG: f32 = 4.0;
+// Pickups pulse up to this scale and back, so they stand out among the enemies
+const PICKUP_PULSE_SCALE: f32 = 1.4;
+const PICKUP_PULSE_DURATION: f32 = 0.5;
+
+const HEALTH_PACK_HEAL: i32 = 5;
+const MAGNET_BOOST_MULTIPLIER: f32 = 2.0;
+const MAGNET_BOOST_DURATION: f32 = 5.0;
+const SCORE_MULTIPLIER: i32 = 2;
+const SCORE_MULTIPLIER_DURATION: f32 = 10.0;
+// Enemies within this distance of whoever picks up frost are slowed
+const FROST_RADIUS: f32 = 350.0;
+// How slow everything runs after picking up slow motion, and for how many real seconds
+const SLOW_MOTION_SCALE: f32 = 0.4;
+const SLOW_MOTION_DURATION: f32 = 3.0;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUps>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_power_ups))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(expire_pickups.before(apply_velocity))
+                    .with_system(collect_pickups.after(apply_velocity))
+                    .with_system(tick_power_ups),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum Pickup {
+    HealthPack,
+    MagnetBoost,
+    ScoreMultiplier,
+    Frost,
+    SlowMotion,
+}
+
+impl Pickup {
+    fn color(&self) -> Color {
+        match self {
+            Pickup::HealthPack => Color::rgb(0.3, 1.0, 0.3),
+            Pickup::MagnetBoost => Color::rgb(0.4, 0.6, 1.0),
+            Pickup::ScoreMultiplier => Color::rgb(1.0, 0.85, 0.2),
+            Pickup::Frost => Color::rgb(0.7, 0.95, 1.0),
+            Pickup::SlowMotion => Color::rgb(0.8, 0.5, 1.0),
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Pickup::HealthPack => "pickup.health_pack",
+            Pickup::MagnetBoost => "pickup.magnet_boost",
+            Pickup::ScoreMultiplier => "pickup.score_multiplier",
+            Pickup::Frost => "pickup.frost",
+            Pickup::SlowMotion => "pickup.slow_motion",
+        }
+    }
+}
+
+#[derive(Component)]
+struct PickupLifetime(Timer);
+
+// Temporary boosts granted by pickups, each running out on its own
+pub struct PowerUps {
+    magnet_boost: Timer,
+    score_multiplier: Timer,
+}
+
+impl Default for PowerUps {
+    fn default() -> Self {
+        let mut power_ups = Self {
+            magnet_boost: Timer::from_seconds(MAGNET_BOOST_DURATION, false),
+            score_multiplier: Timer::from_seconds(SCORE_MULTIPLIER_DURATION, false),
+        };
+        // Nothing is active at the start of a run
+        power_ups.magnet_boost.tick(Duration::from_secs_f32(MAGNET_BOOST_DURATION));
+        power_ups.score_multiplier.tick(Duration::from_secs_f32(SCORE_MULTIPLIER_DURATION));
+        power_ups
+    }
+}
+
+impl PowerUps {
+    // The player's stats with any active magnet boost applied
+    pub fn boosted(&self, stats: &PlayerStats) -> PlayerStats {
+        let mut boosted = stats.clone();
+        if !self.magnet_boost.finished() {
+            boosted.magnet_force *= MAGNET_BOOST_MULTIPLIER;
+        }
+        boosted
+    }
+
+    pub fn score_multiplier(&self) -> i32 {
+        if self.score_multiplier.finished() { 1 } else { SCORE_MULTIPLIER }
+    }
+}
+
+pub fn spawn_pickup(commands: &mut Commands, pickup: Pickup, position: Vec3)
+{
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: pickup.color(),
+                custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.truncate().extend(0.5)),
+            ..default()
+        })
+        .insert(pickup)
+        .insert(
+            Tween::<Scale>::new(Vec3::ONE, Vec3::splat(PICKUP_PULSE_SCALE), PICKUP_PULSE_DURATION, Ease::QuadInOut)
+                .with_mode(TweenMode::PingPong),
+        )
+        .insert(PickupLifetime(Timer::from_seconds(PICKUP_LIFETIME, false)))
+        .insert_bundle(PhysicsBundle::new(Vec2::ZERO, PICKUP_MASS, PICKUP_DRAG))
+        .insert(Magnetic::new(1.0, Polarity::Positive))
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn reset_power_ups(mut power_ups: ResMut<PowerUps>)
+{
+    *power_ups = PowerUps::default();
+}
+
+fn tick_power_ups(mut power_ups: ResMut<PowerUps>)
+{
+    let delta = Duration::from_secs_f32(TIME_STEP);
+    power_ups.magnet_boost.tick(delta);
+    power_ups.score_multiplier.tick(delta);
+}
+
+// Pickups left alone for too long disappear
+fn expire_pickups(mut commands: Commands, mut pickup_query: Query<(Entity, &mut PickupLifetime)>)
+{
+    for (entity, mut lifetime) in &mut pickup_query {
+        if lifetime.0.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut power_ups: ResMut<PowerUps>,
+    mut time_scale: ResMut<TimeScale>,
+    mut player_query: Query<(&Transform, &mut Hp), With<Player>>,
+    pickup_query: Query<(Entity, &Transform, &Pickup), Without<Player>>,
+    mut enemy_query: Query<(&Transform, &mut StatusEffects), (With<Enemy>, Without<Player>)>,
+    mut toasts: EventWriter<Toast>,
+)
+{
+    let reach = (PLAYER_SIZE.x + PICKUP_SIZE) / 2.0;
+    for (entity, transform, pickup) in &pickup_query {
+        let collector = player_query
+            .iter_mut()
+            .find(|(player_transform, _)| {
+                transform.translation.truncate().distance(player_transform.translation.truncate()) <= reach
+            });
+        let (player_transform, mut player_hp) = match collector {
+            Some(collector) => collector,
+            None => continue,
+        };
+
+        match pickup {
+            Pickup::HealthPack => player_hp.current = (player_hp.current + HEALTH_PACK_HEAL).min(player_hp.max),
+            Pickup::MagnetBoost => power_ups.magnet_boost.reset(),
+            Pickup::ScoreMultiplier => power_ups.score_multiplier.reset(),
+            Pickup::Frost => {
+                for (enemy_transform, mut effects) in &mut enemy_query {
+                    if enemy_transform.translation.distance(player_transform.translation) < FROST_RADIUS {
+                        effects.apply(StatusKind::Slow, SLOW_DURATION);
+                    }
+                }
+            }
+            Pickup::SlowMotion => time_scale.slow_motion(SLOW_MOTION_SCALE, SLOW_MOTION_DURATION),
+        }
+        toasts.send(Toast::new(pickup.key()).with_color(pickup.color()));
+        commands.entity(entity).despawn();
+    }
+}
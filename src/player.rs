@@ -0,0 +1,472 @@
+//! The players' ships: spawning them at the start of a run (two of them in co-op), moving and dashing them
+//! around the arena, turning them towards where they aim, their thruster trail and the thud when an enemy runs into one.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_simple_stat_bars::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    animation::AnimatedSprite,
+    arena::{generate_arena, Arena},
+    camera::{track_cursor, CursorWorldPos},
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, AttackCooldown, Hp, Invulnerable},
+    config::GameConfig,
+    deployables::Deployables,
+    enemy::check_for_collisions,
+    levels::LoadingLevel,
+    magnet::{magnet, MagnetEnergy},
+    magnet_railgun::MagnetRailgun,
+    particles::{spawn_particle, ParticleEmitter},
+    physics::Collider,
+    sound::{PlaySfx, Sfx},
+    status::StatusEffects,
+    touch::TouchControls,
+    upgrades::PlayerStats,
+    weapons::Weapon,
+    FixedUpdate, GameState, BACKGROUND_COLOR, TIME_STEP, WALL_THICKNESS,
+};
+
+// These constants are defined in `Transform` units.
+// Using the default 2D camera they correspond 1:1 with screen pixels.
+pub const PLAYER_SIZE: Vec3 = Vec3::new(30.0, 30.0, 0.0);
+const GAP_BETWEEN_PLAYER_AND_FLOOR: f32 = 60.0;
+// How close can the player get to the wall
+const PLAYER_PADDING: f32 = 10.0;
+
+// Starting value, upgraded over the course of a run through `PlayerStats`
+pub const PLAYER_HEALTH: f32 = 20.0;
+
+const DASH_SPEED: f32 = 900.0;
+// Seconds a dash lasts, and how long the player can't be hurt from its start
+const DASH_DURATION: f32 = 0.15;
+const DASH_INVULNERABILITY: f32 = 0.25;
+// Seconds from the start of a dash until the next one is available
+const DASH_COOLDOWN: f32 = 1.0;
+const DASH_TRAIL_COLOR: Color = Color::rgba(0.5, 0.5, 1.0, 0.4);
+const DASH_TRAIL_LIFETIME: f32 = 0.2;
+// Exhaust left behind while the ship moves, streaming out of its back
+const THRUSTER_COLOR: Color = Color::rgba(1.0, 0.7, 0.3, 0.6);
+const THRUSTER_SPEED: f32 = 120.0;
+const THRUSTER_SPREAD: f32 = 40.0;
+const THRUSTER_SIZE: f32 = 5.0;
+const THRUSTER_LIFETIME: f32 = 0.25;
+// Slower than this, e.g. when pressed against a wall, the ship counts as standing still
+const THRUSTER_MIN_SPEED: f32 = 20.0;
+// How quickly a knockback dies down, higher is shorter
+const KNOCKBACK_DECAY: f32 = 8.0;
+const DASH_BAR_COLOR: Color = Color::rgb(1.0, 0.9, 0.4);
+const HEALTH_BAR_COLOR: Color = Color::rgb(0.3, 1.0, 0.3);
+
+// In co-op the players start this far either side of `player_spawn`
+const COOP_SPAWN_OFFSET: f32 = 60.0;
+// Tells the second player's ship apart from the first
+const SECOND_PLAYER_TINT: Color = Color::rgb(0.6, 1.0, 0.6);
+pub const MAX_PLAYERS: usize = 2;
+
+// The sheet holds the idle frames followed by the walking ones
+const PLAYER_SHEET: &str = "images/player_sheet.png";
+const PLAYER_FRAME_SIZE: Vec2 = Vec2::new(72.0, 72.0);
+const IDLE_FRAMES: usize = 4;
+const WALK_FRAMES: usize = 4;
+const IDLE_FPS: f32 = 4.0;
+const WALK_FPS: f32 = 10.0;
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerCollisionEvent>()
+            .init_resource::<PlayerCount>()
+            .add_startup_system(load_player_sprite)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_player))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(move_players_to_spawn.after(generate_arena)))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(aim_with_cursor.after(track_cursor))
+                    .with_system(aim_with_movement)
+                    .with_system(face_aim.after(aim_with_cursor).after(aim_with_movement))
+                    .with_system(face_movement.after(face_aim))
+                    .with_system(animate_player),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(move_player.after(magnet).before(check_for_collisions))
+                    .with_system(thruster_trail.after(move_player))
+                    .with_system(play_player_collision_sounds.after(apply_damage)),
+            );
+    }
+}
+
+// Which player this is, 0 for the first
+#[derive(Component)]
+pub struct Player(pub usize);
+
+// How many players a run starts with, picked on the main menu
+pub struct PlayerCount(pub usize);
+
+impl Default for PlayerCount {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+// Where a player faces, swings their weapon and points their magnet in cone mode, independently of where they move.
+// Kept when the cursor leaves the window or the player stops.
+#[derive(Component)]
+pub struct Aim(pub Vec2);
+
+impl Default for Aim {
+    fn default() -> Self {
+        Self(Vec2::Y)
+    }
+}
+
+// Where the players start, kept clear of obstacles when the arena is generated
+pub fn player_spawn(arena: &Arena) -> Vec2
+{
+    Vec2::new(0.0, arena.bottom() + GAP_BETWEEN_PLAYER_AND_FLOOR)
+}
+
+// Where the player with this index starts, side by side with the others in co-op
+pub fn coop_spawn(arena: &Arena, index: usize, count: usize) -> Vec2
+{
+    let offset = (index as f32 - (count - 1) as f32 / 2.0) * 2.0 * COOP_SPAWN_OFFSET;
+    player_spawn(arena) + offset * Vec2::X
+}
+
+// The position of whichever player is closest to `position`, if any is left
+pub fn nearest_player(players: impl IntoIterator<Item = Vec2>, position: Vec2) -> Option<Vec2>
+{
+    players
+        .into_iter()
+        .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+}
+
+// Throws a player back after a hit, on top of their own movement, dying down over a moment
+#[derive(Component, Default)]
+pub struct Knockback(pub Vec2);
+
+// How fast a player actually moved on the last step, after walls and obstacles stopped them
+#[derive(Component, Default)]
+pub struct Movement(pub Vec2);
+
+#[derive(Component)]
+pub struct Dash {
+    direction: Vec2,
+    duration: Timer,
+    cooldown: Timer,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        let finished = |seconds| {
+            let mut timer = Timer::from_seconds(seconds, false);
+            timer.tick(Duration::from_secs_f32(seconds));
+            timer
+        };
+        // Nothing is running at the start of a run, and the first dash is available straight away
+        Self {
+            direction: Vec2::ZERO,
+            duration: finished(DASH_DURATION),
+            cooldown: finished(DASH_COOLDOWN),
+        }
+    }
+}
+
+impl Dash {
+    pub fn active(&self) -> bool {
+        !self.duration.finished()
+    }
+
+    fn start(&mut self, direction: Vec2) {
+        self.direction = direction;
+        self.duration.reset();
+        self.cooldown.reset();
+    }
+
+    fn tick(&mut self) {
+        let delta = Duration::from_secs_f32(TIME_STEP);
+        self.duration.tick(delta);
+        self.cooldown.tick(delta);
+    }
+}
+
+// Sent whenever an enemy running into a player hurts them, carrying the player. Sent from `apply_damage`, so
+// not while the player is invulnerable
+pub struct PlayerCollisionEvent(pub Entity);
+
+struct PlayerSprite(Handle<TextureAtlas>);
+
+fn load_player_sprite(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+)
+{
+    let atlas = TextureAtlas::from_grid(asset_server.load(PLAYER_SHEET), PLAYER_FRAME_SIZE, IDLE_FRAMES + WALK_FRAMES, 1);
+    commands.insert_resource(PlayerSprite(texture_atlases.add(atlas)));
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    sprite: Res<PlayerSprite>,
+    config: Res<GameConfig>,
+    stats: Res<PlayerStats>,
+    player_count: Res<PlayerCount>,
+    arena: Res<Arena>,
+)
+{
+    let count = player_count.0.clamp(1, MAX_PLAYERS);
+    for index in 0..count {
+        let color = if index == 0 { Color::WHITE } else { SECOND_PLAYER_TINT };
+
+        let player = commands
+            .spawn()
+            .insert(Player(index))
+            .insert(ActionState::default())
+            .insert(Hp { current: stats.max_hp, max: stats.max_hp })
+            .insert(StatusEffects::default())
+            .insert(MagnetEnergy::new(config.magnet_energy))
+            .insert(Aim::default())
+            .insert(AttackCooldown::default())
+            .insert(Weapon::default())
+            .insert(Deployables::default())
+            .insert(MagnetRailgun::default())
+            .insert(Dash::default())
+            .insert(Movement::default())
+            .insert(Knockback::default())
+            .insert(ParticleEmitter::default())
+            .insert_bundle(SpriteSheetBundle {
+                transform: Transform {
+                    translation: coop_spawn(&arena, index, count).extend(0.0),
+                    scale: PLAYER_SIZE,
+                    ..default()
+                },
+                sprite: TextureAtlasSprite {
+                    color,
+                    custom_size: Option::from(Vec2::new(1.0, 1.0)),
+                    ..default()
+                },
+                texture_atlas: sprite.0.clone(),
+                ..default()
+            })
+            .insert(AnimatedSprite::new(0, IDLE_FRAMES, IDLE_FPS, true))
+            .insert(Collider)
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .id();
+
+        // Health, above the ship
+        commands
+            .spawn_bundle((
+                StatBarColor(HEALTH_BAR_COLOR),
+                StatBarEmptyColor(BACKGROUND_COLOR),
+                StatBarValue(1.0),
+                StatBarSize { full_length: PLAYER_SIZE.x, thickness: 3.0 },
+                StatBarSubject(player),
+                StatBarPosition((PLAYER_SIZE.y / 2.0 + 6.0) * Vec2::Y),
+                component_observer(|hp: &Hp| hp.current as f32 / hp.max as f32),
+            ))
+            .insert(DespawnOnStateExit(GameState::Playing));
+
+        // Dash cooldown, below the magnet energy bar
+        commands
+            .spawn_bundle((
+                StatBarColor(DASH_BAR_COLOR),
+                StatBarEmptyColor(BACKGROUND_COLOR),
+                StatBarValue(1.0),
+                StatBarSize { full_length: PLAYER_SIZE.x, thickness: 2.0 },
+                StatBarSubject(player),
+                StatBarPosition(-(PLAYER_SIZE.y / 2.0 + 14.0) * Vec2::Y),
+                component_observer(|dash: &Dash| dash.cooldown.percent()),
+            ))
+            .insert(DespawnOnStateExit(GameState::Playing));
+    }
+}
+
+// The players carry on into the next level, starting over from its spawn
+fn move_players_to_spawn(arena: Res<Arena>, mut query: Query<(&Player, &mut Transform)>)
+{
+    let count = query.iter().count();
+    for (player, mut transform) in &mut query {
+        transform.translation = coop_spawn(&arena, player.0, count).extend(transform.translation.z);
+    }
+}
+
+fn move_player(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    arena: Res<Arena>,
+    mut query: Query<
+        (
+            Entity,
+            &ActionState,
+            &mut Transform,
+            &mut Dash,
+            &mut Movement,
+            &mut Knockback,
+            &StatusEffects,
+            Option<&mut Invulnerable>,
+        ),
+        With<Player>,
+    >,
+)
+{
+    for (player, actions, mut player_transform, mut dash, mut moved, mut knockback, effects, invulnerable) in &mut query {
+        dash.tick();
+        let movement = actions.movement();
+        if actions.just_pressed(Action::Dash) && dash.cooldown.finished() && movement != Vec2::ZERO {
+            dash.start(movement.normalize());
+            match invulnerable {
+                Some(mut invulnerable) => invulnerable.extend(DASH_INVULNERABILITY),
+                None => {
+                    commands.entity(player).insert(Invulnerable::new(DASH_INVULNERABILITY));
+                }
+            }
+        }
+
+        let velocity = if dash.active() {
+            // Leave ghosts of the ship behind while dashing
+            spawn_particle(
+                &mut commands,
+                player_transform.translation - Vec3::Z,
+                Vec2::ZERO,
+                DASH_TRAIL_COLOR,
+                PLAYER_SIZE.x,
+                DASH_TRAIL_LIFETIME,
+            );
+            dash.direction * DASH_SPEED
+        } else {
+            movement * config.player_speed
+        } * effects.speed_scale()
+            + knockback.0;
+        knockback.0 *= (-KNOCKBACK_DECAY * TIME_STEP).exp();
+
+        let new_player_pos_x = player_transform.translation.x + velocity.x * TIME_STEP;
+        let new_player_pos_y = player_transform.translation.y + velocity.y * TIME_STEP;
+
+        // Update the player position,
+        // making sure it doesn't cause the player to leave the arena
+        let left_bound = arena.left() + WALL_THICKNESS / 2.0 + PLAYER_SIZE.x / 2.0 + PLAYER_PADDING;
+        let right_bound = arena.right() - WALL_THICKNESS / 2.0 - PLAYER_SIZE.x / 2.0 - PLAYER_PADDING;
+        let bottom_bound = arena.bottom() + WALL_THICKNESS / 2.0 + PLAYER_SIZE.y / 2.0 + PLAYER_PADDING;
+        let top_bound = arena.top() - WALL_THICKNESS / 2.0 - PLAYER_SIZE.y / 2.0 - PLAYER_PADDING;
+
+        let new_player_pos = Vec2::new(
+            new_player_pos_x.clamp(left_bound, right_bound),
+            new_player_pos_y.clamp(bottom_bound, top_bound),
+        );
+
+        // and doesn't walk through any obstacles
+        let new_player_pos = arena.push_out(new_player_pos, PLAYER_SIZE.truncate() / 2.0);
+        moved.0 = (new_player_pos - player_transform.translation.truncate()) / TIME_STEP;
+        player_transform.translation.x = new_player_pos.x;
+        player_transform.translation.y = new_player_pos.y;
+    }
+}
+
+// The first player aims with the mouse
+pub fn aim_with_cursor(
+    cursor: Res<CursorWorldPos>,
+    touch: Res<TouchControls>,
+    mut query: Query<(&Player, &Transform, &mut Aim)>,
+)
+{
+    // Touch screens emulate a cursor where they were last touched, which is usually a button
+    if touch.enabled {
+        return;
+    }
+    let cursor = match cursor.world {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    for (player, transform, mut aim) in &mut query {
+        if player.0 != 0 {
+            continue;
+        }
+        let direction = cursor - transform.translation.truncate();
+        if direction != Vec2::ZERO {
+            aim.0 = direction.normalize();
+        }
+    }
+}
+
+// Everyone else shares the keyboard or has a gamepad, and aims where they are heading. So does the first player
+// on a touch screen
+pub fn aim_with_movement(touch: Res<TouchControls>, mut query: Query<(&Player, &ActionState, &mut Aim)>)
+{
+    for (player, actions, mut aim) in &mut query {
+        if (player.0 != 0 || touch.enabled) && actions.movement() != Vec2::ZERO {
+            aim.0 = actions.movement().normalize();
+        }
+    }
+}
+
+// The ship's sprite points up
+fn face_aim(mut query: Query<(&Aim, &mut Transform), With<Player>>)
+{
+    for (aim, mut transform) in &mut query {
+        transform.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(aim.0));
+    }
+}
+
+// The ship keeps pointing where it aims, but mirrors itself to lean towards the side it is moving to
+fn face_movement(mut query: Query<(&Aim, &Movement, &mut TextureAtlasSprite), With<Player>>)
+{
+    for (aim, movement, mut sprite) in &mut query {
+        let sideways = movement.0.dot(aim.0.perp());
+        if sideways.abs() > THRUSTER_MIN_SPEED {
+            sprite.flip_x = sideways > 0.0;
+        }
+    }
+}
+
+fn thruster_trail(mut commands: Commands, query: Query<(&Transform, &Movement, &Dash), With<Player>>)
+{
+    for (transform, movement, dash) in &query {
+        // Dashing leaves its own trail
+        if dash.active() || movement.0.length() < THRUSTER_MIN_SPEED {
+            continue;
+        }
+
+        let backwards = -movement.0.normalize();
+        let position = transform.translation.truncate() + backwards * PLAYER_SIZE.x / 2.0;
+        let spread = backwards.perp() * THRUSTER_SPREAD * thread_rng().gen_range(-0.5..0.5);
+        spawn_particle(
+            &mut commands,
+            position.extend(transform.translation.z - 1.0),
+            backwards * THRUSTER_SPEED + spread,
+            THRUSTER_COLOR,
+            THRUSTER_SIZE,
+            THRUSTER_LIFETIME,
+        );
+    }
+}
+
+// Walking while trying to move, idling otherwise
+fn animate_player(mut query: Query<(&ActionState, &mut AnimatedSprite), With<Player>>)
+{
+    for (actions, mut animation) in &mut query {
+        if actions.movement() != Vec2::ZERO {
+            animation.play(IDLE_FRAMES, WALK_FRAMES, WALK_FPS);
+        } else {
+            animation.play(0, IDLE_FRAMES, IDLE_FPS);
+        }
+    }
+}
+
+fn play_player_collision_sounds(
+    player_collision_events: EventReader<PlayerCollisionEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    if !player_collision_events.is_empty() {
+        player_collision_events.clear();
+        sfx_events.send(PlaySfx::new(Sfx::PlayerHit));
+    }
+}
@@ -0,0 +1,37 @@
+//! Recycling of short-lived entities: instead of being despawned once they are done, they are hidden and
+//! released back to their `Pool`, and the next spawn of the same kind acquires one of them instead.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+// Hidden entities marked with `T`, waiting to be shown again
+pub struct Pool<T: Component> {
+    free: Vec<Entity>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Default for Pool<T> {
+    fn default() -> Self {
+        Self { free: Vec::new(), marker: PhantomData }
+    }
+}
+
+impl<T: Component> Pool<T> {
+    // An entity to reuse, if any is free. It may have been despawned since it was released,
+    // so callers look it up and spawn a new one when that fails.
+    pub fn acquire(&mut self) -> Option<Entity> {
+        self.free.pop()
+    }
+
+    // Hands a hidden entity back once it is done
+    pub fn release(&mut self, entity: Entity) {
+        self.free.push(entity);
+    }
+}
+
+// For pools whose entities are despawned along with something else, e.g. the last run's on leaving `Playing`
+pub fn clear_pool<T: Component>(mut pool: ResMut<Pool<T>>)
+{
+    pool.free.clear();
+}
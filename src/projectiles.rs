@@ -0,0 +1,244 @@
+//! Shots fired at the player by shooter enemies and bosses. They fly until they hit or expire,
+//! and the magnet can bend them, pushing one away sends it back at the enemies.
+//! The player's drones fire the same shots, already turned against the enemies.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::{tick_telegraphs, AiState, Telegraph},
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, DamageEvent, DamageKind, Invulnerable},
+    enemy::Enemy,
+    magnet::{Magnetic, Polarity},
+    physics::{apply_velocity, PhysicsBundle},
+    player::{nearest_player, Player, PLAYER_SIZE},
+    status::StatusEffects,
+    FixedUpdate, GameState, LevelEntity, TIME_STEP,
+};
+
+const PROJECTILE_SIZE: f32 = 8.0;
+const PROJECTILE_LIFETIME: f32 = 4.0;
+// Light, so the magnet can easily deflect them
+const PROJECTILE_MASS: f32 = 0.3;
+const PROJECTILE_COLOR: Color = Color::rgb(1.0, 0.6, 0.2);
+const DEFLECTED_PROJECTILE_COLOR: Color = Color::rgb(0.5, 1.0, 0.5);
+
+const SHOT_COOLDOWN: f32 = 2.0;
+const SHOT_SPEED: f32 = 250.0;
+const SHOT_DAMAGE: i32 = 1;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(shooters_fire.after(tick_telegraphs))
+                .with_system(expire_projectiles.after(apply_velocity))
+                .with_system(color_deflected_projectiles)
+                .with_system(projectile_hits_player.after(apply_velocity).before(apply_damage))
+                .with_system(projectile_hits_enemies.after(apply_velocity).before(apply_damage)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub damage: i32,
+    // Hurts the player while hostile, enemies once deflected
+    hostile: bool,
+    lifetime: Timer,
+}
+
+impl Projectile {
+    pub fn deflect(&mut self) {
+        self.hostile = false;
+    }
+}
+
+// Time until a shooter enemy fires again
+#[derive(Component)]
+pub struct ShotCooldown(Timer);
+
+impl Default for ShotCooldown {
+    fn default() -> Self {
+        Self(Timer::from_seconds(SHOT_COOLDOWN, true))
+    }
+}
+
+pub fn spawn_projectile(commands: &mut Commands, position: Vec2, velocity: Vec2, damage: i32)
+{
+    spawn_projectile_with(commands, position, velocity, damage, true);
+}
+
+// Fired on the player's side, only hurting enemies
+pub fn spawn_friendly_projectile(commands: &mut Commands, position: Vec2, velocity: Vec2, damage: i32)
+{
+    spawn_projectile_with(commands, position, velocity, damage, false);
+}
+
+fn spawn_projectile_with(commands: &mut Commands, position: Vec2, velocity: Vec2, damage: i32, hostile: bool)
+{
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: PROJECTILE_COLOR,
+                custom_size: Some(Vec2::splat(PROJECTILE_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(1.0)),
+            ..default()
+        })
+        .insert(Projectile {
+            damage,
+            hostile,
+            lifetime: Timer::from_seconds(PROJECTILE_LIFETIME, false),
+        })
+        .insert_bundle(PhysicsBundle::new(velocity, PROJECTILE_MASS, 0.0))
+        // Bent by the magnet like anything else, pushing one away deflects it
+        .insert(Magnetic::new(1.0, Polarity::Positive))
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity);
+}
+
+fn shooters_fire(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    mut shooter_query: Query<
+        (Entity, &Transform, &mut ShotCooldown, &StatusEffects, &AiState, Option<&Telegraph>),
+        Without<Player>,
+    >,
+)
+{
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+
+    for (entity, transform, mut cooldown, effects, state, telegraph) in &mut shooter_query {
+        // Stunned shooters hold their fire until they come to
+        if effects.stunned() {
+            continue;
+        }
+        // Only shoot once close enough to attack
+        if *state != AiState::Attack {
+            continue;
+        }
+
+        // Each shot is wound up first, and fired the moment the wind-up is over
+        match telegraph {
+            Some(telegraph) if telegraph.0.just_finished() => {}
+            Some(_) => continue,
+            None => {
+                if cooldown.0.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() {
+                    commands.entity(entity).insert(Telegraph::default());
+                }
+                continue;
+            }
+        }
+
+        let position = transform.translation.truncate();
+        let player_position = match nearest_player(players.iter().copied(), position) {
+            Some(player_position) => player_position,
+            None => return,
+        };
+
+        let direction = (player_position - position).normalize_or_zero();
+        let muzzle = position + direction * transform.scale.x;
+        spawn_projectile(&mut commands, muzzle, direction * SHOT_SPEED, SHOT_DAMAGE);
+    }
+}
+
+// Projectiles vanish once they leave the arena, hit an obstacle or have flown for too long
+fn expire_projectiles(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    mut query: Query<(Entity, &mut Projectile, &Transform)>,
+)
+{
+    for (entity, mut projectile, transform) in &mut query {
+        let position = transform.translation;
+        let outside = position.x < arena.left()
+            || position.x > arena.right()
+            || position.y < arena.bottom()
+            || position.y > arena.top()
+            || arena.overlaps(position.truncate(), Vec2::ZERO);
+
+        if projectile.lifetime.tick(Duration::from_secs_f32(TIME_STEP)).finished() || outside {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn projectile_hits_player(
+    mut commands: Commands,
+    projectile_query: Query<(Entity, &Projectile, &Transform)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    player_query: Query<(Entity, &Transform, Option<&Invulnerable>), (With<Player>, Without<Projectile>)>,
+)
+{
+    let hit_distance = (PLAYER_SIZE.x + PROJECTILE_SIZE) / 2.0;
+    for (player, player_transform, invulnerable) in &player_query {
+        // Dashing straight through a volley is the point of the dash
+        if invulnerable.is_some() {
+            continue;
+        }
+
+        for (entity, projectile, transform) in &projectile_query {
+            if !projectile.hostile
+                || transform.translation.truncate().distance(player_transform.translation.truncate()) > hit_distance
+            {
+                continue;
+            }
+
+            damage_events.send(DamageEvent {
+                target: player,
+                amount: projectile.damage,
+                source: None,
+                kind: DamageKind::Projectile,
+            });
+            commands.entity(entity).despawn();
+            // Anything else arriving this step is shrugged off
+            break;
+        }
+    }
+}
+
+fn projectile_hits_enemies(
+    mut commands: Commands,
+    projectile_query: Query<(Entity, &Projectile, &Transform)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    enemy_query: Query<(Entity, &Transform), (With<Enemy>, Without<Projectile>)>,
+)
+{
+    for (entity, projectile, transform) in &projectile_query {
+        if projectile.hostile {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let hit = enemy_query.iter().find(|(_, enemy_transform)| {
+            position.distance(enemy_transform.translation.truncate()) < (enemy_transform.scale.x + PROJECTILE_SIZE) / 2.0
+        });
+
+        if let Some((enemy, _)) = hit {
+            damage_events.send(DamageEvent {
+                target: enemy,
+                amount: projectile.damage,
+                source: None,
+                kind: DamageKind::Projectile,
+            });
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn color_deflected_projectiles(mut query: Query<(&Projectile, &mut Sprite), Changed<Projectile>>)
+{
+    for (projectile, mut sprite) in &mut query {
+        if !projectile.hostile {
+            sprite.color = DEFLECTED_PROJECTILE_COLOR;
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! The random number generator gameplay draws from: arena layouts, enemy spawns and pickup drops.
+//! It is reseeded at the start of every run, from `--seed` when given so the same seed always lays out the same run.
+//! Purely cosmetic randomness, like particles and screen shake, keeps using `thread_rng`.
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{arena::generate_arena, cli::Options, GameState};
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reseed_rng.before(generate_arena)));
+    }
+}
+
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+fn reseed_rng(options: Res<Options>, mut rng: ResMut<GameRng>)
+{
+    rng.0 = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+}
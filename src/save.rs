@@ -0,0 +1,159 @@
+//! Persists the current run to disk on quit so it can be continued from the main menu.
+
+use std::fs;
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combat::Hp, difficulty::Difficulty, player::Player, survival::GameMode, upgrades::reset_player_stats, upgrades::PlayerStats,
+    waves::reset_waves, waves::WaveSpawner, GameState, Scoreboard,
+};
+
+const SAVE_PATH: &str = "savegame.ron";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_save_data)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(restore_run.after(reset_waves).after(reset_player_stats)))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(restore_player))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(delete_save_data))
+            .add_system_to_stage(CoreStage::Last, save_on_exit);
+    }
+}
+
+// A run in progress, as written to disk
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveData {
+    pub score: i32,
+    pub wave: u32,
+    pub player_hp: i32,
+    // Missing from saves written before upgrades existed
+    #[serde(default)]
+    pub stats: PlayerStats,
+    // Missing from saves written before difficulties existed, those were all played on normal
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+impl SaveData {
+    fn load() -> Option<SaveData> {
+        let contents = fs::read_to_string(SAVE_PATH).ok()?;
+        match ron::from_str(&contents) {
+            Ok(save_data) => Some(save_data),
+            Err(error) => {
+                warn!("Ignoring unreadable save file {}: {}", SAVE_PATH, error);
+                None
+            }
+        }
+    }
+
+    fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(SAVE_PATH, contents).map_err(|error| error.to_string()));
+
+        if let Err(error) = result {
+            error!("Failed to write save file {}: {}", SAVE_PATH, error);
+        }
+    }
+}
+
+// Inserted by the main menu when the player picks "Continue", consumed once the run is restored
+pub struct PendingLoad(pub SaveData);
+
+fn load_save_data(mut commands: Commands)
+{
+    if let Some(save_data) = SaveData::load() {
+        commands.insert_resource(save_data);
+    }
+}
+
+fn restore_run(
+    pending: Option<Res<PendingLoad>>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut spawner: ResMut<WaveSpawner>,
+    mut stats: ResMut<PlayerStats>,
+)
+{
+    if let Some(pending) = pending {
+        scoreboard.score = pending.0.score;
+        *stats = pending.0.stats.clone();
+        // The saved wave was never cleared, so it is fought again from the start
+        spawner.wave = pending.0.wave.saturating_sub(1);
+    }
+}
+
+// The players only exist a frame after entering `Playing`, so their HP is restored separately
+fn restore_player(
+    mut commands: Commands,
+    pending: Option<Res<PendingLoad>>,
+    stats: Res<PlayerStats>,
+    mut player_query: Query<&mut Hp, With<Player>>,
+)
+{
+    let pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+
+    if player_query.is_empty() {
+        return;
+    }
+    for mut hp in &mut player_query {
+        hp.max = stats.max_hp;
+        hp.current = pending.0.player_hp.min(hp.max);
+    }
+    commands.remove_resource::<PendingLoad>();
+}
+
+// A finished run can't be continued
+fn delete_save_data(mut commands: Commands)
+{
+    commands.remove_resource::<SaveData>();
+    let _ = fs::remove_file(SAVE_PATH);
+}
+
+fn save_on_exit(
+    app_exit_events: EventReader<AppExit>,
+    state: Res<State<GameState>>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    scoreboard: Res<Scoreboard>,
+    spawner: Res<WaveSpawner>,
+    stats: Res<PlayerStats>,
+    player_query: Query<&Hp, With<Player>>,
+)
+{
+    if app_exit_events.is_empty() {
+        return;
+    }
+
+    if !matches!(state.current(), GameState::Playing | GameState::Paused | GameState::Upgrade) {
+        return;
+    }
+
+    // A survival run is only worth its unbroken time, so it can't be continued
+    if *mode != GameMode::Waves {
+        return;
+    }
+
+    // Levels are picked up again from their checkpoint instead
+    if spawner.in_level() {
+        return;
+    }
+
+    // In co-op, everyone comes back with the health of whoever was worst off
+    if let Some(player_hp) = player_query.iter().map(|hp| hp.current).min() {
+        SaveData {
+            score: scoreboard.score,
+            wave: spawner.wave,
+            player_hp,
+            stats: stats.clone(),
+            difficulty: *difficulty,
+        }
+        .write();
+    }
+}
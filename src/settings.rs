@@ -0,0 +1,284 @@
+//! Player preferences for audio, screen shake, the window and the language, persisted between sessions.
+
+use std::fs;
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    cli::Options,
+    juice::JuiceSettings,
+    locale::{Locale, DEFAULT_LANGUAGE, LANGUAGES},
+    ui::{menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE},
+    GameState, BACKGROUND_COLOR, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+};
+
+const SETTINGS_PATH: &str = "settings.ron";
+
+// How much a volume or the shake intensity changes per key press
+const SETTING_STEP: f32 = 0.1;
+// The same shape as the default arena
+pub const WINDOW_SIZES: [(f32, f32); 3] = [(1080.0, 720.0), (1350.0, 900.0), (1620.0, 1080.0)];
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .init_resource::<SettingsCursor>()
+            .add_system(apply_settings)
+            .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(setup_settings_screen))
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(settings_menu)
+                    .with_system(update_settings_text.after(settings_menu)),
+            );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub screen_shake: f32,
+    pub fullscreen: bool,
+    // Index into `WINDOW_SIZES`, used while not fullscreen
+    pub window_size: usize,
+    // Waits for the display to refresh before showing the next frame, no tearing at the cost of some latency
+    #[serde(default = "default_vsync")]
+    pub vsync: bool,
+    // Set once the tutorial has been played through, so it no longer starts by itself
+    #[serde(default)]
+    pub tutorial_done: bool,
+    // Code of the language UI text is shown in, one of `LANGUAGES`
+    #[serde(default = "default_language")]
+    pub language: String,
+}
+
+fn default_language() -> String
+{
+    DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_vsync() -> bool
+{
+    true
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.5,
+            sfx_volume: 1.0,
+            screen_shake: 1.0,
+            fullscreen: false,
+            window_size: 0,
+            vsync: default_vsync(),
+            tutorial_done: false,
+            language: default_language(),
+        }
+    }
+}
+
+impl Settings {
+    fn load() -> Settings {
+        let contents = match fs::read_to_string(SETTINGS_PATH) {
+            Ok(contents) => contents,
+            Err(_) => return Settings::default(),
+        };
+
+        match ron::from_str(&contents) {
+            Ok(settings) => settings,
+            Err(error) => {
+                warn!("Ignoring unreadable settings {}: {}", SETTINGS_PATH, error);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| fs::write(SETTINGS_PATH, contents).map_err(|error| error.to_string()));
+
+        if let Err(error) = result {
+            error!("Failed to write settings {}: {}", SETTINGS_PATH, error);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsEntry {
+    MusicVolume,
+    SfxVolume,
+    ScreenShake,
+    Fullscreen,
+    WindowSize,
+    Vsync,
+    Language,
+}
+
+impl SettingsEntry {
+    const ALL: [SettingsEntry; 7] = [
+        SettingsEntry::MusicVolume,
+        SettingsEntry::SfxVolume,
+        SettingsEntry::ScreenShake,
+        SettingsEntry::Fullscreen,
+        SettingsEntry::WindowSize,
+        SettingsEntry::Vsync,
+        SettingsEntry::Language,
+    ];
+
+    fn label(&self, settings: &Settings, locale: &Locale) -> String {
+        let percent = |value: f32| format!("{:.0}", value * 100.0);
+        match self {
+            SettingsEntry::MusicVolume => locale.format("settings.music_volume", &[("percent", percent(settings.music_volume))]),
+            SettingsEntry::SfxVolume => locale.format("settings.sfx_volume", &[("percent", percent(settings.sfx_volume))]),
+            SettingsEntry::ScreenShake => locale.format("settings.screen_shake", &[("percent", percent(settings.screen_shake))]),
+            SettingsEntry::Fullscreen => {
+                let value = locale.get(if settings.fullscreen { "settings.on" } else { "settings.off" });
+                locale.format("settings.fullscreen", &[("value", value.to_string())])
+            }
+            SettingsEntry::WindowSize => {
+                let (width, height) = WINDOW_SIZES[settings.window_size];
+                locale.format("settings.window_size", &[("width", width.to_string()), ("height", height.to_string())])
+            }
+            SettingsEntry::Vsync => {
+                let value = locale.get(if settings.vsync { "settings.on" } else { "settings.off" });
+                locale.format("settings.vsync", &[("value", value.to_string())])
+            }
+            SettingsEntry::Language => {
+                let name = LANGUAGES
+                    .iter()
+                    .find(|(code, _)| *code == settings.language)
+                    .map_or(settings.language.as_str(), |(_, name)| name);
+                locale.format("settings.language", &[("language", name.to_string())])
+            }
+        }
+    }
+
+    // Steps the setting up or down, toggles flip either way
+    fn adjust(&self, settings: &mut Settings, up: bool) {
+        let step = if up { SETTING_STEP } else { -SETTING_STEP };
+        match self {
+            SettingsEntry::MusicVolume => settings.music_volume = (settings.music_volume + step).clamp(0.0, 1.0),
+            SettingsEntry::SfxVolume => settings.sfx_volume = (settings.sfx_volume + step).clamp(0.0, 1.0),
+            SettingsEntry::ScreenShake => settings.screen_shake = (settings.screen_shake + step).clamp(0.0, 1.0),
+            SettingsEntry::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsEntry::Vsync => settings.vsync = !settings.vsync,
+            SettingsEntry::WindowSize => {
+                let count = WINDOW_SIZES.len();
+                settings.window_size = if up {
+                    (settings.window_size + 1) % count
+                } else {
+                    (settings.window_size + count - 1) % count
+                };
+            }
+            SettingsEntry::Language => {
+                let count = LANGUAGES.len();
+                let current = LANGUAGES.iter().position(|(code, _)| *code == settings.language).unwrap_or(0);
+                let next = if up { (current + 1) % count } else { (current + count - 1) % count };
+                settings.language = LANGUAGES[next].0.to_string();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct SettingsCursor(usize);
+
+#[derive(Component)]
+struct SettingsEntryText(usize);
+
+// Pushes changed settings out to the systems they affect, the volumes are picked up by `sound`.
+// A window given on the command line wins over the saved one for the whole session.
+fn apply_settings(
+    settings: Res<Settings>,
+    options: Res<Options>,
+    mut juice: ResMut<JuiceSettings>,
+    mut windows: ResMut<Windows>,
+)
+{
+    if !settings.is_changed() {
+        return;
+    }
+
+    juice.shake_intensity = settings.screen_shake;
+
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_present_mode(if settings.vsync { PresentMode::Fifo } else { PresentMode::Immediate });
+        if options.fullscreen || (settings.fullscreen && options.window_size.is_none()) {
+            window.set_mode(WindowMode::BorderlessFullscreen);
+        } else if let Some(size) = options.window_size {
+            window.set_mode(WindowMode::Windowed);
+            window.set_resolution(size.x, size.y);
+        } else {
+            let (width, height) = WINDOW_SIZES[settings.window_size.min(WINDOW_SIZES.len() - 1)];
+            window.set_mode(WindowMode::Windowed);
+            window.set_resolution(width, height);
+        }
+    }
+}
+
+fn setup_settings_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut cursor: ResMut<SettingsCursor>,
+)
+{
+    *cursor = SettingsCursor::default();
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::Settings), BACKGROUND_COLOR, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("settings.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        for index in 0..SettingsEntry::ALL.len() {
+            parent
+                .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, WALL_COLOR))
+                .insert(SettingsEntryText(index));
+        }
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            locale.get("settings.hint"),
+            MENU_TEXT_FONT_SIZE,
+            WALL_COLOR,
+        ));
+    });
+}
+
+// Settings is pushed on top of the menu it was opened from, so going back pops it
+fn settings_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cursor: ResMut<SettingsCursor>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    let count = SettingsEntry::ALL.len();
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        let _ = state.pop();
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        cursor.0 = (cursor.0 + 1) % count;
+    } else if keyboard_input.any_just_pressed([KeyCode::Left, KeyCode::Right]) {
+        SettingsEntry::ALL[cursor.0].adjust(&mut settings, keyboard_input.just_pressed(KeyCode::Right));
+        settings.write();
+    }
+}
+
+fn update_settings_text(
+    cursor: Res<SettingsCursor>,
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    mut query: Query<(&mut Text, &SettingsEntryText)>,
+)
+{
+    for (mut text, entry) in &mut query {
+        text.sections[0].value = SettingsEntry::ALL[entry.0].label(&settings, &locale);
+        text.sections[0].style.color = if entry.0 == cursor.0 { SCORE_COLOR } else { WALL_COLOR };
+    }
+}
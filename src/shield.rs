@@ -0,0 +1,105 @@
+//! Shields soaking up damage before it reaches health. They recharge once their bearer has gone
+//! a while without being hit, and show as a second bar just above the health bar.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_simple_stat_bars::prelude::*;
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    player::{Player, PLAYER_SIZE},
+    FixedUpdate, GameState, BACKGROUND_COLOR, TIME_STEP,
+};
+
+// Seconds between each point a shield gets back once it starts recharging
+const SHIELD_REGEN_INTERVAL: f32 = 0.25;
+const SHIELD_BAR_COLOR: Color = Color::rgb(0.4, 0.8, 1.0);
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(regenerate_shields),
+        )
+        .add_system(spawn_player_shield_bars);
+    }
+}
+
+#[derive(Component)]
+pub struct Shield {
+    pub current: i32,
+    pub max: i32,
+    // Time without a hit before it starts recharging
+    pub regen_delay: Timer,
+    regen: Timer,
+}
+
+impl Shield {
+    pub fn new(max: i32, regen_delay: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            regen_delay: Timer::from_seconds(regen_delay, false),
+            regen: Timer::from_seconds(SHIELD_REGEN_INTERVAL, true),
+        }
+    }
+
+    // Takes as much of `amount` as it can and returns what is left for health
+    pub fn absorb(&mut self, amount: i32) -> i32 {
+        let absorbed = amount.min(self.current).max(0);
+        self.current -= absorbed;
+        self.regen_delay.reset();
+        amount - absorbed
+    }
+
+    // Raises the maximum, filling the shield up by the same amount
+    pub fn raise_max(&mut self, max: i32) {
+        self.current += max - self.max;
+        self.max = max;
+    }
+
+    fn fraction(&self) -> f32 {
+        if self.max > 0 { self.current as f32 / self.max as f32 } else { 0.0 }
+    }
+}
+
+// Just above a health bar drawn at `offset` above the subject
+pub fn spawn_shield_bar(commands: &mut Commands, subject: Entity, length: f32, offset: f32)
+{
+    commands
+        .spawn_bundle((
+            StatBarColor(SHIELD_BAR_COLOR),
+            StatBarEmptyColor(BACKGROUND_COLOR),
+            StatBarValue(1.0),
+            StatBarSize { full_length: length, thickness: 2.0 },
+            StatBarSubject(subject),
+            StatBarPosition((offset + 4.0) * Vec2::Y),
+            component_observer(|shield: &Shield| shield.fraction()),
+        ))
+        .insert(DespawnOnStateExit(GameState::Playing));
+}
+
+// Players only get a shield once they upgrade to one, the bar shows up with it
+fn spawn_player_shield_bars(mut commands: Commands, query: Query<Entity, (Added<Shield>, With<Player>)>)
+{
+    for player in &query {
+        spawn_shield_bar(&mut commands, player, PLAYER_SIZE.x, PLAYER_SIZE.y / 2.0 + 6.0);
+    }
+}
+
+fn regenerate_shields(mut query: Query<&mut Shield>)
+{
+    let delta = Duration::from_secs_f32(TIME_STEP);
+    for mut shield in &mut query {
+        if shield.current >= shield.max || !shield.regen_delay.tick(delta).finished() {
+            continue;
+        }
+        if shield.regen.tick(delta).just_finished() {
+            shield.current += 1;
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Music and sound effects on separate channels, each following its volume setting.
+//! The music ducks for a moment whenever something explodes, and the magnet hums for as long as it is held.
+//! Sound effects are asked for by name through `PlaySfx`, the `SfxLibrary` holds the files behind each one.
+//! Those given a position are panned and quieted by where they happen relative to the middle of the view,
+//! so what goes on off-screen can still be placed by ear.
+
+use std::{io::Cursor, time::Duration};
+
+use bevy::{
+    audio::{AudioSink, Decodable},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::HashMap,
+};
+use rand::{seq::SliceRandom, thread_rng};
+use rodio::{source::ChannelVolume, Decoder};
+
+use crate::{
+    camera::FollowCamera,
+    cli::Options,
+    fx::ExplosionToSpawn,
+    levels::LevelClearedEvent,
+    settings::Settings,
+    waves::WaveClearedEvent,
+    GameState,
+};
+
+// The files each sound effect picks from at random, and the speed each is played at, which sets its pitch.
+// Several share a file until they get a recording of their own.
+const SFX_FILES: [(Sfx, &[(&str, f32)]); 10] = [
+    (Sfx::MagnetPull, &[("sounds/magnet_pull.ogg", 1.0)]),
+    (Sfx::MagnetPush, &[("sounds/magnet_push.ogg", 1.0)]),
+    (Sfx::PlayerHit, &[("sounds/player_hit.ogg", 1.0)]),
+    (
+        Sfx::Hit,
+        &[("sounds/player_hit.ogg", 1.3), ("sounds/player_hit.ogg", 1.45), ("sounds/player_hit.ogg", 1.6)],
+    ),
+    (Sfx::Crit, &[("sounds/player_hit.ogg", 0.75)]),
+    (Sfx::Kill, &[("sounds/player_hit.ogg", 0.9)]),
+    (Sfx::ComboBreak, &[("sounds/magnet_push.ogg", 1.0)]),
+    (Sfx::WaveClear, &[("sounds/magnet_pull.ogg", 0.7)]),
+    (Sfx::Teleport, &[("sounds/magnet_pull.ogg", 1.0)]),
+    (Sfx::Explosion, &[("sounds/magnet_push.ogg", 0.6)]),
+];
+// Positioned sounds play at full volume this far from the middle of the view, about the edge of the screen
+const FULL_VOLUME_DISTANCE: f32 = 500.0;
+// Past that they fade over this distance, down to `MIN_SPATIAL_VOLUME` so nothing goes entirely unheard
+const VOLUME_FALLOFF: f32 = 1200.0;
+const MIN_SPATIAL_VOLUME: f32 = 0.15;
+// Sounds this far to the side are only heard from that side's speaker
+const FULL_PAN_DISTANCE: f32 = 800.0;
+
+// Fraction of its volume the music drops to while ducked
+const DUCK_VOLUME: f32 = 0.35;
+const DUCK_DURATION: f32 = 0.6;
+// How quickly the music volume follows its target, per second
+const MUSIC_FADE_SPEED: f32 = 4.0;
+// Seconds the magnet hum takes to fade out once released
+const MAGNET_RELEASE_TAIL: f32 = 0.15;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .init_resource::<MusicDucking>()
+            .init_resource::<MagnetHold>()
+            .init_resource::<MagnetLoops>()
+            .init_resource::<SfxLibrary>()
+            .add_startup_system(start_music)
+            .add_system(announce_cleared_waves.before(play_sfx))
+            .add_system(play_sfx)
+            .add_system(play_explosion_sounds.before(play_sfx))
+            .add_system(duck_music_on_explosions.before(update_music_volume))
+            .add_system(update_music_volume)
+            .add_system(update_magnet_loops);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sfx {
+    MagnetPull,
+    MagnetPush,
+    // An enemy running into a player
+    PlayerHit,
+    // A player's hit landing on an enemy
+    Hit,
+    Crit,
+    Kill,
+    ComboBreak,
+    // The sting announcing a cleared wave or level
+    WaveClear,
+    Teleport,
+    Explosion,
+}
+
+// Plays a sound effect on the SFX channel, `pitch` times as high as it is in the library.
+// Heard from where it happened when given a `position` in the arena, otherwise evenly from both sides.
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    pub pitch: f32,
+    pub position: Option<Vec2>,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        Self { sfx, pitch: 1.0, position: None }
+    }
+
+    pub fn with_pitch(self, pitch: f32) -> Self {
+        Self { pitch, ..self }
+    }
+
+    pub fn at(self, position: Vec2) -> Self {
+        Self { position: Some(position), ..self }
+    }
+}
+
+// A sound effect mixed down to mono and played at its own volume on each speaker.
+// Made fresh for every positioned sound, from the already loaded `AudioSource`.
+#[derive(TypeUuid)]
+#[uuid = "b1d3dcfe-6ee7-4563-9b8c-f6709888c12d"]
+pub struct PannedSound {
+    source: AudioSource,
+    // Left speaker first
+    volumes: [f32; 2],
+}
+
+impl Decodable for PannedSound {
+    type Decoder = ChannelVolume<Decoder<Cursor<AudioSource>>>;
+    type DecoderItem = i16;
+
+    fn decoder(&self) -> Self::Decoder {
+        // Only made from sources that have loaded, so they have already been decoded once
+        let decoder = Decoder::new(Cursor::new(self.source.clone())).expect("loaded sounds can be decoded");
+        ChannelVolume::new(decoder, self.volumes.to_vec())
+    }
+}
+
+// Every sound effect, loaded once up front rather than each time one plays
+pub struct SfxLibrary(HashMap<Sfx, Vec<(Handle<AudioSource>, f32)>>);
+
+impl FromWorld for SfxLibrary {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let sounds = SFX_FILES
+            .into_iter()
+            .map(|(sfx, files)| {
+                let variants = files.iter().map(|(path, speed)| (asset_server.load(*path), *speed)).collect();
+                (sfx, variants)
+            })
+            .collect();
+        Self(sounds)
+    }
+}
+
+impl SfxLibrary {
+    // One of the sound's variants at random, and the speed to play it at
+    fn pick(&self, sfx: Sfx) -> Option<&(Handle<AudioSource>, f32)> {
+        self.0.get(&sfx).and_then(|variants| variants.choose(&mut thread_rng()))
+    }
+
+    // The first variant, for sounds that loop rather than vary
+    fn first(&self, sfx: Sfx) -> Option<&Handle<AudioSource>> {
+        self.0.get(&sfx).and_then(|variants| variants.first()).map(|(handle, _)| handle)
+    }
+}
+
+// The looping soundtrack, kept so its volume can be changed while it plays
+struct Soundtrack {
+    sink: Handle<AudioSink>,
+    volume: f32,
+}
+
+struct MusicDucking(Timer);
+
+impl Default for MusicDucking {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(DUCK_DURATION, false);
+        timer.tick(Duration::from_secs_f32(DUCK_DURATION));
+        Self(timer)
+    }
+}
+
+// Whether the magnet is pulling or pushing, as of the last step
+#[derive(Default)]
+pub struct MagnetHold {
+    pub pulling: bool,
+    pub pushing: bool,
+}
+
+#[derive(Default)]
+struct MagnetLoops {
+    pull: Option<SoundLoop>,
+    push: Option<SoundLoop>,
+}
+
+// A looping sound, fading out over `release` once let go of
+struct SoundLoop {
+    sink: Handle<AudioSink>,
+    release: Option<Timer>,
+}
+
+// `--mute` silences both channels without touching the saved volumes
+fn music_volume(settings: &Settings, options: &Options) -> f32
+{
+    if options.mute { 0.0 } else { settings.music_volume }
+}
+
+fn sfx_volume(settings: &Settings, options: &Options) -> f32
+{
+    if options.mute { 0.0 } else { settings.sfx_volume }
+}
+
+fn start_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    settings: Res<Settings>,
+    options: Res<Options>,
+)
+{
+    let volume = music_volume(&settings, &options);
+    let sink = audio.play_with_settings(
+        asset_server.load("sounds/soundtrack.ogg"),
+        PlaybackSettings::LOOP.with_volume(volume),
+    );
+    commands.insert_resource(Soundtrack {
+        sink: audio_sinks.get_handle(sink),
+        volume,
+    });
+}
+
+// The volume of a sound at `position` on the left and right speaker, heard from `listener`
+fn spatial_volumes(position: Vec2, listener: Vec2) -> [f32; 2]
+{
+    let offset = position - listener;
+    let falloff = (offset.length() - FULL_VOLUME_DISTANCE).max(0.0) / VOLUME_FALLOFF;
+    let volume = (1.0 - falloff).max(MIN_SPATIAL_VOLUME);
+    let pan = (offset.x / FULL_PAN_DISTANCE).clamp(-1.0, 1.0);
+    [volume * (1.0 - pan).min(1.0), volume * (1.0 + pan).min(1.0)]
+}
+
+fn play_sfx(
+    mut sfx_events: EventReader<PlaySfx>,
+    library: Res<SfxLibrary>,
+    audio: Res<Audio>,
+    panned_audio: Res<Audio<PannedSound>>,
+    sources: Res<Assets<AudioSource>>,
+    mut panned_sounds: ResMut<Assets<PannedSound>>,
+    camera_query: Query<&GlobalTransform, With<FollowCamera>>,
+    settings: Res<Settings>,
+    options: Res<Options>,
+)
+{
+    let volume = sfx_volume(&settings, &options);
+    let listener = camera_query.get_single().ok().map(|transform| transform.translation().truncate());
+    for event in sfx_events.iter() {
+        let (sound, speed) = match library.pick(event.sfx) {
+            Some(variant) => variant,
+            None => continue,
+        };
+        let settings = PlaybackSettings::ONCE.with_volume(volume).with_speed(speed * event.pitch);
+
+        // Until the file has loaded it can't be panned, and is played as it is once it has
+        let panned = event.position.zip(listener).zip(sources.get(sound)).map(|((position, listener), source)| {
+            PannedSound { source: source.clone(), volumes: spatial_volumes(position, listener) }
+        });
+        match panned {
+            Some(panned) => {
+                panned_audio.play_with_settings(panned_sounds.add(panned), settings);
+            }
+            None => {
+                audio.play_with_settings(sound.clone(), settings);
+            }
+        }
+    }
+}
+
+// Explosion sprites are pooled, but what asks for one is spawned fresh every time
+fn play_explosion_sounds(
+    explosion_query: Query<&ExplosionToSpawn, Added<ExplosionToSpawn>>,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    for explosion in &explosion_query {
+        sfx_events.send(PlaySfx::new(Sfx::Explosion).at(explosion.0.truncate()));
+    }
+}
+
+fn announce_cleared_waves(
+    mut wave_cleared_events: EventReader<WaveClearedEvent>,
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    if wave_cleared_events.iter().count() + level_cleared_events.iter().count() > 0 {
+        sfx_events.send(PlaySfx::new(Sfx::WaveClear));
+    }
+}
+
+// Like the explosion sounds, goes by what asks for an explosion
+fn duck_music_on_explosions(explosion_query: Query<(), Added<ExplosionToSpawn>>, mut ducking: ResMut<MusicDucking>)
+{
+    if !explosion_query.is_empty() {
+        ducking.0.reset();
+    }
+}
+
+// Eases the music toward its volume setting, or the ducked volume right after an explosion
+fn update_music_volume(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    options: Res<Options>,
+    mut ducking: ResMut<MusicDucking>,
+    soundtrack: Option<ResMut<Soundtrack>>,
+    audio_sinks: Res<Assets<AudioSink>>,
+)
+{
+    ducking.0.tick(time.delta());
+
+    let mut soundtrack = match soundtrack {
+        Some(soundtrack) => soundtrack,
+        None => return,
+    };
+
+    let volume = music_volume(&settings, &options);
+    let target = if ducking.0.finished() { volume } else { volume * DUCK_VOLUME };
+    let step = MUSIC_FADE_SPEED * time.delta_seconds();
+    soundtrack.volume += (target - soundtrack.volume).clamp(-step, step);
+
+    if let Some(sink) = audio_sinks.get(&soundtrack.sink) {
+        sink.set_volume(soundtrack.volume);
+    }
+}
+
+fn update_magnet_loops(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    hold: Res<MagnetHold>,
+    settings: Res<Settings>,
+    options: Res<Options>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    library: Res<SfxLibrary>,
+    mut loops: ResMut<MagnetLoops>,
+)
+{
+    // The magnet goes quiet as soon as the run is paused or over
+    let playing = *state.current() == GameState::Playing;
+    let loops = &mut *loops;
+    let volume = sfx_volume(&settings, &options);
+
+    for (sound_loop, held, sfx) in [
+        (&mut loops.pull, playing && hold.pulling, Sfx::MagnetPull),
+        (&mut loops.push, playing && hold.pushing, Sfx::MagnetPush),
+    ] {
+        let sound = match library.first(sfx) {
+            Some(sound) => sound,
+            None => continue,
+        };
+        update_sound_loop(sound_loop, held, sound, volume, time.delta(), &audio, &audio_sinks);
+    }
+}
+
+fn update_sound_loop(
+    sound_loop: &mut Option<SoundLoop>,
+    held: bool,
+    sound: &Handle<AudioSource>,
+    volume: f32,
+    delta: Duration,
+    audio: &Audio,
+    audio_sinks: &Assets<AudioSink>,
+)
+{
+    if held {
+        if let Some(active) = sound_loop.as_ref().filter(|active| active.release.is_none()) {
+            if let Some(sink) = audio_sinks.get(&active.sink) {
+                sink.set_volume(volume);
+            }
+            return;
+        }
+
+        // Pressed again during the release tail, cut the tail and start over
+        if let Some(sink) = sound_loop.take().and_then(|releasing| audio_sinks.get(&releasing.sink)) {
+            sink.pause();
+        }
+
+        let sink = audio.play_with_settings(sound.clone(), PlaybackSettings::LOOP.with_volume(volume));
+        *sound_loop = Some(SoundLoop {
+            sink: audio_sinks.get_handle(sink),
+            release: None,
+        });
+        return;
+    }
+
+    let active = match sound_loop {
+        Some(active) => active,
+        None => return,
+    };
+    let release = active
+        .release
+        .get_or_insert_with(|| Timer::from_seconds(MAGNET_RELEASE_TAIL, false));
+    release.tick(delta);
+
+    let finished = release.finished();
+    let fade = release.percent_left();
+    if let Some(sink) = audio_sinks.get(&active.sink) {
+        if finished {
+            sink.pause();
+        } else {
+            sink.set_volume(volume * fade);
+        }
+    }
+    if finished {
+        *sound_loop = None;
+    }
+}
@@ -0,0 +1,92 @@
+//! A spatial hash bucketing enemies into a uniform grid, rebuilt every step after they move,
+//! so radius and box lookups only look at the enemies nearby rather than all of them.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{enemy::Enemy, physics::apply_velocity, FixedUpdate};
+
+// Side of a grid cell, about the size of the larger enemies
+const CELL_SIZE: f32 = 100.0;
+
+pub struct SpatialHashPlugin;
+
+impl Plugin for SpatialHashPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialHash>().add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(update_spatial_hash.after(apply_velocity)),
+        );
+    }
+}
+
+// Every enemy is listed in each cell its box touches, along with its position as of the last rebuild
+#[derive(Default)]
+pub struct SpatialHash {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl SpatialHash {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn insert(&mut self, entity: Entity, center: Vec2, half_size: Vec2) {
+        let min = SpatialHash::cell(center - half_size);
+        let max = SpatialHash::cell(center + half_size);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push((entity, center));
+            }
+        }
+    }
+
+    // Enemies whose box may overlap the given one, each listed once.
+    // Cells are coarse, callers still check for an actual overlap.
+    pub fn query_aabb(&self, center: Vec2, half_size: Vec2) -> Vec<Entity> {
+        self.entries(center, half_size).into_iter().map(|(entity, _)| entity).collect()
+    }
+
+    // Enemies that may be within `radius` of `center`, callers still check the actual distance
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> Vec<Entity> {
+        self.query_aabb(center, Vec2::splat(radius))
+    }
+
+    // Enemies whose center is within `radius` of `center`, with their position as of the last rebuild.
+    // Saves looking each of them up when that is all that is needed, e.g. to keep enemies apart.
+    pub fn neighbours(&self, center: Vec2, radius: f32) -> Vec<(Entity, Vec2)> {
+        let mut found = self.entries(center, Vec2::splat(radius));
+        found.retain(|(_, position)| position.distance(center) < radius);
+        found
+    }
+
+    fn entries(&self, center: Vec2, half_size: Vec2) -> Vec<(Entity, Vec2)> {
+        let min = SpatialHash::cell(center - half_size);
+        let max = SpatialHash::cell(center + half_size);
+        let mut found = Vec::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if let Some(entries) = self.cells.get(&IVec2::new(x, y)) {
+                    found.extend_from_slice(entries);
+                }
+            }
+        }
+        // Enemies spanning several cells are listed in each of them
+        found.sort_unstable_by_key(|(entity, _)| *entity);
+        found.dedup_by_key(|(entity, _)| *entity);
+        found
+    }
+}
+
+pub fn update_spatial_hash(mut spatial_hash: ResMut<SpatialHash>, query: Query<(Entity, &Transform), With<Enemy>>)
+{
+    // Cells are emptied rather than dropped, the arena is small enough to keep them all allocated
+    for entities in spatial_hash.cells.values_mut() {
+        entities.clear();
+    }
+
+    // Sprites are scaled to the enemy's size
+    for (entity, transform) in &query {
+        spatial_hash.insert(entity, transform.translation.truncate(), transform.scale.truncate() / 2.0);
+    }
+}
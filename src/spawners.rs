@@ -0,0 +1,162 @@
+//! Spawner structures, the objective of every few waves: instead of a fixed batch of enemies, a handful of
+//! buildings show up and keep sending enemies out until they are destroyed. They are sturdy, never move and
+//! are left alone by the magnet. Destroying them all is the wave's objective, see `objectives`.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, Hp},
+    data::{DifficultyScale, EnemyTable, WaveTable},
+    enemy::{spawn_enemy_at, spawn_enemy_health_bar, Enemy, EnemySpawn, SpawnTelegraph, Tint},
+    fx::ExplosionToSpawn,
+    juice::{ScreenShake, ENEMY_DEATH_TRAUMA},
+    physics::Collider,
+    pickups::PowerUps,
+    rng::GameRng,
+    FixedUpdate, GameState, LevelEntity, Scoreboard, TIME_STEP,
+};
+
+const SPAWNER_SIZE: f32 = 50.0;
+const SPAWNER_COLOR: Color = Color::rgb(0.6, 0.2, 0.7);
+// Kept this far from the players and from each other when placed
+const SPAWNER_CLEARANCE: f32 = 300.0;
+// Seconds between two enemies, at the base spawn rate
+const SPAWNER_INTERVAL: f32 = 3.0;
+// How far from the spawner its enemies appear
+const SPAWNER_EMIT_DISTANCE: f32 = 60.0;
+// Spawners hold off while this many enemies are around, so they can't bury the players
+const SPAWNER_ENEMY_CAP: usize = 20;
+const SPAWNER_SCORE: i32 = 50;
+
+pub struct SpawnerPlugin;
+
+impl Plugin for SpawnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(emit_enemies)
+                .with_system(destroy_spawners.after(apply_damage)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Spawner {
+    timer: Timer,
+    // The wave it belongs to, deciding which kinds it sends out and how tough they are
+    wave: u32,
+    difficulty: DifficultyScale,
+    // A frame may run several steps before the despawn is applied, it must only be destroyed once
+    destroyed: bool,
+}
+
+impl Spawner {
+    pub fn standing(&self) -> bool {
+        !self.destroyed
+    }
+}
+
+// Places the spawners of a wave, away from the players and from each other
+pub fn spawn_spawners(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    arena: &Arena,
+    players: &[Vec2],
+    waves: &WaveTable,
+    wave: u32,
+    difficulty: DifficultyScale,
+)
+{
+    let mut avoid = players.to_vec();
+    for _ in 0..waves.spawner_count.max(1) {
+        let position = arena.random_open_position(rng, Vec2::splat(SPAWNER_SIZE / 2.0), &avoid, SPAWNER_CLEARANCE);
+        avoid.push(position);
+        spawn_spawner(commands, position, waves, wave, difficulty);
+    }
+}
+
+pub fn spawn_spawner(commands: &mut Commands, position: Vec2, waves: &WaveTable, wave: u32, difficulty: DifficultyScale)
+{
+    let health = ((waves.spawner_health * difficulty.health) as i32).max(1);
+    let spawner = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite { color: SPAWNER_COLOR, ..default() },
+            transform: Transform {
+                translation: position.extend(-0.1),
+                scale: Vec3::new(SPAWNER_SIZE, SPAWNER_SIZE, 1.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Spawner {
+            timer: Timer::from_seconds(SPAWNER_INTERVAL / difficulty.spawn_rate, true),
+            wave,
+            difficulty,
+            destroyed: false,
+        })
+        .insert(Hp { current: health, max: health })
+        .insert(Tint(SPAWNER_COLOR))
+        .insert(Collider)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .insert(LevelEntity)
+        .id();
+    spawn_enemy_health_bar(commands, spawner, SPAWNER_SIZE);
+}
+
+fn emit_enemies(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    enemies: Option<Res<EnemyTable>>,
+    waves: Option<Res<WaveTable>>,
+    mut rng: ResMut<GameRng>,
+    mut spawner_query: Query<(&mut Spawner, &Transform)>,
+    enemy_query: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+)
+{
+    let (enemies, waves) = match (enemies, waves) {
+        (Some(enemies), Some(waves)) => (enemies, waves),
+        _ => return,
+    };
+    let mut alive = enemy_query.iter().count();
+
+    for (mut spawner, transform) in &mut spawner_query {
+        if !spawner.timer.tick(Duration::from_secs_f32(TIME_STEP)).just_finished() || spawner.destroyed || alive >= SPAWNER_ENEMY_CAP {
+            continue;
+        }
+
+        let kind = waves.random_kind(&mut rng.0, spawner.wave);
+        let spawn = EnemySpawn::new(&enemies, kind, spawner.difficulty);
+        let angle = rng.0.gen_range(0.0..TAU);
+        let position = transform.translation.truncate() + Vec2::new(angle.cos(), angle.sin()) * SPAWNER_EMIT_DISTANCE;
+        let position = arena.push_out(position, Vec2::splat(spawn.definition.size / 2.0));
+        spawn_enemy_at(&mut commands, &mut rng.0, position, spawn);
+        alive += 1;
+    }
+}
+
+pub fn destroy_spawners(
+    mut commands: Commands,
+    mut scoreboard: ResMut<Scoreboard>,
+    power_ups: Res<PowerUps>,
+    mut shake: ResMut<ScreenShake>,
+    mut spawner_query: Query<(Entity, &mut Spawner, &Hp, &Transform)>,
+)
+{
+    for (entity, mut spawner, hp, transform) in &mut spawner_query {
+        if hp.current > 0 || spawner.destroyed {
+            continue;
+        }
+
+        spawner.destroyed = true;
+        commands.entity(entity).despawn();
+        commands.spawn().insert(ExplosionToSpawn(transform.translation));
+        scoreboard.score += SPAWNER_SCORE * power_ups.score_multiplier();
+        shake.add_trauma(ENEMY_DEATH_TRAUMA);
+    }
+}
@@ -0,0 +1,122 @@
+//! Statistics of the current run: kills of each kind of enemy, damage dealt and taken, time spent pulling and pushing,
+//! and distance traveled. Shown as a table on the game-over screen.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    enemy::EnemyKind,
+    locale::Locale,
+    magnet::magnet,
+    player::Player,
+    sound::MagnetHold,
+    FixedUpdate, GameState, TIME_STEP, WALL_COLOR,
+};
+
+const STATS_FONT_SIZE: f32 = 20.0;
+// Characters between the longest label and its value
+const COLUMN_GAP: usize = 2;
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_run_stats))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(track_magnet_usage.after(magnet))
+                    .with_system(track_distance),
+            );
+    }
+}
+
+// Kills and damage are counted where they happen, in `loot` and `combat`
+#[derive(Default)]
+pub struct RunStats {
+    pub kills: HashMap<EnemyKind, u32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    // Seconds anyone held their magnet, co-op players holding at once count once
+    pub pull_time: f32,
+    pub push_time: f32,
+    // Summed over every player
+    pub distance: f32,
+}
+
+impl RunStats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    fn table(&self, locale: &Locale) -> String {
+        let mut rows: Vec<(String, String)> = EnemyKind::ALL
+            .iter()
+            .map(|kind| {
+                let label = locale.format("stats.kills", &[("enemy", locale.get(kind.key()).to_string())]);
+                (label, self.kills.get(kind).copied().unwrap_or(0).to_string())
+            })
+            .collect();
+        rows.push((locale.get("stats.total_kills").to_string(), self.total_kills().to_string()));
+        rows.push((locale.get("stats.damage_dealt").to_string(), self.damage_dealt.to_string()));
+        rows.push((locale.get("stats.damage_taken").to_string(), self.damage_taken.to_string()));
+        rows.push((locale.get("stats.pull_time").to_string(), format!("{:.1}s", self.pull_time)));
+        rows.push((locale.get("stats.push_time").to_string(), format!("{:.1}s", self.push_time)));
+        rows.push((locale.get("stats.distance").to_string(), format!("{:.0}", self.distance)));
+
+        // Translations differ in length, so the label column fits the longest one
+        let width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0) + COLUMN_GAP;
+        rows.iter()
+            .map(|(label, value)| format!("{:<width$}{:>8}", label, value, width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn spawn_run_stats(parent: &mut ChildBuilder, asset_server: &AssetServer, locale: &Locale, stats: &RunStats)
+{
+    parent.spawn_bundle(
+        TextBundle::from_section(
+            stats.table(locale),
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: STATS_FONT_SIZE,
+                color: WALL_COLOR,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        }),
+    );
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>)
+{
+    *stats = RunStats::default();
+}
+
+fn track_magnet_usage(hold: Res<MagnetHold>, mut stats: ResMut<RunStats>)
+{
+    if hold.pulling {
+        stats.pull_time += TIME_STEP;
+    }
+    if hold.pushing {
+        stats.push_time += TIME_STEP;
+    }
+}
+
+// Where each player was on the last step
+fn track_distance(
+    mut last_positions: Local<HashMap<Entity, Vec2>>,
+    mut stats: ResMut<RunStats>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+)
+{
+    for (player, transform) in &player_query {
+        let position = transform.translation.truncate();
+        if let Some(last_position) = last_positions.insert(player, position) {
+            stats.distance += last_position.distance(position);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Timed status effects on players and enemies alike: stun from being slammed into a wall,
+//! slow from the frost pickup, and burn from standing too close to an explosion.
+//! Each effect runs out on its own, stun and slow scale how fast their target moves, burn hurts on every tick.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    combat::{apply_damage, DamageEvent, DamageKind},
+    enemy::{check_for_collisions, WallSlamEvent},
+    fx::ExplosionToSpawn,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const STUN_DURATION: f32 = 0.8;
+pub const SLOW_DURATION: f32 = 4.0;
+// Fraction of their speed slowed targets keep
+const SLOW_FACTOR: f32 = 0.4;
+const BURN_DURATION: f32 = 2.0;
+// Seconds between two burn ticks, and the damage of each
+const BURN_INTERVAL: f32 = 0.5;
+const BURN_DAMAGE: i32 = 1;
+// Anything this close to an explosion catches fire
+const BURN_RADIUS: f32 = 60.0;
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(stun_slammed_enemies.after(check_for_collisions))
+                .with_system(tick_status_effects.before(apply_damage)),
+        )
+        // Explosions are only asked for for a frame, which may not have a fixed step in it
+        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(burn_near_explosions));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusKind {
+    Stun,
+    Slow,
+    Burn,
+}
+
+struct StatusEffect {
+    kind: StatusKind,
+    duration: Timer,
+    // When the effect acts next, for those that act repeatedly
+    tick: Option<Timer>,
+}
+
+// Every effect currently on an entity, at most one of each kind
+#[derive(Component, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    // Starts an effect, or makes one already running last at least `seconds` longer
+    pub fn apply(&mut self, kind: StatusKind, seconds: f32) {
+        if let Some(effect) = self.0.iter_mut().find(|effect| effect.kind == kind) {
+            let remaining = effect.duration.duration().as_secs_f32() - effect.duration.elapsed_secs();
+            if remaining < seconds {
+                effect.duration = Timer::from_seconds(seconds, false);
+            }
+            return;
+        }
+
+        let tick = (kind == StatusKind::Burn).then(|| Timer::from_seconds(BURN_INTERVAL, true));
+        self.0.push(StatusEffect { kind, duration: Timer::from_seconds(seconds, false), tick });
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.has(StatusKind::Stun)
+    }
+
+    // Multiplies the speed the target moves at, a stun stops it entirely
+    pub fn speed_scale(&self) -> f32 {
+        if self.stunned() {
+            0.0
+        } else if self.has(StatusKind::Slow) {
+            SLOW_FACTOR
+        } else {
+            1.0
+        }
+    }
+}
+
+fn stun_slammed_enemies(mut wall_slam_events: EventReader<WallSlamEvent>, mut query: Query<&mut StatusEffects>)
+{
+    for slam in wall_slam_events.iter() {
+        if let Ok(mut effects) = query.get_mut(slam.enemy) {
+            effects.apply(StatusKind::Stun, STUN_DURATION);
+        }
+    }
+}
+
+fn burn_near_explosions(
+    explosion_query: Query<&ExplosionToSpawn, Added<ExplosionToSpawn>>,
+    mut query: Query<(&Transform, &mut StatusEffects)>,
+)
+{
+    for explosion in &explosion_query {
+        let center = explosion.0.truncate();
+        for (transform, mut effects) in &mut query {
+            if transform.translation.truncate().distance(center) < BURN_RADIUS {
+                effects.apply(StatusKind::Burn, BURN_DURATION);
+            }
+        }
+    }
+}
+
+// Counts every effect down and lets the repeating ones act
+fn tick_status_effects(mut damage_events: EventWriter<DamageEvent>, mut query: Query<(Entity, &mut StatusEffects)>)
+{
+    let delta = Duration::from_secs_f32(TIME_STEP);
+    for (entity, mut effects) in &mut query {
+        let mut burns = 0;
+        for effect in effects.0.iter_mut() {
+            effect.duration.tick(delta);
+            if let Some(tick) = effect.tick.as_mut() {
+                burns += tick.tick(delta).times_finished_this_tick();
+            }
+        }
+        effects.0.retain(|effect| !effect.duration.finished());
+
+        if burns > 0 {
+            damage_events.send(DamageEvent {
+                target: entity,
+                amount: BURN_DAMAGE * burns as i32,
+                source: None,
+                kind: DamageKind::Burn,
+            });
+        }
+    }
+}
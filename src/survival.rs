@@ -0,0 +1,132 @@
+//! Survival, an alternate game mode: instead of waves, enemies keep trickling in faster and faster,
+//! and a run is measured by how long it lasts rather than its score.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::Arena,
+    data::{DifficultyCurve, EnemyTable, WaveTable},
+    difficulty::Difficulty,
+    enemy::{spawn_enemy, Enemy, EnemySpawn, SpawnTelegraph},
+    leaderboard::RunClock,
+    player::Player,
+    rng::GameRng,
+    tutorial::Tutorial,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+// Seconds between spawns at the start of a run, divided by the `DifficultyCurve` spawn rate
+const START_SPAWN_INTERVAL: f32 = 1.5;
+// New kinds of enemies show up as if a wave was cleared every this many seconds
+const SECONDS_PER_WAVE: f32 = 30.0;
+// Spawning pauses while this many enemies are alive
+const MAX_SURVIVAL_ENEMIES: usize = 60;
+
+pub struct SurvivalPlugin;
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<SurvivalSpawner>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_survival_spawner))
+            .add_system_set(SystemSet::new().with_run_criteria(FixedUpdate).with_system(spawn_survival_enemies));
+    }
+}
+
+// Picked on the main menu, kept across restarts
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Waves,
+    Survival,
+    // Hundreds of weak enemies at once, see `horde`
+    Horde,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Waves, GameMode::Survival, GameMode::Horde];
+
+    // As typed on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Waves => "Waves",
+            GameMode::Survival => "Survival",
+            GameMode::Horde => "Horde",
+        }
+    }
+
+    // As shown in the menus, translated
+    pub fn key(&self) -> &'static str {
+        match self {
+            GameMode::Waves => "mode.waves",
+            GameMode::Survival => "mode.survival",
+            GameMode::Horde => "mode.horde",
+        }
+    }
+
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Waves => GameMode::Survival,
+            GameMode::Survival => GameMode::Horde,
+            GameMode::Horde => GameMode::Waves,
+        }
+    }
+}
+
+struct SurvivalSpawner(Timer);
+
+impl Default for SurvivalSpawner {
+    fn default() -> Self {
+        Self(Timer::from_seconds(START_SPAWN_INTERVAL, false))
+    }
+}
+
+fn reset_survival_spawner(mut spawner: ResMut<SurvivalSpawner>)
+{
+    *spawner = SurvivalSpawner::default();
+}
+
+fn spawn_survival_enemies(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    arena: Res<Arena>,
+    clock: Res<RunClock>,
+    preset: Res<Difficulty>,
+    tutorial: Res<Tutorial>,
+    enemies: Option<Res<EnemyTable>>,
+    waves: Option<Res<WaveTable>>,
+    curve: Option<Res<DifficultyCurve>>,
+    mut rng: ResMut<GameRng>,
+    mut spawner: ResMut<SurvivalSpawner>,
+    enemy_query: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    player_query: Query<&Transform, With<Player>>,
+)
+{
+    if *mode != GameMode::Survival || tutorial.active() {
+        return;
+    }
+
+    // The enemy mix is shared with the wave mode
+    let (enemies, waves, curve) = match (enemies, waves, curve) {
+        (Some(enemies), Some(waves), Some(curve)) => (enemies, waves, curve),
+        _ => return,
+    };
+
+    if !spawner.0.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+        return;
+    }
+    // Survival has no waves, only the clock drives the curve
+    let difficulty = preset.apply(curve.at(0, clock.0));
+    spawner.0 = Timer::from_seconds(START_SPAWN_INTERVAL / difficulty.spawn_rate, false);
+
+    if enemy_query.iter().count() >= MAX_SURVIVAL_ENEMIES {
+        return;
+    }
+
+    let kind = waves.random_kind(&mut rng.0, (clock.0 / SECONDS_PER_WAVE) as u32 + 1);
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+    spawn_enemy(&mut commands, &mut rng.0, &arena, &players, EnemySpawn::new(&enemies, kind, difficulty));
+}
@@ -0,0 +1,141 @@
+//! Pairs of teleporter pads placed around the arena. Stepping onto one warps a player to its partner,
+//! and enemies flung onto one by the magnet go through as well, keeping their speed.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    arena::{generate_arena, Arena},
+    cleanup::DespawnOnStateExit,
+    config::GameConfig,
+    enemy::Enemy,
+    fx::spawn_sparks,
+    levels::LoadingLevel,
+    magnet::Magnetized,
+    particles::spawn_particle,
+    physics::apply_velocity,
+    player::{player_spawn, Player},
+    rng::GameRng,
+    sound::{PlaySfx, Sfx},
+    FixedUpdate, GameState, LevelEntity, TIME_STEP,
+};
+
+const TELEPORTER_PAIRS: usize = 1;
+const PAD_RADIUS: f32 = 24.0;
+const PAD_THICKNESS: f32 = 3.0;
+const PAD_COLOR: Color = Color::rgb(0.3, 1.0, 0.8);
+const PAD_FILL_COLOR: Color = Color::rgba(0.3, 1.0, 0.8, 0.2);
+// The two pads of a pair are at least this far apart
+const PAD_SEPARATION: f32 = 600.0;
+// Pads are kept this far from where the player appears, so nobody starts on one
+const PAD_SPAWN_CLEARANCE: f32 = 150.0;
+// Seconds before something that went through a pad can do so again, so it doesn't bounce straight back
+const TELEPORT_COOLDOWN: f32 = 1.0;
+// A bright flash at both ends, fading out
+const FLASH_SIZE: f32 = 70.0;
+const FLASH_LIFETIME: f32 = 0.25;
+const FLASH_COLOR: Color = Color::rgba(0.8, 1.0, 0.95, 0.9);
+
+pub struct TeleporterPlugin;
+
+impl Plugin for TeleporterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_teleporters.after(generate_arena)))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(spawn_teleporters.after(generate_arena)))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(tick_teleport_cooldowns)
+                    .with_system(teleport.after(apply_velocity).after(tick_teleport_cooldowns)),
+            );
+    }
+}
+
+// A pad, warping whatever steps on it to its partner
+#[derive(Component)]
+pub struct Teleporter {
+    partner: Entity,
+}
+
+#[derive(Component)]
+struct TeleportCooldown(Timer);
+
+fn spawn_teleporters(mut commands: Commands, mut rng: ResMut<GameRng>, arena: Res<Arena>)
+{
+    let half_size = Vec2::splat(PAD_RADIUS);
+    let mut placed = vec![player_spawn(&arena)];
+
+    for _ in 0..TELEPORTER_PAIRS {
+        let first = arena.random_open_position(&mut rng.0, half_size, &placed, PAD_SPAWN_CLEARANCE);
+        placed.push(first);
+        // Far from its partner, so going through actually gets somewhere
+        let second = arena.random_open_position(&mut rng.0, half_size, &placed, PAD_SEPARATION);
+        placed.push(second);
+
+        let first_pad = commands.spawn().id();
+        let second_pad = commands.spawn().id();
+        for (pad, position, partner) in [(first_pad, first, second_pad), (second_pad, second, first_pad)] {
+            commands
+                .entity(pad)
+                .insert_bundle(GeometryBuilder::build_as(
+                    &shapes::Circle { radius: PAD_RADIUS, center: Vec2::ZERO },
+                    DrawMode::Outlined {
+                        fill_mode: FillMode::color(PAD_FILL_COLOR),
+                        outline_mode: StrokeMode::new(PAD_COLOR, PAD_THICKNESS),
+                    },
+                    Transform::from_translation(position.extend(-0.5)),
+                ))
+                .insert(Teleporter { partner })
+                .insert(DespawnOnStateExit(GameState::Playing))
+                .insert(LevelEntity);
+        }
+    }
+}
+
+fn tick_teleport_cooldowns(mut commands: Commands, mut query: Query<(Entity, &mut TeleportCooldown)>)
+{
+    for (entity, mut cooldown) in &mut query {
+        if cooldown.0.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+            commands.entity(entity).remove::<TeleportCooldown>();
+        }
+    }
+}
+
+// Players on a pad, and enemies the magnet has flung onto one, come out on its partner
+fn teleport(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    pad_query: Query<(&Teleporter, &Transform)>,
+    mut traveller_query: Query<
+        (Entity, &mut Transform, Option<&Player>, Option<&Magnetized>),
+        (Or<(With<Player>, With<Enemy>)>, Without<Teleporter>, Without<TeleportCooldown>),
+    >,
+    mut sfx_events: EventWriter<PlaySfx>,
+)
+{
+    for (entity, mut transform, player, magnetized) in &mut traveller_query {
+        if player.is_none() && magnetized.is_none() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let pad = pad_query
+            .iter()
+            .find(|(_, pad_transform)| pad_transform.translation.truncate().distance(position) < PAD_RADIUS);
+        let exit = match pad.and_then(|(teleporter, _)| pad_query.get(teleporter.partner).ok()) {
+            Some((_, exit)) => exit.translation,
+            None => continue,
+        };
+
+        for flash_position in [transform.translation, exit] {
+            let flash_position = flash_position.truncate().extend(1.0);
+            spawn_particle(&mut commands, flash_position, Vec2::ZERO, FLASH_COLOR, FLASH_SIZE, FLASH_LIFETIME);
+            spawn_sparks(&mut commands, &config, flash_position);
+        }
+        transform.translation = exit.truncate().extend(transform.translation.z);
+        commands.entity(entity).insert(TeleportCooldown(Timer::from_seconds(TELEPORT_COOLDOWN, false)));
+        sfx_events.send(PlaySfx::new(Sfx::Teleport));
+    }
+}
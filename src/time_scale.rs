@@ -0,0 +1,88 @@
+//! How fast the game runs. The fixed timestep is driven by a clock running `TimeScale` times as fast as real time,
+//! so physics, timers and AI all slow down or speed up together: slow motion from a pickup or a boss kill,
+//! or any speed picked with the debug keys.
+
+use std::time::Duration;
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use crate::{GameState, TIME_STEP};
+
+pub const MIN_TIME_SCALE: f32 = 0.25;
+pub const MAX_TIME_SCALE: f32 = 4.0;
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_time_scale))
+            .add_system(tick_time_scale);
+    }
+}
+
+pub struct TimeScale {
+    // Set with the debug keys, kept across runs
+    pub base: f32,
+    // A slow-down or speed-up on top of it, lasting for the timer in real time
+    effect: Option<(f32, Timer)>,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self { base: 1.0, effect: None }
+    }
+}
+
+impl TimeScale {
+    pub fn get(&self) -> f32 {
+        let effect = self.effect.as_ref().map_or(1.0, |(scale, _)| *scale);
+        (self.base * effect).clamp(MIN_TIME_SCALE, MAX_TIME_SCALE)
+    }
+
+    // Runs the game at `scale` for the next `seconds`, replacing any effect already playing
+    pub fn slow_motion(&mut self, scale: f32, seconds: f32) {
+        self.effect = Some((scale, Timer::from_seconds(seconds, false)));
+    }
+}
+
+fn reset_time_scale(mut time_scale: ResMut<TimeScale>)
+{
+    time_scale.effect = None;
+}
+
+// Slow motion wears off in real time, or it would last longer the slower it is
+fn tick_time_scale(time: Res<Time>, mut time_scale: ResMut<TimeScale>)
+{
+    let finished = match &mut time_scale.effect {
+        Some((_, timer)) => timer.tick(time.delta()).finished(),
+        None => return,
+    };
+    if finished {
+        time_scale.effect = None;
+    }
+}
+
+// Stands in for `FixedTimestep`, accumulating scaled time and stepping by `TIME_STEP` for as long as it has enough
+pub fn scaled_fixed_timestep(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut accumulator: Local<Duration>,
+    mut looping: Local<bool>,
+) -> ShouldRun
+{
+    // Only the first check of a frame adds to the accumulator, the rest drain it
+    if !*looping {
+        *accumulator += time.delta().mul_f32(time_scale.get());
+    }
+
+    let step = Duration::from_secs_f32(TIME_STEP);
+    if *accumulator >= step {
+        *accumulator -= step;
+        *looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        *looping = false;
+        ShouldRun::No
+    }
+}
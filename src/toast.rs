@@ -0,0 +1,164 @@
+//! Short notifications in the top right corner of the screen. Any system can send a `Toast` event,
+//! the toast slides in, stacks below the ones already showing, and slides back out once it expires.
+
+use bevy::prelude::*;
+
+use crate::{locale::Locale, SCORE_COLOR, TEXT_COLOR};
+
+const TOAST_DURATION: f32 = 3.0;
+// Seconds taken to slide in, and again to slide out
+const TOAST_SLIDE: f32 = 0.25;
+const TOAST_WIDTH: f32 = 320.0;
+const TOAST_MARGIN: f32 = 10.0;
+// Vertical space each toast takes in the stack
+const TOAST_SPACING: f32 = 70.0;
+// How quickly toasts move up once the ones above them are gone
+const TOAST_STACK_SPEED: f32 = 10.0;
+const TOAST_TITLE_FONT_SIZE: f32 = 24.0;
+const TOAST_BODY_FONT_SIZE: f32 = 18.0;
+const TOAST_BACKGROUND: Color = Color::rgba(0.1, 0.1, 0.1, 0.85);
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        // Toasts count down in real time, so they also go away over the pause and game over screens
+        app.add_event::<Toast>()
+            .add_system(spawn_toasts)
+            .add_system(animate_toasts.after(spawn_toasts));
+    }
+}
+
+// A notification to show, sent as an event. Title and body are keys into the string table,
+// with any `{name}` in their text filled in from the arguments.
+pub struct Toast {
+    pub title: String,
+    pub body: Option<String>,
+    pub args: Vec<(&'static str, String)>,
+    pub color: Color,
+}
+
+impl Toast {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: None,
+            args: Vec::new(),
+            color: SCORE_COLOR,
+        }
+    }
+
+    pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+#[derive(Component)]
+struct ToastNode {
+    lifetime: Timer,
+    // Toasts are stacked in the order they were sent
+    order: u32,
+    top: f32,
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    mut next_order: Local<u32>,
+    mut toasts: EventReader<Toast>,
+    query: Query<(), With<ToastNode>>,
+)
+{
+    // New toasts start out below the ones already showing
+    let mut shown = query.iter().count();
+    for toast in toasts.iter() {
+        let top = TOAST_MARGIN + shown as f32 * TOAST_SPACING;
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(top),
+                        right: Val::Px(-TOAST_WIDTH),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(TOAST_WIDTH), Val::Auto),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    flex_direction: FlexDirection::ColumnReverse,
+                    ..default()
+                },
+                color: TOAST_BACKGROUND.into(),
+                ..default()
+            })
+            .insert(ToastNode {
+                lifetime: Timer::from_seconds(TOAST_DURATION, false),
+                order: *next_order,
+                top,
+            })
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle::from_section(
+                    locale.format(&toast.title, &toast.args),
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: TOAST_TITLE_FONT_SIZE,
+                        color: toast.color,
+                    },
+                ));
+                if let Some(body) = &toast.body {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        locale.format(body, &toast.args),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: TOAST_BODY_FONT_SIZE,
+                            color: TEXT_COLOR,
+                        },
+                    ));
+                }
+            });
+
+        *next_order = next_order.wrapping_add(1);
+        shown += 1;
+    }
+}
+
+fn animate_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ToastNode, &mut Style)>,
+)
+{
+    let mut toasts: Vec<_> = query.iter_mut().collect();
+    toasts.sort_by_key(|(_, toast, _)| toast.order);
+
+    let mut slot = 0;
+    for (entity, mut toast, mut style) in toasts {
+        if toast.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Slides in from beyond the right edge and back out, easing towards its resting place
+        let elapsed = toast.lifetime.elapsed_secs();
+        let shown = (elapsed.min(TOAST_DURATION - elapsed) / TOAST_SLIDE).clamp(0.0, 1.0);
+        let hidden = (1.0 - shown).powi(2);
+        style.position.right = Val::Px(TOAST_MARGIN - hidden * (TOAST_WIDTH + TOAST_MARGIN));
+
+        // Closes up the gap left by toasts that expired
+        let target = TOAST_MARGIN + slot as f32 * TOAST_SPACING;
+        toast.top += (target - toast.top) * (TOAST_STACK_SPEED * time.delta_seconds()).min(1.0);
+        style.position.top = Val::Px(toast.top);
+        slot += 1;
+    }
+}
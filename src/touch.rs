@@ -0,0 +1,251 @@
+//! On-screen controls for touch screens: a virtual joystick on the left half of the screen moves the first player,
+//! and two buttons in the bottom right corner pull and push. They are turned on from the start on mobile, and
+//! elsewhere as soon as the screen is touched. What they do is merged into the first player's actions.
+
+use bevy::{input::InputSystem, prelude::*, utils::HashSet};
+
+use crate::{
+    actions::{update_action_state, Action},
+    cleanup::DespawnOnStateExit,
+    locale::Locale,
+    GameState, WALL_COLOR,
+};
+
+// How far the thumb has to move from where it came down for the joystick to be fully deflected, in pixels
+const JOYSTICK_RADIUS: f32 = 60.0;
+const KNOB_SIZE: f32 = 50.0;
+// Where the joystick rests while no thumb is on it, from the bottom left corner to its center
+const JOYSTICK_HOME: Vec2 = Vec2::new(140.0, 140.0);
+const JOYSTICK_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.1);
+const KNOB_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.3);
+
+const TOUCH_BUTTONS: [Action; 2] = [Action::Pull, Action::Push];
+const BUTTON_SIZE: f32 = 90.0;
+// Kept from the bottom and right edges of the window, and between the buttons
+const BUTTON_MARGIN: f32 = 40.0;
+const BUTTON_GAP: f32 = 20.0;
+const BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+const BUTTON_PRESSED_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+const BUTTON_FONT_SIZE: f32 = 20.0;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                read_touch_controls.after(InputSystem).before(update_action_state),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(spawn_touch_overlay)
+                    .with_system(update_touch_overlay.after(spawn_touch_overlay)),
+            );
+    }
+}
+
+// What the on-screen controls are doing this frame
+pub struct TouchControls {
+    pub enabled: bool,
+    pub movement: Vec2,
+    pub pressed: HashSet<Action>,
+    // The touch steering the joystick, until it is lifted
+    joystick: Option<u64>,
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(any(target_os = "android", target_os = "ios")),
+            movement: Vec2::ZERO,
+            pressed: HashSet::default(),
+            joystick: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct TouchOverlay;
+
+#[derive(Component)]
+struct JoystickBase;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+#[derive(Component)]
+struct TouchButton(Action);
+
+// Distance from the right edge of the window to the button's right side, the first button furthest in
+fn button_right(index: usize) -> f32
+{
+    BUTTON_MARGIN + (TOUCH_BUTTONS.len() - 1 - index) as f32 * (BUTTON_SIZE + BUTTON_GAP)
+}
+
+// The button under a touch, if any. Touches count down from the top of the window
+fn button_at(position: Vec2, window_size: Vec2) -> Option<Action>
+{
+    let top = window_size.y - BUTTON_MARGIN - BUTTON_SIZE;
+    if position.y < top || position.y > top + BUTTON_SIZE {
+        return None;
+    }
+
+    TOUCH_BUTTONS.into_iter().enumerate().find_map(|(index, action)| {
+        let right = window_size.x - button_right(index);
+        (position.x >= right - BUTTON_SIZE && position.x <= right).then_some(action)
+    })
+}
+
+fn read_touch_controls(touches: Res<Touches>, windows: Res<Windows>, mut controls: ResMut<TouchControls>)
+{
+    if touches.iter_just_pressed().next().is_some() {
+        controls.enabled = true;
+    }
+    if !controls.enabled {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    if controls.joystick.map_or(false, |id| touches.get_pressed(id).is_none()) {
+        controls.joystick = None;
+    }
+    // Any touch coming down on the left half takes over the joystick while it is free
+    for touch in touches.iter_just_pressed() {
+        if controls.joystick.is_none()
+            && touch.position().x < window_size.x / 2.0
+            && button_at(touch.position(), window_size).is_none()
+        {
+            controls.joystick = Some(touch.id());
+        }
+    }
+
+    controls.movement = controls
+        .joystick
+        .and_then(|id| touches.get_pressed(id))
+        .map_or(Vec2::ZERO, |touch| {
+            let offset = touch.position() - touch.start_position();
+            (Vec2::new(offset.x, -offset.y) / JOYSTICK_RADIUS).clamp_length_max(1.0)
+        });
+
+    let joystick = controls.joystick;
+    controls.pressed = touches
+        .iter()
+        .filter(|touch| Some(touch.id()) != joystick)
+        .filter_map(|touch| button_at(touch.position(), window_size))
+        .collect();
+}
+
+fn spawn_touch_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    controls: Res<TouchControls>,
+    overlay_query: Query<(), With<TouchOverlay>>,
+)
+{
+    if !controls.enabled || !overlay_query.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(JOYSTICK_HOME.x - JOYSTICK_RADIUS),
+                    bottom: Val::Px(JOYSTICK_HOME.y - JOYSTICK_RADIUS),
+                    ..default()
+                },
+                size: Size::new(Val::Px(JOYSTICK_RADIUS * 2.0), Val::Px(JOYSTICK_RADIUS * 2.0)),
+                ..default()
+            },
+            color: JOYSTICK_COLOR.into(),
+            ..default()
+        })
+        .insert(JoystickBase)
+        .insert(TouchOverlay)
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(KNOB_SIZE), Val::Px(KNOB_SIZE)),
+                        ..default()
+                    },
+                    color: KNOB_COLOR.into(),
+                    ..default()
+                })
+                .insert(JoystickKnob);
+        });
+
+    for (index, action) in TOUCH_BUTTONS.into_iter().enumerate() {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(button_right(index)),
+                        bottom: Val::Px(BUTTON_MARGIN),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(BUTTON_SIZE), Val::Px(BUTTON_SIZE)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                color: BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(TouchButton(action))
+            .insert(TouchOverlay)
+            .insert(DespawnOnStateExit(GameState::Playing))
+            .with_children(|parent| {
+                parent.spawn_bundle(TextBundle::from_section(
+                    locale.get(action.key()),
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: BUTTON_FONT_SIZE,
+                        color: WALL_COLOR,
+                    },
+                ));
+            });
+    }
+}
+
+// The joystick jumps to wherever the thumb came down, and the knob follows the thumb from there
+fn update_touch_overlay(
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    controls: Res<TouchControls>,
+    mut base_query: Query<&mut Style, With<JoystickBase>>,
+    mut knob_query: Query<&mut Style, (With<JoystickKnob>, Without<JoystickBase>)>,
+    mut button_query: Query<(&TouchButton, &mut UiColor)>,
+)
+{
+    let window_height = windows.get_primary().map_or(0.0, |window| window.height());
+    let center = match controls.joystick.and_then(|id| touches.get_pressed(id)) {
+        Some(touch) => Vec2::new(touch.start_position().x, window_height - touch.start_position().y),
+        None => JOYSTICK_HOME,
+    };
+    for mut style in &mut base_query {
+        style.position.left = Val::Px(center.x - JOYSTICK_RADIUS);
+        style.position.bottom = Val::Px(center.y - JOYSTICK_RADIUS);
+    }
+
+    let knob = Vec2::splat(JOYSTICK_RADIUS - KNOB_SIZE / 2.0) + controls.movement * JOYSTICK_RADIUS;
+    for mut style in &mut knob_query {
+        style.position.left = Val::Px(knob.x);
+        style.position.bottom = Val::Px(knob.y);
+    }
+
+    for (button, mut color) in &mut button_query {
+        let pressed = controls.pressed.contains(&button.0);
+        *color = if pressed { BUTTON_PRESSED_COLOR } else { BUTTON_COLOR }.into();
+    }
+}
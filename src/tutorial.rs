@@ -0,0 +1,191 @@
+//! A short tutorial, played on the first run or picked from the main menu: no enemies spawn while prompts
+//! walk the player through moving, pulling, pushing and attacking, each moving on once the player has done it.
+
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState, InputMap},
+    cleanup::DespawnOnStateExit,
+    hud::HUD_FONT_SIZE,
+    locale::Locale,
+    settings::Settings,
+    GameState, SCORE_COLOR,
+};
+
+// Seconds the closing prompt stays up before the waves start
+const TUTORIAL_DONE_DURATION: f32 = 2.0;
+const TUTORIAL_PROMPT_BOTTOM: Val = Val::Px(80.0);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_tutorial_prompt))
+            // Dying halfway through leaves the rest for another time
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(stop_tutorial))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(advance_tutorial)
+                    .with_system(update_tutorial_prompt.after(advance_tutorial)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TutorialStep {
+    Move,
+    Pull,
+    Push,
+    Attack,
+    Done,
+}
+
+impl TutorialStep {
+    fn next(self) -> Option<TutorialStep> {
+        match self {
+            TutorialStep::Move => Some(TutorialStep::Pull),
+            TutorialStep::Pull => Some(TutorialStep::Push),
+            TutorialStep::Push => Some(TutorialStep::Attack),
+            TutorialStep::Attack => Some(TutorialStep::Done),
+            TutorialStep::Done => None,
+        }
+    }
+
+    // Names whatever the player has bound to each action
+    fn prompt(self, input_map: &InputMap, locale: &Locale) -> String {
+        let key = |action| input_map.label(action, locale);
+        match self {
+            TutorialStep::Move => locale.format(
+                "tutorial.move",
+                &[
+                    ("up", key(Action::MoveUp)),
+                    ("left", key(Action::MoveLeft)),
+                    ("down", key(Action::MoveDown)),
+                    ("right", key(Action::MoveRight)),
+                ],
+            ),
+            TutorialStep::Pull => locale.format("tutorial.pull", &[("key", key(Action::Pull))]),
+            TutorialStep::Push => locale.format("tutorial.push", &[("key", key(Action::Push))]),
+            TutorialStep::Attack => locale.format("tutorial.attack", &[("key", key(Action::Attack))]),
+            TutorialStep::Done => locale.get("tutorial.done").to_string(),
+        }
+    }
+
+    fn performed(self, actions: &ActionState) -> bool {
+        match self {
+            TutorialStep::Move => actions.movement() != Vec2::ZERO,
+            TutorialStep::Pull => actions.just_pressed(Action::Pull),
+            TutorialStep::Push => actions.just_pressed(Action::Push),
+            TutorialStep::Attack => actions.just_pressed(Action::Attack),
+            // Moves on by itself
+            TutorialStep::Done => false,
+        }
+    }
+}
+
+// Started from the main menu, spawners hold off while it is active
+#[derive(Default)]
+pub struct Tutorial {
+    step: Option<TutorialStep>,
+    // Counts down the closing prompt
+    done: Timer,
+}
+
+impl Tutorial {
+    pub fn start(&mut self) {
+        self.step = Some(TutorialStep::Move);
+        self.done = Timer::from_seconds(TUTORIAL_DONE_DURATION, false);
+    }
+
+    pub fn active(&self) -> bool {
+        self.step.is_some()
+    }
+}
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+fn spawn_tutorial_prompt(mut commands: Commands, asset_server: Res<AssetServer>, tutorial: Res<Tutorial>)
+{
+    if !tutorial.active() {
+        return;
+    }
+
+    // Along the bottom of the screen, clear of the HUD
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: TUTORIAL_PROMPT_BOTTOM,
+                    ..default()
+                },
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(DespawnOnStateExit(GameState::Playing))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: HUD_FONT_SIZE * 0.75,
+                        color: SCORE_COLOR,
+                    },
+                ))
+                .insert(TutorialPrompt);
+        });
+}
+
+fn stop_tutorial(mut tutorial: ResMut<Tutorial>)
+{
+    tutorial.step = None;
+}
+
+fn advance_tutorial(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    mut tutorial: ResMut<Tutorial>,
+    mut settings: ResMut<Settings>,
+)
+{
+    let step = match tutorial.step {
+        Some(step) => step,
+        None => return,
+    };
+
+    if step == TutorialStep::Done {
+        if tutorial.done.tick(time.delta()).finished() {
+            tutorial.step = None;
+            // Only ever offered by itself once
+            if !settings.tutorial_done {
+                settings.tutorial_done = true;
+                settings.write();
+            }
+        }
+    } else if step.performed(&actions) {
+        tutorial.step = step.next();
+    }
+}
+
+fn update_tutorial_prompt(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    input_map: Res<InputMap>,
+    locale: Res<Locale>,
+    mut query: Query<(&mut Text, &Parent), With<TutorialPrompt>>,
+)
+{
+    for (mut text, parent) in &mut query {
+        match tutorial.step {
+            Some(step) => text.sections[0].value = step.prompt(&input_map, &locale),
+            None => commands.entity(parent.get()).despawn_recursive(),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Small interpolation helpers: a `Tween<L>` component animates one property of its entity, picked by the lens `L`,
+//! from a start to an end value along an easing curve. Tweens run in real time, like the rest of the visuals.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(animate_tweens::<Translation>)
+            .add_system(animate_tweens::<Scale>)
+            .add_system(animate_tweens::<SpriteColor>)
+            .add_system(animate_tweens::<SpriteSize>)
+            .add_system(animate_tweens::<TextColor>);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    // Overshoots a little before settling, for things popping into place
+    BackOut,
+}
+
+impl Ease {
+    // Maps progress through the tween, from 0 to 1, onto how far along the value is
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::BackOut => {
+                const OVERSHOOT: f32 = 1.7;
+                let t = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * t * t * t + OVERSHOOT * t * t
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TweenMode {
+    // Stops at the end value, and the tween is removed
+    Once,
+    // Jumps back to the start value and goes again
+    Loop,
+    // Goes back and forth between the two values
+    PingPong,
+}
+
+// A property of a component that can be tweened
+pub trait Lens: Send + Sync + 'static {
+    type Target: Component;
+    type Value: Copy + Send + Sync + 'static;
+
+    fn lerp(start: Self::Value, end: Self::Value, ratio: f32) -> Self::Value;
+    fn set(target: &mut Self::Target, value: Self::Value);
+}
+
+pub struct Translation;
+
+impl Lens for Translation {
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn lerp(start: Vec3, end: Vec3, ratio: f32) -> Vec3 {
+        start.lerp(end, ratio)
+    }
+
+    fn set(target: &mut Transform, value: Vec3) {
+        target.translation = value;
+    }
+}
+
+pub struct Scale;
+
+impl Lens for Scale {
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn lerp(start: Vec3, end: Vec3, ratio: f32) -> Vec3 {
+        start.lerp(end, ratio)
+    }
+
+    fn set(target: &mut Transform, value: Vec3) {
+        target.scale = value;
+    }
+}
+
+pub struct SpriteColor;
+
+impl Lens for SpriteColor {
+    type Target = Sprite;
+    type Value = Color;
+
+    fn lerp(start: Color, end: Color, ratio: f32) -> Color {
+        lerp_color(start, end, ratio)
+    }
+
+    fn set(target: &mut Sprite, value: Color) {
+        target.color = value;
+    }
+}
+
+// The drawn size of an atlas sprite, which leaves its `Transform` and so anything colliding with it alone
+pub struct SpriteSize;
+
+impl Lens for SpriteSize {
+    type Target = TextureAtlasSprite;
+    type Value = Vec2;
+
+    fn lerp(start: Vec2, end: Vec2, ratio: f32) -> Vec2 {
+        start.lerp(end, ratio)
+    }
+
+    fn set(target: &mut TextureAtlasSprite, value: Vec2) {
+        target.custom_size = Some(value);
+    }
+}
+
+// Every section of the text takes the same color
+pub struct TextColor;
+
+impl Lens for TextColor {
+    type Target = Text;
+    type Value = Color;
+
+    fn lerp(start: Color, end: Color, ratio: f32) -> Color {
+        lerp_color(start, end, ratio)
+    }
+
+    fn set(target: &mut Text, value: Color) {
+        for section in target.sections.iter_mut() {
+            section.style.color = value;
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Tween<L: Lens> {
+    start: L::Value,
+    end: L::Value,
+    ease: Ease,
+    mode: TweenMode,
+    timer: Timer,
+    // Set while a ping-pong tween heads back to its start value
+    reversed: bool,
+    lens: PhantomData<L>,
+}
+
+impl<L: Lens> Tween<L> {
+    pub fn new(start: L::Value, end: L::Value, seconds: f32, ease: Ease) -> Self {
+        Self {
+            start,
+            end,
+            ease,
+            mode: TweenMode::Once,
+            timer: Timer::from_seconds(seconds, false),
+            reversed: false,
+            lens: PhantomData,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: TweenMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn value(&self) -> L::Value {
+        let progress = self.timer.percent();
+        let progress = if self.reversed { 1.0 - progress } else { progress };
+        L::lerp(self.start, self.end, self.ease.apply(progress))
+    }
+}
+
+pub fn lerp_color(start: Color, end: Color, ratio: f32) -> Color
+{
+    let start = Vec4::from(start.as_rgba_f32());
+    let end = Vec4::from(end.as_rgba_f32());
+    let color = start.lerp(end, ratio);
+    Color::rgba(color.x, color.y, color.z, color.w)
+}
+
+pub fn animate_tweens<L: Lens>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Tween<L>, &mut L::Target)>,
+)
+{
+    for (entity, mut tween, mut target) in &mut query {
+        tween.timer.tick(time.delta());
+        L::set(&mut target, tween.value());
+
+        if !tween.timer.finished() {
+            continue;
+        }
+        match tween.mode {
+            TweenMode::Once => {
+                commands.entity(entity).remove::<Tween<L>>();
+            }
+            TweenMode::Loop => tween.timer.reset(),
+            TweenMode::PingPong => {
+                tween.reversed = !tween.reversed;
+                tween.timer.reset();
+            }
+        }
+    }
+}
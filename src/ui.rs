@@ -0,0 +1,452 @@
+//! The main, pause and game over menus, plus the helpers other screens are built with.
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_simple_stat_bars::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    cleanup::DespawnOnStateExit,
+    data::LevelTable,
+    difficulty::Difficulty,
+    leaderboard::{begin_name_entry, format_duration, spawn_leaderboard, NameEntry, RunClock},
+    levels::{RunState, SelectedLevel},
+    locale::Locale,
+    player::{PlayerCount, MAX_PLAYERS},
+    save::{PendingLoad, SaveData},
+    settings::Settings,
+    stats::{spawn_run_stats, RunStats},
+    survival::GameMode,
+    tutorial::Tutorial,
+    GameState, Scoreboard, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+};
+
+pub const MENU_TITLE_FONT_SIZE: f32 = 80.0;
+pub const MENU_TEXT_FONT_SIZE: f32 = 30.0;
+pub const OVERLAY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(setup_main_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(main_menu)
+                    .with_system(update_game_mode_text.after(main_menu))
+                    .with_system(update_difficulty_text.after(main_menu))
+                    .with_system(update_player_count_text.after(main_menu))
+                    .with_system(update_level_name_text.after(main_menu)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(despawn_screen::<OnMainMenuScreen>))
+            // Settings is pushed on top of the main and pause menus, which are hidden meanwhile
+            .add_system_set(SystemSet::on_pause(GameState::MainMenu).with_system(despawn_screen::<OnMainMenuScreen>))
+            .add_system_set(SystemSet::on_resume(GameState::MainMenu).with_system(setup_main_menu))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(reset_pause_cursor)
+                    .with_system(setup_pause_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(pause_menu)
+                    .with_system(update_pause_text.after(pause_menu)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(despawn_screen::<OnPauseScreen>))
+            .add_system_set(SystemSet::on_pause(GameState::Paused).with_system(despawn_screen::<OnPauseScreen>))
+            .add_system_set(SystemSet::on_resume(GameState::Paused).with_system(setup_pause_screen))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver)
+                    .with_system(begin_name_entry)
+                    .with_system(setup_game_over_screen.after(begin_name_entry))
+            )
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(game_over_menu))
+            .init_resource::<PauseCursor>()
+            .add_system(toggle_pause)
+            .add_system(despawn_orphaned_stat_bars);
+    }
+}
+
+#[derive(Component)]
+struct OnMainMenuScreen;
+
+#[derive(Component)]
+struct GameModeText;
+
+#[derive(Component)]
+struct PlayerCountText;
+
+#[derive(Component)]
+struct DifficultyText;
+
+#[derive(Component)]
+struct LevelNameText;
+
+#[derive(Component)]
+struct OnPauseScreen;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PauseEntry {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+}
+
+impl PauseEntry {
+    const ALL: [PauseEntry; 4] = [PauseEntry::Resume, PauseEntry::Restart, PauseEntry::Settings, PauseEntry::Quit];
+
+    fn key(&self) -> &'static str {
+        match self {
+            PauseEntry::Resume => "pause.resume",
+            PauseEntry::Restart => "pause.restart",
+            PauseEntry::Settings => "pause.settings",
+            PauseEntry::Quit => "pause.quit",
+        }
+    }
+}
+
+// Kept while settings is open on top of the pause menu, so going back lands on the same entry
+#[derive(Default)]
+struct PauseCursor(usize);
+
+#[derive(Component)]
+struct PauseEntryText(usize);
+
+// Stat bars outlive their subject otherwise, e.g. when an enemy is killed
+fn despawn_orphaned_stat_bars(
+    mut commands: Commands,
+    bar_query: Query<(Entity, &StatBarSubject)>,
+    subject_query: Query<()>,
+)
+{
+    for (entity, subject) in &bar_query {
+        if subject_query.get(subject.0).is_err() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Whether any connected gamepad just pressed the button
+pub fn gamepad_just_pressed(gamepads: &Gamepads, gamepad_buttons: &Input<GamepadButton>, button: GamepadButtonType) -> bool
+{
+    gamepads.iter().any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(*gamepad, button)))
+}
+
+fn start_pressed(gamepads: &Gamepads, gamepad_buttons: &Input<GamepadButton>) -> bool
+{
+    gamepad_just_pressed(gamepads, gamepad_buttons, GamepadButtonType::Start)
+}
+
+// Spawns a full-screen node that centers its children in a column
+pub fn spawn_screen<T: Component>(
+    commands: &mut Commands,
+    marker: T,
+    background: Color,
+    spawn_children: impl FnOnce(&mut ChildBuilder),
+)
+{
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                // The UI y-axis points up, so reverse the column to stack top to bottom
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: background.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(spawn_children);
+}
+
+pub fn menu_text(asset_server: &AssetServer, value: impl Into<String>, font_size: f32, color: Color) -> TextBundle
+{
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size,
+            color,
+        },
+    )
+        .with_style(Style {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        })
+}
+
+pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands)
+{
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn setup_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    save_data: Option<Res<SaveData>>,
+)
+{
+    spawn_screen(&mut commands, OnMainMenuScreen, Color::NONE, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.start"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        parent
+            .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(GameModeText);
+        parent
+            .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(DifficultyText);
+        parent
+            .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(PlayerCountText);
+        parent
+            .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(LevelNameText);
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.tutorial"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.controls"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.settings"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.high_scores"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        if let Some(save_data) = save_data {
+            parent.spawn_bundle(menu_text(
+                &asset_server,
+                locale.format(
+                    "menu.continue",
+                    &[("wave", save_data.wave.to_string()), ("score", save_data.score.to_string())],
+                ),
+                MENU_TEXT_FONT_SIZE,
+                WALL_COLOR,
+            ));
+        }
+    });
+}
+
+fn main_menu(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    save_data: Option<Res<SaveData>>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut player_count: ResMut<PlayerCount>,
+    settings: Res<Settings>,
+    mut tutorial: ResMut<Tutorial>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if keyboard_input.just_pressed(KeyCode::Return) || start_pressed(&gamepads, &gamepad_buttons) {
+        // The very first run starts with the tutorial
+        if !settings.tutorial_done {
+            tutorial.start();
+        }
+        let _ = state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::T) {
+        tutorial.start();
+        let _ = state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::M) {
+        *mode = mode.next();
+    } else if keyboard_input.just_pressed(KeyCode::D) {
+        *difficulty = difficulty.next();
+    } else if keyboard_input.just_pressed(KeyCode::Key2) {
+        player_count.0 = player_count.0 % MAX_PLAYERS + 1;
+    } else if keyboard_input.just_pressed(KeyCode::V) {
+        let _ = state.set(GameState::LevelSelect);
+    } else if keyboard_input.just_pressed(KeyCode::K) {
+        let _ = state.set(GameState::Controls);
+    } else if keyboard_input.just_pressed(KeyCode::O) {
+        let _ = state.push(GameState::Settings);
+    } else if keyboard_input.just_pressed(KeyCode::L) {
+        let _ = state.set(GameState::Leaderboard);
+    } else if let Some(save_data) = save_data {
+        if keyboard_input.just_pressed(KeyCode::C) {
+            // Only wave runs are ever saved
+            *mode = GameMode::Waves;
+            *difficulty = save_data.difficulty;
+            commands.insert_resource(PendingLoad(save_data.clone()));
+            let _ = state.set(GameState::Playing);
+        }
+    }
+}
+
+fn update_game_mode_text(mode: Res<GameMode>, locale: Res<Locale>, mut query: Query<&mut Text, With<GameModeText>>)
+{
+    for mut text in &mut query {
+        text.sections[0].value = locale.format("menu.mode", &[("mode", locale.get(mode.key()).to_string())]);
+    }
+}
+
+fn update_difficulty_text(
+    difficulty: Res<Difficulty>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<DifficultyText>>,
+)
+{
+    for mut text in &mut query {
+        text.sections[0].value =
+            locale.format("menu.difficulty", &[("difficulty", locale.get(difficulty.key()).to_string())]);
+    }
+}
+
+fn update_player_count_text(
+    player_count: Res<PlayerCount>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<PlayerCountText>>,
+)
+{
+    for mut text in &mut query {
+        text.sections[0].value = locale.format("menu.players", &[("count", player_count.0.to_string())]);
+    }
+}
+
+fn update_level_name_text(
+    selected: Res<SelectedLevel>,
+    levels: Option<Res<LevelTable>>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<LevelNameText>>,
+)
+{
+    for mut text in &mut query {
+        let name = selected.name(levels.as_deref(), &locale).to_string();
+        text.sections[0].value = locale.format("menu.level", &[("level", name)]);
+    }
+}
+
+fn toggle_pause(actions: Res<ActionState>, mut state: ResMut<State<GameState>>)
+{
+    if !actions.just_pressed(Action::Pause) {
+        return;
+    }
+
+    // A transition may already be queued this frame (e.g. game over), which wins over pausing
+    match state.current() {
+        GameState::Playing => {
+            let _ = state.push(GameState::Paused);
+        }
+        GameState::Paused => {
+            let _ = state.pop();
+        }
+        _ => {}
+    }
+}
+
+fn reset_pause_cursor(mut cursor: ResMut<PauseCursor>)
+{
+    *cursor = PauseCursor::default();
+}
+
+// The run stays frozen underneath, the fixed timestep only runs while playing
+fn setup_pause_screen(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>)
+{
+    spawn_screen(&mut commands, OnPauseScreen, OVERLAY_COLOR, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("pause.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        for (index, entry) in PauseEntry::ALL.iter().enumerate() {
+            parent
+                .spawn_bundle(menu_text(&asset_server, locale.get(entry.key()), MENU_TEXT_FONT_SIZE, WALL_COLOR))
+                .insert(PauseEntryText(index));
+        }
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("pause.hint"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+    });
+}
+
+// Navigated with the arrow keys or the d-pad, picked with Enter or the south button
+fn pause_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut cursor: ResMut<PauseCursor>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut state: ResMut<State<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+)
+{
+    let pressed = |key, button| {
+        keyboard_input.just_pressed(key) || gamepad_just_pressed(&gamepads, &gamepad_buttons, button)
+    };
+
+    let count = PauseEntry::ALL.len();
+    if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    } else if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+    } else if pressed(KeyCode::Return, GamepadButtonType::South) {
+        match PauseEntry::ALL[cursor.0] {
+            PauseEntry::Resume => {
+                let _ = state.pop();
+            }
+            PauseEntry::Restart => restart_run(&mut scoreboard, &mut state),
+            PauseEntry::Settings => {
+                let _ = state.push(GameState::Settings);
+            }
+            // The run is saved on the way out, see `save`
+            PauseEntry::Quit => app_exit_events.send(AppExit),
+        }
+    }
+}
+
+fn update_pause_text(cursor: Res<PauseCursor>, mut query: Query<(&mut Text, &PauseEntryText)>)
+{
+    for (mut text, entry) in &mut query {
+        text.sections[0].style.color = if entry.0 == cursor.0 { SCORE_COLOR } else { WALL_COLOR };
+    }
+}
+
+// Starts a fresh run, everything else is reset when `Playing` is entered again
+fn restart_run(scoreboard: &mut Scoreboard, state: &mut State<GameState>)
+{
+    scoreboard.score = 0;
+    // Unwinds the whole stack, so `Playing` is exited and entered anew even with a menu on top of it,
+    // taking the last run's entities with it
+    let _ = state.replace(GameState::Playing);
+}
+
+fn setup_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    scoreboard: Res<Scoreboard>,
+    mode: Res<GameMode>,
+    clock: Res<RunClock>,
+    run_stats: Res<RunStats>,
+    run: Res<RunState>,
+    name_entry: Res<NameEntry>,
+)
+{
+    let title = if run.completed() { "game_over.victory" } else { "game_over.title" };
+    let result = match *mode {
+        GameMode::Waves | GameMode::Horde => locale.format("game_over.score", &[("score", scoreboard.score.to_string())]),
+        GameMode::Survival => locale.format("game_over.survived", &[("time", format_duration(clock.0))]),
+    };
+
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::GameOver), OVERLAY_COLOR, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get(title), MENU_TITLE_FONT_SIZE, SCORE_COLOR));
+        parent.spawn_bundle(menu_text(&asset_server, result, MENU_TEXT_FONT_SIZE, WALL_COLOR));
+        spawn_run_stats(parent, &asset_server, &locale, &run_stats);
+        spawn_leaderboard(parent, &asset_server, &name_entry);
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("menu.restart"), MENU_TEXT_FONT_SIZE, WALL_COLOR));
+    });
+}
+
+fn game_over_menu(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    name_entry: Res<NameEntry>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    // Letters are typed into the name while it is being entered
+    if name_entry.active {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::R) || start_pressed(&gamepads, &gamepad_buttons) {
+        restart_run(&mut scoreboard, &mut state);
+    }
+}
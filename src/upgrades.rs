@@ -0,0 +1,353 @@
+//! Per-run player stats and the shop that upgrades them between waves.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cleanup::DespawnOnStateExit,
+    combat::{Hp, CRIT_CHANCE, CRIT_MULTIPLIER, DAMAGE},
+    locale::Locale,
+    magnet::{MAGNET_FORCE, MAGNET_RADIUS},
+    player::{Player, PLAYER_HEALTH},
+    shield::Shield,
+    ui::{menu_text, spawn_screen, MENU_TEXT_FONT_SIZE, MENU_TITLE_FONT_SIZE, OVERLAY_COLOR},
+    waves::WaveClearedEvent,
+    GameState, Scoreboard, BACKGROUND_COLOR, SCORE_COLOR, TEXT_COLOR, WALL_COLOR,
+};
+
+// Score points the first level of an upgrade costs, each further level costs this much more
+const UPGRADE_BASE_COST: i32 = 5;
+// Seconds the player has to go unhurt before their shield recharges
+const PLAYER_SHIELD_REGEN_DELAY: f32 = 4.0;
+
+const CARD_SIZE: Size<Val> = Size { width: Val::Px(150.0), height: Val::Px(220.0) };
+const CARD_FONT_SIZE: f32 = 24.0;
+const CARD_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const CARD_HOVERED_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerStats::default())
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_player_stats))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(open_shop_on_wave_cleared)
+                    .with_system(grant_player_shields),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Upgrade).with_system(setup_upgrade_screen))
+            .add_system_set(
+                SystemSet::on_update(GameState::Upgrade)
+                    .with_system(upgrade_shop)
+                    .with_system(update_upgrade_cards.after(upgrade_shop)),
+            );
+    }
+}
+
+// Everything about the player that can be improved during a run
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub magnet_radius: f32,
+    pub magnet_force: f32,
+    pub weapon_damage: f32,
+    // Chance of a weapon hit being critical, and how much harder critical hits land
+    #[serde(default = "default_crit_chance")]
+    pub crit_chance: f32,
+    #[serde(default = "default_crit_multiplier")]
+    pub crit_multiplier: f32,
+    pub max_hp: i32,
+    // Players start without a shield
+    #[serde(default)]
+    pub max_shield: i32,
+    // Turret drones orbiting each player, none to start with
+    #[serde(default)]
+    pub drones: u32,
+    // Levels bought so far, indexed by `UpgradeKind`
+    pub levels: [u32; 7],
+}
+
+fn default_crit_chance() -> f32
+{
+    CRIT_CHANCE
+}
+
+fn default_crit_multiplier() -> f32
+{
+    CRIT_MULTIPLIER
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            magnet_radius: MAGNET_RADIUS,
+            magnet_force: MAGNET_FORCE,
+            weapon_damage: DAMAGE,
+            crit_chance: CRIT_CHANCE,
+            crit_multiplier: CRIT_MULTIPLIER,
+            max_hp: PLAYER_HEALTH as i32,
+            max_shield: 0,
+            drones: 0,
+            levels: [0; 7],
+        }
+    }
+}
+
+impl PlayerStats {
+    pub fn level(&self, kind: UpgradeKind) -> u32 {
+        self.levels[kind as usize]
+    }
+
+    pub fn cost(&self, kind: UpgradeKind) -> i32 {
+        UPGRADE_BASE_COST * (self.level(kind) as i32 + 1)
+    }
+
+    fn value(&self, kind: UpgradeKind) -> f32 {
+        match kind {
+            UpgradeKind::MagnetRadius => self.magnet_radius,
+            UpgradeKind::MagnetForce => self.magnet_force,
+            UpgradeKind::WeaponDamage => self.weapon_damage,
+            UpgradeKind::MaxHp => self.max_hp as f32,
+            UpgradeKind::Shield => self.max_shield as f32,
+            // Shown as a percentage
+            UpgradeKind::CritChance => (self.crit_chance * 100.0).round(),
+            UpgradeKind::Drone => self.drones as f32,
+        }
+    }
+
+    fn apply(&mut self, kind: UpgradeKind) {
+        match kind {
+            UpgradeKind::MagnetRadius => self.magnet_radius += 50.0,
+            UpgradeKind::MagnetForce => self.magnet_force += 25.0,
+            UpgradeKind::WeaponDamage => self.weapon_damage += 2.5,
+            UpgradeKind::MaxHp => self.max_hp += 5,
+            UpgradeKind::Shield => self.max_shield += 3,
+            UpgradeKind::CritChance => self.crit_chance += 0.05,
+            UpgradeKind::Drone => self.drones += 1,
+        }
+        self.levels[kind as usize] += 1;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpgradeKind {
+    MagnetRadius,
+    MagnetForce,
+    WeaponDamage,
+    MaxHp,
+    Shield,
+    CritChance,
+    Drone,
+}
+
+impl UpgradeKind {
+    pub const ALL: [UpgradeKind; 7] = [
+        UpgradeKind::MagnetRadius,
+        UpgradeKind::MagnetForce,
+        UpgradeKind::WeaponDamage,
+        UpgradeKind::MaxHp,
+        UpgradeKind::Shield,
+        UpgradeKind::CritChance,
+        UpgradeKind::Drone,
+    ];
+
+    // Key of its name in the string table
+    fn name_key(&self) -> &'static str {
+        match self {
+            UpgradeKind::MagnetRadius => "upgrade.magnet_radius",
+            UpgradeKind::MagnetForce => "upgrade.magnet_force",
+            UpgradeKind::WeaponDamage => "upgrade.weapon_damage",
+            UpgradeKind::MaxHp => "upgrade.max_hp",
+            UpgradeKind::Shield => "upgrade.shield",
+            UpgradeKind::CritChance => "upgrade.crit_chance",
+            UpgradeKind::Drone => "upgrade.drone",
+        }
+    }
+
+    fn key(&self) -> KeyCode {
+        match self {
+            UpgradeKind::MagnetRadius => KeyCode::Key1,
+            UpgradeKind::MagnetForce => KeyCode::Key2,
+            UpgradeKind::WeaponDamage => KeyCode::Key3,
+            UpgradeKind::MaxHp => KeyCode::Key4,
+            UpgradeKind::Shield => KeyCode::Key5,
+            UpgradeKind::CritChance => KeyCode::Key6,
+            UpgradeKind::Drone => KeyCode::Key7,
+        }
+    }
+}
+
+#[derive(Component)]
+struct UpgradeCard(UpgradeKind);
+
+#[derive(Component)]
+struct UpgradeCardText(UpgradeKind);
+
+#[derive(Component)]
+struct UpgradePointsText;
+
+pub fn reset_player_stats(mut stats: ResMut<PlayerStats>)
+{
+    *stats = PlayerStats::default();
+}
+
+fn open_shop_on_wave_cleared(
+    mut wave_cleared_events: EventReader<WaveClearedEvent>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if wave_cleared_events.iter().last().is_some() {
+        let _ = state.push(GameState::Upgrade);
+    }
+}
+
+fn setup_upgrade_screen(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>)
+{
+    spawn_screen(&mut commands, DespawnOnStateExit(GameState::Upgrade), OVERLAY_COLOR, |parent| {
+        parent.spawn_bundle(menu_text(&asset_server, locale.get("upgrade.title"), MENU_TITLE_FONT_SIZE, TEXT_COLOR));
+        parent
+            .spawn_bundle(menu_text(&asset_server, "", MENU_TEXT_FONT_SIZE, SCORE_COLOR))
+            .insert(UpgradePointsText);
+
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .with_children(|row| {
+                for kind in UpgradeKind::ALL {
+                    row.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: CARD_SIZE,
+                            margin: UiRect::all(Val::Px(10.0)),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            flex_direction: FlexDirection::ColumnReverse,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: CARD_COLOR.into(),
+                        ..default()
+                    })
+                        .insert(UpgradeCard(kind))
+                        .with_children(|card| {
+                            card.spawn_bundle(menu_text(&asset_server, locale.get(kind.name_key()), CARD_FONT_SIZE, TEXT_COLOR));
+                            card.spawn_bundle(menu_text(&asset_server, "", CARD_FONT_SIZE, WALL_COLOR))
+                                .insert(UpgradeCardText(kind));
+                        });
+                }
+            });
+
+        parent.spawn_bundle(menu_text(
+            &asset_server,
+            locale.get("upgrade.hint"),
+            MENU_TEXT_FONT_SIZE,
+            WALL_COLOR,
+        ));
+    });
+}
+
+fn upgrade_shop(
+    keyboard_input: Res<Input<KeyCode>>,
+    card_query: Query<(&Interaction, &UpgradeCard), Changed<Interaction>>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut stats: ResMut<PlayerStats>,
+    mut player_query: Query<&mut Hp, With<Player>>,
+    mut state: ResMut<State<GameState>>,
+)
+{
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let _ = state.pop();
+        return;
+    }
+
+    let clicked = card_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, card)| card.0);
+    let pressed = UpgradeKind::ALL
+        .into_iter()
+        .filter(|kind| keyboard_input.just_pressed(kind.key()));
+
+    for kind in clicked.chain(pressed) {
+        let cost = stats.cost(kind);
+        if scoreboard.score < cost {
+            continue;
+        }
+
+        scoreboard.score -= cost;
+        stats.apply(kind);
+
+        // The extra max HP is granted straight away
+        if kind == UpgradeKind::MaxHp {
+            for mut hp in &mut player_query {
+                let gained = stats.max_hp - hp.max;
+                hp.max = stats.max_hp;
+                hp.current += gained;
+            }
+        }
+    }
+}
+
+// Keeps the players' shields in line with what has been bought, including in a restored run
+fn grant_player_shields(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    mut player_query: Query<(Entity, Option<&mut Shield>), With<Player>>,
+)
+{
+    if stats.max_shield <= 0 {
+        return;
+    }
+    for (player, shield) in &mut player_query {
+        match shield {
+            Some(mut shield) if shield.max != stats.max_shield => shield.raise_max(stats.max_shield),
+            Some(_) => {}
+            None => {
+                commands.entity(player).insert(Shield::new(stats.max_shield, PLAYER_SHIELD_REGEN_DELAY));
+            }
+        }
+    }
+}
+
+fn update_upgrade_cards(
+    scoreboard: Res<Scoreboard>,
+    stats: Res<PlayerStats>,
+    locale: Res<Locale>,
+    mut card_query: Query<(&Interaction, &UpgradeCard, &mut UiColor)>,
+    mut text_query: Query<(&mut Text, Option<&UpgradeCardText>), Or<(With<UpgradeCardText>, With<UpgradePointsText>)>>,
+)
+{
+    for (interaction, card, mut color) in &mut card_query {
+        *color = if scoreboard.score < stats.cost(card.0) {
+            BACKGROUND_COLOR.into()
+        } else if *interaction == Interaction::None {
+            CARD_COLOR.into()
+        } else {
+            CARD_HOVERED_COLOR.into()
+        };
+    }
+
+    for (mut text, card_text) in &mut text_query {
+        text.sections[0].value = match card_text {
+            Some(UpgradeCardText(kind)) => {
+                let mut upgraded = stats.clone();
+                upgraded.apply(*kind);
+                locale.format(
+                    "upgrade.card",
+                    &[
+                        ("level", stats.level(*kind).to_string()),
+                        ("value", stats.value(*kind).to_string()),
+                        ("upgraded", upgraded.value(*kind).to_string()),
+                        ("cost", stats.cost(*kind).to_string()),
+                    ],
+                )
+            }
+            None => locale.format("upgrade.points", &[("points", scoreboard.score.to_string())]),
+        };
+    }
+}
@@ -0,0 +1,251 @@
+//! Successive enemy waves that get larger, tougher and faster as the run goes on,
+//! following the composition in `assets/data/waves.ron` and the growth in `assets/data/difficulty.ron`.
+//! Each wave comes with an objective: usually clearing it out, but every few waves a boss shows up instead,
+//! spawners have to be destroyed, the payload escorted or the players must hold out against a steady stream.
+//! A handcrafted level is a single wave of its own instead, clearing it leads on to the next level.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::Arena,
+    boss::spawn_boss,
+    cli::Options,
+    data::{DifficultyCurve, EnemyTable, LevelDefinition, LevelTable, WaveTable},
+    difficulty::Difficulty,
+    enemy::{spawn_enemy, Enemy, EnemySpawn, SpawnTelegraph},
+    leaderboard::RunClock,
+    levels::{LevelClearedEvent, LoadingLevel, SelectedLevel},
+    objectives::{evaluate_objective, CurrentObjective, Objective},
+    payload::spawn_payload,
+    player::Player,
+    rng::GameRng,
+    spawners::{spawn_spawner, spawn_spawners},
+    survival::GameMode,
+    tutorial::Tutorial,
+    toast::Toast,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaveSpawner::default())
+            .add_event::<WaveClearedEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_waves))
+            .add_system_set(SystemSet::new().with_run_criteria(LoadingLevel).with_system(reset_waves))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedUpdate)
+                    .with_system(spawn_waves.after(evaluate_objective)),
+            );
+    }
+}
+
+// Seconds between two enemies trickling in during a timed objective, divided by the `DifficultyCurve` spawn rate
+const TRICKLE_INTERVAL: f32 = 2.0;
+// Trickling pauses while this many enemies are alive
+const MAX_TRICKLE_ENEMIES: usize = 25;
+
+pub struct WaveSpawner {
+    // The wave currently being fought, 0 before the first one has spawned
+    pub wave: u32,
+    // Whether the current wave's objective is still being played for
+    in_progress: bool,
+    delay: Timer,
+    trickle: Timer,
+    // The selected level, until its wave has spawned
+    level: Option<LevelDefinition>,
+    // Whether a level is being played, rather than endless waves
+    in_level: bool,
+}
+
+// Sent once the last enemy of a wave is gone, carrying the wave number
+pub struct WaveClearedEvent(pub u32);
+
+impl Default for WaveSpawner {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            in_progress: false,
+            // Set from `WaveTable` before it is first used
+            delay: Timer::from_seconds(0.0, false),
+            trickle: Timer::from_seconds(TRICKLE_INTERVAL, true),
+            level: None,
+            in_level: false,
+        }
+    }
+}
+
+impl WaveSpawner {
+    pub fn in_level(&self) -> bool {
+        self.in_level
+    }
+
+    fn enemy_count(&self, waves: &WaveTable) -> u32 {
+        waves.first_wave_enemy_count + (self.wave - 1) * waves.extra_enemies_per_wave
+    }
+
+    fn is_boss_wave(&self, waves: &WaveTable) -> bool {
+        waves.boss_interval > 0 && self.wave % waves.boss_interval == 0
+    }
+
+    // Boss waves are cleared like any other, the rest take precedence in this order
+    fn objective(&self, waves: &WaveTable) -> Objective {
+        let every = |interval: u32| interval > 0 && self.wave % interval == 0;
+        if self.is_boss_wave(waves) {
+            Objective::ClearAllEnemies
+        } else if every(waves.spawner_interval) {
+            Objective::DestroySpawners
+        } else if every(waves.escort_interval) {
+            Objective::EscortPayload
+        } else if every(waves.survive_interval) {
+            Objective::SurviveTimer(waves.survive_time)
+        } else {
+            Objective::ClearAllEnemies
+        }
+    }
+}
+
+pub fn reset_waves(
+    options: Res<Options>,
+    selected: Res<SelectedLevel>,
+    levels: Option<Res<LevelTable>>,
+    mut spawner: ResMut<WaveSpawner>,
+)
+{
+    *spawner = WaveSpawner::default();
+    spawner.level = selected.get(levels.as_deref()).cloned();
+    spawner.in_level = spawner.level.is_some();
+    // The next wave spawned is `wave + 1`, a level counts as the wave it is set at
+    let first_wave = spawner.level.as_ref().map_or(options.start_wave.unwrap_or(1), |level| level.wave.max(1));
+    spawner.wave = first_wave - 1;
+}
+
+fn spawn_waves(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    asset_server: Res<AssetServer>,
+    arena: Res<Arena>,
+    clock: Res<RunClock>,
+    preset: Res<Difficulty>,
+    tutorial: Res<Tutorial>,
+    // The data files as one parameter, keeping the system within Bevy's limit of sixteen
+    data: (Option<Res<EnemyTable>>, Option<Res<WaveTable>>, Option<Res<DifficultyCurve>>),
+    mut rng: ResMut<GameRng>,
+    mut spawner: ResMut<WaveSpawner>,
+    mut objective: ResMut<CurrentObjective>,
+    // Enemies still being telegraphed count as alive
+    enemy_query: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut wave_cleared_events: EventWriter<WaveClearedEvent>,
+    mut level_cleared_events: EventWriter<LevelClearedEvent>,
+    mut toasts: EventWriter<Toast>,
+)
+{
+    // Survival has its own spawner, and nothing spawns during the tutorial
+    if *mode != GameMode::Waves || tutorial.active() {
+        return;
+    }
+
+    // Nothing to spawn until the data files have loaded
+    let (enemies, waves, curve) = match data {
+        (Some(enemies), Some(waves), Some(curve)) => (enemies, waves, curve),
+        _ => return,
+    };
+
+    let players: Vec<Vec2> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+
+    if spawner.in_progress {
+        if !objective.complete {
+            // Timed objectives keep enemies coming for as long as they last
+            let timed = matches!(objective.objective, Some(Objective::SurviveTimer(_) | Objective::EscortPayload));
+            let interval = TRICKLE_INTERVAL / curve.at(spawner.wave, clock.0).spawn_rate;
+            spawner.trickle.set_duration(Duration::from_secs_f32(interval));
+            if timed
+                && spawner.trickle.tick(Duration::from_secs_f32(TIME_STEP)).just_finished()
+                && enemy_query.iter().count() < MAX_TRICKLE_ENEMIES
+            {
+                let difficulty = preset.apply(curve.at(spawner.wave, clock.0));
+                let kind = waves.random_kind(&mut rng.0, spawner.wave);
+                spawn_enemy(&mut commands, &mut rng.0, &arena, &players, EnemySpawn::new(&enemies, kind, difficulty));
+            }
+            return;
+        }
+        spawner.in_progress = false;
+        if spawner.in_level {
+            level_cleared_events.send(LevelClearedEvent);
+        } else {
+            wave_cleared_events.send(WaveClearedEvent(spawner.wave));
+        }
+    }
+
+    // Once a level's wave is over, nothing more spawns until the next level is loaded
+    if spawner.in_level && spawner.level.is_none() {
+        return;
+    }
+
+    // The first wave arrives straight away, later ones after a short delay
+    let delay = waves.delay / curve.at(spawner.wave, clock.0).spawn_rate;
+    spawner.delay.set_duration(Duration::from_secs_f32(delay));
+    if spawner.wave > 0 && !spawner.delay.tick(Duration::from_secs_f32(TIME_STEP)).finished() {
+        return;
+    }
+
+    spawner.wave += 1;
+    spawner.in_progress = true;
+    spawner.delay.reset();
+
+    let difficulty = preset.apply(curve.at(spawner.wave, clock.0));
+    spawner.trickle.reset();
+
+    // A level is set up as it describes
+    if let Some(level) = spawner.level.take() {
+        objective.start(level.objective);
+        let mut toast = Toast::new(level.name.clone());
+        if let Some(body) = level.objective.toast_key() {
+            toast = toast.with_body(body);
+        }
+        toasts.send(toast);
+        for position in &level.spawners {
+            spawn_spawner(&mut commands, *position, &waves, spawner.wave, difficulty);
+        }
+        if level.objective == Objective::EscortPayload {
+            spawn_payload(&mut commands, &arena);
+        }
+        for _ in 0..level.enemies {
+            let kind = waves.random_kind(&mut rng.0, spawner.wave);
+            spawn_enemy(&mut commands, &mut rng.0, &arena, &players, EnemySpawn::new(&enemies, kind, difficulty));
+        }
+        return;
+    }
+
+    let wave_objective = spawner.objective(&waves);
+    objective.start(wave_objective);
+    if spawner.is_boss_wave(&waves) {
+        toasts.send(Toast::new("toast.wave").with_arg("wave", spawner.wave).with_body("toast.boss"));
+        let health = (waves.boss_health * difficulty.health) as i32;
+        spawn_boss(&mut commands, &asset_server, &arena, health);
+        return;
+    }
+
+    let mut toast = Toast::new("toast.wave").with_arg("wave", spawner.wave);
+    if let Some(body) = wave_objective.toast_key() {
+        toast = toast.with_body(body);
+    }
+    toasts.send(toast);
+    match wave_objective {
+        Objective::DestroySpawners => {
+            spawn_spawners(&mut commands, &mut rng.0, &arena, &players, &waves, spawner.wave, difficulty);
+            return;
+        }
+        Objective::EscortPayload => spawn_payload(&mut commands, &arena),
+        Objective::SurviveTimer(_) | Objective::ClearAllEnemies => {}
+    }
+    for _ in 0..preset.scale_count(spawner.enemy_count(&waves)) {
+        let kind = waves.random_kind(&mut rng.0, spawner.wave);
+        spawn_enemy(&mut commands, &mut rng.0, &arena, &players, EnemySpawn::new(&enemies, kind, difficulty));
+    }
+}
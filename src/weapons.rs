@@ -0,0 +1,215 @@
+//! The weapons a player switches between with the number keys, all sharing the `AttackCooldown` and the attack button.
+//! The pulse swings in `combat`; the railgun fires a beam piercing everything up to the first obstacle,
+//! and the mine layer drops the player's `deployables` mines.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    cleanup::DespawnOnStateExit,
+    combat::{apply_damage, trigger_attack, AttackCooldown, DamageEvent, DamageKind, ATTACK_COOLDOWN},
+    deployables::{spawn_mine, Deployables, MINE_COLOR},
+    enemy::Enemy,
+    lock_on::LockedTarget,
+    physics::{integrate_forces, Forces},
+    player::{Aim, Player},
+    spatial::SpatialHash,
+    spawners::Spawner,
+    upgrades::PlayerStats,
+    FixedUpdate, GameState, TIME_STEP,
+};
+
+const RAILGUN_COOLDOWN: f32 = 1.2;
+const RAILGUN_RANGE: f32 = 700.0;
+// The beam is traced in steps this long, stopping at the first one that hits an obstacle or leaves the arena
+const RAILGUN_STEP: f32 = 10.0;
+const RAILGUN_WIDTH: f32 = 8.0;
+// Multiplies the weapon damage, making up for the long cooldown
+const RAILGUN_DAMAGE_SCALE: f32 = 2.0;
+const RAILGUN_KNOCKBACK: f32 = 350.0;
+const RAILGUN_BEAM_DURATION: f32 = 0.15;
+const RAILGUN_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.9);
+const MINE_COOLDOWN: f32 = 0.8;
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::new()
+                .with_run_criteria(FixedUpdate)
+                .with_system(switch_weapons.before(trigger_attack))
+                .with_system(fire_railgun.after(trigger_attack).before(apply_damage).before(integrate_forces))
+                .with_system(fade_railgun_beams)
+                .with_system(lay_mines.after(trigger_attack)),
+        );
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Weapon {
+    #[default]
+    Pulse,
+    Railgun,
+    MineLayer,
+}
+
+impl Weapon {
+    pub const ALL: [Weapon; 3] = [Weapon::Pulse, Weapon::Railgun, Weapon::MineLayer];
+
+    fn action(self) -> Action {
+        match self {
+            Weapon::Pulse => Action::SelectPulse,
+            Weapon::Railgun => Action::SelectRailgun,
+            Weapon::MineLayer => Action::SelectMineLayer,
+        }
+    }
+
+    // Seconds before the next attack once this one has fired
+    pub fn cooldown(self) -> f32 {
+        match self {
+            Weapon::Pulse => ATTACK_COOLDOWN,
+            Weapon::Railgun => RAILGUN_COOLDOWN,
+            Weapon::MineLayer => MINE_COOLDOWN,
+        }
+    }
+
+    // Of its HUD icon
+    pub fn color(self) -> Color {
+        match self {
+            Weapon::Pulse => Color::WHITE,
+            Weapon::Railgun => RAILGUN_COLOR,
+            Weapon::MineLayer => MINE_COLOR,
+        }
+    }
+}
+
+#[derive(Component)]
+struct RailgunBeam(Timer);
+
+fn switch_weapons(mut query: Query<(&ActionState, &mut Weapon), With<Player>>)
+{
+    for (actions, mut weapon) in &mut query {
+        if let Some(selected) = Weapon::ALL.into_iter().find(|selected| actions.just_pressed(selected.action())) {
+            *weapon = selected;
+        }
+    }
+}
+
+fn fire_railgun(
+    mut commands: Commands,
+    mut damage_events: EventWriter<DamageEvent>,
+    stats: Res<PlayerStats>,
+    arena: Res<Arena>,
+    locked: Res<LockedTarget>,
+    spatial_hash: Res<SpatialHash>,
+    mut player_query: Query<(Entity, &ActionState, &Aim, &Transform, &mut AttackCooldown, &Weapon), With<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Forces), (With<Enemy>, Without<Player>)>,
+    spawner_query: Query<(Entity, &Transform), (With<Spawner>, Without<Player>)>,
+)
+{
+    for (player, actions, aim, transform, mut cooldown, weapon) in &mut player_query {
+        if *weapon != Weapon::Railgun || !cooldown.0.finished() || !actions.just_pressed(Action::Attack) {
+            continue;
+        }
+        cooldown.start(weapon.cooldown());
+
+        let origin = transform.translation.truncate();
+        // Fired at the locked target while it is in range
+        let aim = locked.direction_from(origin, RAILGUN_RANGE).unwrap_or(aim.0);
+        let mut end = origin;
+        let steps = (RAILGUN_RANGE / RAILGUN_STEP) as usize;
+        for step in 1..=steps {
+            let next = origin + aim * step as f32 * RAILGUN_STEP;
+            let inside = next.x > arena.left() && next.x < arena.right() && next.y > arena.bottom() && next.y < arena.top();
+            if !inside || !arena.line_of_sight(origin, next) {
+                break;
+            }
+            end = next;
+        }
+
+        commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Line(origin, end),
+                DrawMode::Stroke(StrokeMode::new(RAILGUN_COLOR, RAILGUN_WIDTH)),
+                Transform::from_xyz(0.0, 0.0, 1.0),
+            ))
+            .insert(RailgunBeam(Timer::from_seconds(RAILGUN_BEAM_DURATION, false)))
+            .insert(DespawnOnStateExit(GameState::Playing));
+
+        // Pierces every enemy along the beam
+        let half_size = (end - origin).abs() / 2.0 + Vec2::splat(RAILGUN_WIDTH);
+        for entity in spatial_hash.query_aabb((origin + end) / 2.0, half_size) {
+            let (enemy_transform, mut forces) = match enemy_query.get_mut(entity) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+            let position = enemy_transform.translation.truncate();
+            let along = (position - origin).dot(aim).clamp(0.0, origin.distance(end));
+            let distance = position.distance(origin + aim * along);
+            if distance > enemy_transform.scale.x / 2.0 + RAILGUN_WIDTH / 2.0 {
+                continue;
+            }
+
+            damage_events.send(DamageEvent {
+                target: entity,
+                amount: (stats.weapon_damage * RAILGUN_DAMAGE_SCALE) as i32,
+                source: Some(player),
+                kind: DamageKind::Melee,
+            });
+            forces.add_impulse(aim * RAILGUN_KNOCKBACK);
+        }
+
+        // And any spawner in its way, which the spatial hash doesn't know about
+        for (entity, spawner_transform) in &spawner_query {
+            let position = spawner_transform.translation.truncate();
+            let along = (position - origin).dot(aim).clamp(0.0, origin.distance(end));
+            if position.distance(origin + aim * along) > spawner_transform.scale.x / 2.0 + RAILGUN_WIDTH / 2.0 {
+                continue;
+            }
+            damage_events.send(DamageEvent {
+                target: entity,
+                amount: (stats.weapon_damage * RAILGUN_DAMAGE_SCALE) as i32,
+                source: Some(player),
+                kind: DamageKind::Melee,
+            });
+        }
+    }
+}
+
+fn fade_railgun_beams(mut commands: Commands, mut query: Query<(Entity, &mut RailgunBeam, &mut DrawMode)>)
+{
+    for (entity, mut beam, mut draw_mode) in &mut query {
+        beam.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if beam.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let DrawMode::Stroke(ref mut stroke) = *draw_mode {
+            stroke.color.set_a(RAILGUN_COLOR.a() * beam.0.percent_left());
+        }
+    }
+}
+
+fn lay_mines(
+    mut commands: Commands,
+    mut player_query: Query<(&ActionState, &Transform, &mut AttackCooldown, &mut Deployables, &Weapon), With<Player>>,
+)
+{
+    for (actions, transform, mut cooldown, mut deployables, weapon) in &mut player_query {
+        if *weapon != Weapon::MineLayer || !cooldown.0.finished() || !actions.just_pressed(Action::Attack) {
+            continue;
+        }
+        // Out of mines until one recharges
+        if !deployables.take() {
+            continue;
+        }
+        cooldown.start(weapon.cooldown());
+        spawn_mine(&mut commands, transform.translation.truncate());
+    }
+}
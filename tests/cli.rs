@@ -0,0 +1,32 @@
+//! Parses command line options the way `Options::from_args` does.
+
+use bevy::prelude::*;
+use magnet_game::cli::Options;
+
+fn parse(args: &[&str]) -> Result<Options, String>
+{
+    Options::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn parses_sizes()
+{
+    let options = parse(&["--window", "1350x900", "--arena", "1200x800"]).unwrap();
+    assert_eq!(options.window_size, Some(Vec2::new(1350.0, 900.0)));
+    assert_eq!(options.arena_size, Some(Vec2::new(1200.0, 800.0)));
+}
+
+#[test]
+fn rejects_sizes_that_are_not_positive()
+{
+    assert!(parse(&["--window", "0x720"]).is_err());
+    assert!(parse(&["--arena", "1200x-800"]).is_err());
+}
+
+#[test]
+fn rejects_sizes_that_are_not_finite()
+{
+    assert!(parse(&["--arena", "NaNx600"]).is_err());
+    assert!(parse(&["--arena", "1200xinf"]).is_err());
+    assert!(parse(&["--window", "infx720"]).is_err());
+}
@@ -0,0 +1,165 @@
+//! Runs the game headless and checks on the run as it is stepped.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::event::Events, prelude::*};
+use magnet_game::{
+    ai::{AiState, Telegraph},
+    bullet_time::BulletTime,
+    cli::Options,
+    combat::{DamageEvent, DamageKind, Hp},
+    data::{DifficultyCurve, EnemyTable, WaveTable},
+    enemy::Enemy,
+    headless_app,
+    player::Player,
+    Scoreboard,
+};
+
+// The data files load in the background, at their own pace rather than the app's
+const DATA_TIMEOUT: Duration = Duration::from_secs(10);
+// Enough steps for the first wave's spawn telegraphs to run out
+const FIRST_WAVE_TICKS: usize = 120;
+// Enough steps for a hit, or a death, to be resolved, with room for the hit-stop it brings
+const RESOLVE_TICKS: usize = 30;
+// More than anything in the first wave has
+const LETHAL_DAMAGE: i32 = 10_000;
+
+fn seeded_app() -> App
+{
+    headless_app(Options { seed: Some(1), ..default() })
+}
+
+fn wait_for_data(app: &mut App)
+{
+    let start = Instant::now();
+    while !(app.world.contains_resource::<EnemyTable>()
+        && app.world.contains_resource::<WaveTable>()
+        && app.world.contains_resource::<DifficultyCurve>())
+    {
+        assert!(start.elapsed() < DATA_TIMEOUT, "data files did not load");
+        app.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn step(app: &mut App, ticks: usize)
+{
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+fn first_player(app: &mut App) -> Entity
+{
+    let mut query = app.world.query::<(Entity, &Player)>();
+    query
+        .iter(&app.world)
+        .find(|(_, player)| player.0 == 0)
+        .map(|(entity, _)| entity)
+        .expect("no first player")
+}
+
+// Waits out the first wave's telegraphs, and hands back one of its enemies
+fn first_enemy(app: &mut App) -> Entity
+{
+    wait_for_data(app);
+    step(app, FIRST_WAVE_TICKS);
+
+    let mut query = app.world.query_filtered::<Entity, With<Enemy>>();
+    query.iter(&app.world).next().expect("the first wave did not spawn")
+}
+
+#[test]
+fn players_start_at_full_health()
+{
+    let mut app = seeded_app();
+    app.update();
+
+    let mut query = app.world.query_filtered::<&Hp, With<Player>>();
+    assert!(query.iter(&app.world).count() > 0);
+    for hp in query.iter(&app.world) {
+        assert_eq!(hp.current, hp.max);
+    }
+    assert_eq!(app.world.resource::<Scoreboard>().score, 0);
+}
+
+#[test]
+fn first_wave_spawns()
+{
+    let mut app = seeded_app();
+    wait_for_data(&mut app);
+    step(&mut app, FIRST_WAVE_TICKS);
+
+    let mut query = app.world.query_filtered::<(), With<Enemy>>();
+    assert!(query.iter(&app.world).count() > 0);
+}
+
+#[test]
+fn player_moves_with_input()
+{
+    let mut app = seeded_app();
+    app.update();
+    let player = first_player(&mut app);
+    let start = app.world.get::<Transform>(player).unwrap().translation;
+
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Right);
+    step(&mut app, RESOLVE_TICKS);
+
+    let end = app.world.get::<Transform>(player).unwrap().translation;
+    assert!(end.x > start.x, "moved from {} to {}", start, end);
+}
+
+#[test]
+fn enemy_contact_hurts_player()
+{
+    let mut app = seeded_app();
+    let enemy = first_enemy(&mut app);
+    let player = first_player(&mut app);
+    let hp_before = app.world.get::<Hp>(player).unwrap().current;
+
+    // Right on top of the player, with its attack already wound up
+    let position = app.world.get::<Transform>(player).unwrap().translation;
+    app.world.get_mut::<Transform>(enemy).unwrap().translation = position;
+    app.world.entity_mut(enemy).insert(AiState::Attack).remove::<Telegraph>();
+    step(&mut app, RESOLVE_TICKS);
+
+    let hp_after = app.world.get::<Hp>(player).unwrap().current;
+    assert!(hp_after < hp_before, "hp went from {} to {}", hp_before, hp_after);
+}
+
+#[test]
+fn killing_an_enemy_scores()
+{
+    let mut app = seeded_app();
+    let enemy = first_enemy(&mut app);
+    let score_before = app.world.resource::<Scoreboard>().score;
+
+    app.world.resource_mut::<Events<DamageEvent>>().send(DamageEvent {
+        target: enemy,
+        amount: LETHAL_DAMAGE,
+        source: None,
+        kind: DamageKind::Hazard,
+    });
+    step(&mut app, RESOLVE_TICKS);
+
+    assert!(app.world.get_entity(enemy).is_none());
+    assert!(app.world.resource::<Scoreboard>().score > score_before);
+}
+
+#[test]
+fn bullet_time_triggers_with_pull_just_before_push()
+{
+    let mut app = seeded_app();
+    app.update();
+
+    // Pull goes down a step ahead, long enough for the magnet to start draining the charge
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Q);
+    app.update();
+    app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::E);
+    app.update();
+
+    assert!(app.world.resource::<BulletTime>().active());
+}